@@ -39,3 +39,26 @@ fn update_instances_1000x1000_grid_10times(b: &mut Bencher) {
         }
     })
 }
+
+// Unlike the benchmarks above, these don't need a window or OpenGL context
+#[bench]
+fn software_render_50x50_grid_10times(b: &mut Bencher) {
+    let grid = Grid::new(50, 50);
+    let renderer = software::SoftwareRenderer::new(50, 50, 1024, 768);
+    b.iter(|| {
+        for _ in 0..10 {
+            renderer.render_grid(&grid);
+        }
+    })
+}
+
+#[bench]
+fn software_render_500x500_grid_10times(b: &mut Bencher) {
+    let grid = Grid::new(500, 500);
+    let renderer = software::SoftwareRenderer::new(500, 500, 1024, 768);
+    b.iter(|| {
+        for _ in 0..10 {
+            renderer.render_grid(&grid);
+        }
+    })
+}
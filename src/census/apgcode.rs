@@ -0,0 +1,251 @@
+use super::pattern::Pattern;
+use crate::data::{PeriodKind, Transform};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// Each column of a 5 row strip is a character, with the top cell as the lowest bit
+const COLUMN_CHARS: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+// Runs of 4 or more empty columns are y followed by one of these, for 4 up to 39
+const RUN_CHARS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const STRIP_HEIGHT: usize = 5;
+
+/// The first part of an apgcode, which says what sort of object it is
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Prefix {
+    /// `xs`, followed by the number of live cells
+    StillLife { population: usize },
+    /// `xp`, followed by the period
+    Oscillator { period: u64 },
+    /// `xq`, followed by the period
+    Spaceship { period: u64 },
+}
+
+impl Prefix {
+    /// The prefix for an object that behaves in the given way, if it has one
+    pub const fn new(kind: PeriodKind, period: u64, population: usize) -> Option<Self> {
+        match kind {
+            PeriodKind::StillLife => Some(Self::StillLife { population }),
+            PeriodKind::Oscillator => Some(Self::Oscillator { period }),
+            PeriodKind::Spaceship => Some(Self::Spaceship { period }),
+            PeriodKind::Extinct => None,
+        }
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StillLife { population } => write!(f, "xs{population}"),
+            Self::Oscillator { period } => write!(f, "xp{period}"),
+            Self::Spaceship { period } => write!(f, "xq{period}"),
+        }
+    }
+}
+
+/// An object's identifier in the format used by apgsearch and Catagolue, like `xs4_33`
+/// for a block, `xp2_7` for a blinker or `xq4_153` for a glider.
+///
+/// The cells are one phase of the object, in the format Catagolue calls extended
+/// Wechsler format. For codes made by `classify` that's the phase and orientation giving
+/// the shortest code, then the first in alphabetical order, so that they match the ones
+/// in published censuses.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Apgcode {
+    pub prefix: Prefix,
+    pub cells: Pattern,
+}
+
+impl Apgcode {
+    /// The code for an object from each of its phases, picking whichever phase and
+    /// orientation gives the canonical code
+    pub fn canonical<'a, I>(prefix: Prefix, phases: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Pattern>,
+    {
+        let (_, cells) = phases
+            .into_iter()
+            .flat_map(|phase| Transform::ALL.map(|transform| phase.transform(transform)))
+            .map(|cells| (wechsler(&cells), cells))
+            .min_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+            .unwrap_or_default();
+        Self { prefix, cells }
+    }
+}
+
+/// Encodes a pattern, as it is, in extended Wechsler format: 5 row strips from the top
+/// separated by `z`, each a character per column with trailing empty columns left out,
+/// and with runs of empty columns shortened to `w`, `x`, or `y` and a count
+pub fn wechsler(pattern: &Pattern) -> String {
+    let strips = pattern.height().div_ceil(STRIP_HEIGHT);
+    let mut columns = vec![vec![0_u8; pattern.width()]; strips];
+    for &(i, j) in pattern.cells() {
+        columns[i / STRIP_HEIGHT][j] |= 1 << (i % STRIP_HEIGHT);
+    }
+    let mut code = String::new();
+    for (strip, columns) in columns.iter().enumerate() {
+        if strip > 0 {
+            code.push('z');
+        }
+        let mut empty = 0;
+        for &column in columns {
+            if column == 0 {
+                empty += 1;
+                continue;
+            }
+            while empty > 0 {
+                let run = empty.min(RUN_CHARS.len() + 3);
+                match run {
+                    1 => code.push('0'),
+                    2 => code.push('w'),
+                    3 => code.push('x'),
+                    _ => {
+                        code.push('y');
+                        code.push(char::from(RUN_CHARS[run - 4]));
+                    }
+                }
+                empty -= run;
+            }
+            code.push(char::from(COLUMN_CHARS[usize::from(column)]));
+        }
+    }
+    code
+}
+
+fn parse_wechsler(code: &str) -> Option<Pattern> {
+    let mut cells = Vec::new();
+    let (mut strip, mut j) = (0, 0);
+    let mut chars = code.bytes();
+    while let Some(c) = chars.next() {
+        match c {
+            b'w' => j += 2,
+            b'x' => j += 3,
+            b'y' => {
+                let run = chars.next()?;
+                j += 4 + RUN_CHARS.iter().position(|&r| r == run)?;
+            }
+            b'z' => (strip, j) = (strip + 1, 0),
+            _ => {
+                let column = COLUMN_CHARS.iter().position(|&r| r == c)?;
+                for bit in 0..STRIP_HEIGHT {
+                    if column & (1 << bit) != 0 {
+                        cells.push(super::pattern::signed((strip * STRIP_HEIGHT + bit, j)));
+                    }
+                }
+                j += 1;
+            }
+        }
+    }
+    Some(Pattern::new(cells))
+}
+
+impl fmt::Display for Apgcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}", self.prefix, wechsler(&self.cells))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseApgcodeError(String);
+
+impl fmt::Display for ParseApgcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid apgcode [{}], expected xs, xp or xq and a number, then _ and the cells",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseApgcodeError {}
+
+impl FromStr for Apgcode {
+    type Err = ParseApgcodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseApgcodeError(s.to_string());
+        let (prefix, cells) = s.trim().split_once('_').ok_or_else(error)?;
+        let number = prefix.get(2..).ok_or_else(error)?;
+        let cells = parse_wechsler(cells)
+            .filter(|cells| !cells.is_empty())
+            .ok_or_else(error)?;
+        let prefix = match prefix.get(..2) {
+            Some("xs") => Prefix::StillLife {
+                population: number.parse().map_err(|_| error())?,
+            },
+            Some("xp") => Prefix::Oscillator {
+                period: number.parse().map_err(|_| error())?,
+            },
+            Some("xq") => Prefix::Spaceship {
+                period: number.parse().map_err(|_| error())?,
+            },
+            _ => return Err(error()),
+        };
+        if let Prefix::StillLife { population } = prefix {
+            if population != cells.population() {
+                return Err(error());
+            }
+        }
+        Ok(Self { prefix, cells })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::census::classify;
+    use crate::data::Rule;
+
+    fn apgcode(cells: &str) -> String {
+        let pattern: Pattern = cells.parse().unwrap();
+        classify(&pattern, Rule::default())
+            .unwrap()
+            .apgcode
+            .to_string()
+    }
+
+    #[test]
+    fn test_known_apgcodes() {
+        assert_eq!(apgcode("oo/oo"), "xs4_33");
+        assert_eq!(apgcode(".oo./o..o/.oo."), "xs6_696");
+        assert_eq!(apgcode("oo./o.o/.o."), "xs5_253");
+        assert_eq!(apgcode("ooo"), "xp2_7");
+        assert_eq!(apgcode(".ooo/ooo."), "xp2_7e");
+        assert_eq!(apgcode(".o./..o/ooo"), "xq4_153");
+        assert_eq!(apgcode("o.o/.oo/.o."), "xq4_153");
+        assert_eq!(apgcode(".o..o/o..../o...o/oooo."), "xq4_6frc");
+        assert_eq!(apgcode("..o....o../oo.oooo.oo/..o....o.."), "xp15_4r4z4r4");
+    }
+
+    #[test]
+    fn test_wechsler() {
+        // Taller than a strip, and with long runs of empty columns
+        let pattern = Pattern::new([(0, 0), (0, 5), (6, 46)]);
+        assert_eq!(wechsler(&pattern), "1y01zyzy32");
+        assert_eq!(parse_wechsler("1y01zyzy32"), Some(pattern));
+    }
+
+    #[test]
+    fn test_parse() {
+        let glider: Apgcode = "xq4_153".parse().unwrap();
+        assert_eq!(glider.prefix, Prefix::Spaceship { period: 4 });
+        assert_eq!(glider.cells, "ooo/..o/.o.".parse().unwrap());
+        assert_eq!(glider.to_string(), "xq4_153");
+        let pulsar: Apgcode = "xp3_co9nas0san9oczgoldlo0oldlogz1047210127401"
+            .parse()
+            .unwrap();
+        // The phase with the shortest code isn't the usual one with 48 cells
+        assert_eq!(pulsar.cells.population(), 72);
+        assert_eq!(
+            apgcode(&pulsar.cells.to_string()),
+            "xp3_co9nas0san9oczgoldlo0oldlogz1047210127401"
+        );
+
+        assert!("xs5_33".parse::<Apgcode>().is_err());
+        assert!("xs4_".parse::<Apgcode>().is_err());
+        assert!("yl144_33".parse::<Apgcode>().is_err());
+        assert!("xp2_7!".parse::<Apgcode>().is_err());
+        assert!("xp2_y".parse::<Apgcode>().is_err());
+    }
+}
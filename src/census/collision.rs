@@ -0,0 +1,321 @@
+use super::lifespan::{self, Lifespan};
+use super::pattern::{signed, Pattern};
+use super::{classify, names, Census, INTERACTION_RANGE};
+use crate::data::Rule;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+pub const DEFAULT_OFFSETS: RangeInclusive<isize> = -8..=8;
+pub const DEFAULT_MAX_GENERATIONS: u64 = 10_000;
+
+// Furthest a moving pattern gets backed up to start clear of the other one
+const MAX_BACK_UP: isize = 1000;
+
+/// Where one pattern starts relative to the other in a collision
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Placement {
+    /// Rows and columns from the target's top left corner to the bullet's. For a
+    /// moving bullet this is where it would be if it had moved there freely, rather
+    /// than where it starts, since it starts far enough back to be clear of the target.
+    pub offset: (isize, isize),
+    /// Generations each pattern has been run on its own for before they're placed
+    pub target_phase: u64,
+    pub bullet_phase: u64,
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (rows, columns) = self.offset;
+        write!(
+            f,
+            "offset {rows},{columns} phases {},{}",
+            self.target_phase, self.bullet_phase
+        )
+    }
+}
+
+/// One of the different ways a collision can turn out
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Outcome {
+    /// The first placement in the sweep that turned out this way
+    pub first: Placement,
+    /// Both patterns in the first placement, ready to run
+    pub start: Pattern,
+    /// Number of placements that turned out this way
+    pub count: usize,
+    /// How the first placement played out
+    pub lifespan: Lifespan,
+    /// What was left, e.g. `1 block, 1 blinker, escaped 2 glider`, or `nothing`
+    pub description: String,
+}
+
+/// Crashes a bullet, like a glider, into a target, like a block, from every offset in a
+/// range and in every combination of their phases, and groups the results by how they
+/// turned out.
+///
+/// Each collision runs until it settles, as with `lifespan::measure`. Placements where
+/// the patterns start too close together are skipped, and so are ones where what's left
+/// is the target and the bullet carrying on as if nothing had happened.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Sweep {
+    target: Pattern,
+    bullet: Pattern,
+    rule: Rule,
+    offsets: RangeInclusive<isize>,
+    max_generations: u64,
+}
+
+impl Sweep {
+    pub const fn new(target: Pattern, bullet: Pattern) -> Self {
+        Self {
+            target,
+            bullet,
+            rule: Rule::CONWAY,
+            offsets: DEFAULT_OFFSETS,
+            max_generations: DEFAULT_MAX_GENERATIONS,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Rows and columns to try the bullet at, relative to the target
+    #[must_use]
+    pub const fn with_offsets(mut self, offsets: RangeInclusive<isize>) -> Self {
+        self.offsets = offsets;
+        self
+    }
+
+    /// Most generations to wait for each collision to settle
+    #[must_use]
+    pub const fn with_max_generations(mut self, generations: u64) -> Self {
+        self.max_generations = generations;
+        self
+    }
+
+    /// Runs every collision, spread across threads, returning each different outcome
+    /// in the order it was first found
+    pub fn run(&self) -> Vec<Outcome> {
+        let target = Phases::of(&self.target, self.rule);
+        let bullet = Phases::of(&self.bullet, self.rule);
+        let mut placements = Vec::new();
+        for rows in self.offsets.clone() {
+            for columns in self.offsets.clone() {
+                for target_phase in 0..target.patterns.len() as u64 {
+                    for bullet_phase in 0..bullet.patterns.len() as u64 {
+                        placements.push(Placement {
+                            offset: (rows, columns),
+                            target_phase,
+                            bullet_phase,
+                        });
+                    }
+                }
+            }
+        }
+        let results: Vec<Option<(Pattern, Lifespan)>> = placements
+            .par_iter()
+            .map(|placement| {
+                let start = place(&target, &bullet, placement)?;
+                let lifespan = lifespan::measure(&start, self.rule, self.max_generations);
+                Some((start, lifespan))
+            })
+            .collect();
+
+        let miss = outcome_key(target.key.clone(), std::slice::from_ref(&bullet.label));
+        let mut outcomes: Vec<Outcome> = Vec::new();
+        let mut seen: HashMap<(Option<Pattern>, Vec<String>), usize> = HashMap::new();
+        for (placement, (start, lifespan)) in placements
+            .into_iter()
+            .zip(results)
+            .filter_map(|(placement, result)| Some((placement, result?)))
+        {
+            let key = if lifespan.settled.is_some() {
+                let remains = classify(&lifespan.remains, self.rule)
+                    .map_or_else(|| lifespan.remains.canonical(), |c| c.key);
+                outcome_key(Some(remains), &lifespan.escaped)
+            } else {
+                (None, Vec::new())
+            };
+            if key == miss {
+                continue;
+            }
+            if let Some(&index) = seen.get(&key) {
+                outcomes[index].count += 1;
+                continue;
+            }
+            seen.insert(key, outcomes.len());
+            outcomes.push(Outcome {
+                first: placement,
+                description: describe(&lifespan, self.rule),
+                start,
+                count: 1,
+                lifespan,
+            });
+        }
+        outcomes
+    }
+}
+
+// Each phase of a pattern, and how far it moves each period
+struct Phases {
+    patterns: Vec<Pattern>,
+    displacement: (isize, isize),
+    // Smallest canonical phase, for recognising it afterwards
+    key: Option<Pattern>,
+    label: String,
+}
+
+impl Phases {
+    fn of(pattern: &Pattern, rule: Rule) -> Self {
+        let Some(classification) = classify(pattern, rule) else {
+            return Self {
+                patterns: vec![pattern.clone()],
+                displacement: (0, 0),
+                key: Some(pattern.canonical()),
+                label: pattern.to_string(),
+            };
+        };
+        let mut grid = pattern.to_grid(super::MARGIN, rule);
+        let mut patterns = Vec::new();
+        for _ in 0..classification.period {
+            patterns.push(Pattern::from_grid(&grid));
+            grid.advance();
+        }
+        let label = names::name(&classification.key, rule)
+            .map_or_else(|| classification.apgcode.to_string(), str::to_string);
+        Self {
+            patterns,
+            displacement: classification.displacement,
+            key: Some(classification.key),
+            label,
+        }
+    }
+}
+
+// Both patterns together, with the bullet backed up along the way it's going until it's
+// clear of the target, or None if it can't be. Backing up a whole period at a time keeps
+// the timing of the collision the same.
+fn place(target: &Phases, bullet: &Phases, placement: &Placement) -> Option<Pattern> {
+    let target_cells = &target.patterns[usize::try_from(placement.target_phase).ok()?];
+    let bullet_cells = &bullet.patterns[usize::try_from(placement.bullet_phase).ok()?];
+    let size = |pattern: &Pattern| signed((pattern.height(), pattern.width()));
+    let (target_height, target_width) = size(target_cells);
+    let (bullet_height, bullet_width) = size(bullet_cells);
+    let (rows, columns) = placement.offset;
+    let (down, across) = bullet.displacement;
+    let corner = |back: isize| (rows - down * back, columns - across * back);
+    let clear = |back: isize| {
+        let (top, left) = corner(back);
+        top - (target_height - 1) > INTERACTION_RANGE
+            || left - (target_width - 1) > INTERACTION_RANGE
+            || -(top + bullet_height - 1) > INTERACTION_RANGE
+            || -(left + bullet_width - 1) > INTERACTION_RANGE
+    };
+    let furthest = if (down, across) == (0, 0) {
+        0
+    } else {
+        MAX_BACK_UP
+    };
+    let (top, left) = corner((0..=furthest).find(|&back| clear(back))?);
+    let target_cells = target_cells.cells().iter().map(|&cell| signed(cell));
+    let bullet_cells = bullet_cells.cells().iter().map(|&cell| {
+        let (i, j) = signed(cell);
+        (i + top, j + left)
+    });
+    Some(Pattern::new(target_cells.chain(bullet_cells)))
+}
+
+fn outcome_key(remains: Option<Pattern>, escaped: &[String]) -> (Option<Pattern>, Vec<String>) {
+    let mut escaped = escaped.to_vec();
+    escaped.sort();
+    (remains, escaped)
+}
+
+fn describe(lifespan: &Lifespan, rule: Rule) -> String {
+    if lifespan.settled.is_none() {
+        return "didn't settle".to_string();
+    }
+    let mut parts = Vec::new();
+    if !lifespan.remains.is_empty() {
+        let grid = lifespan.remains.to_grid(super::MARGIN, rule);
+        parts.push(Census::of(&grid).to_string());
+    }
+    let mut escaped: BTreeMap<&str, usize> = BTreeMap::new();
+    for label in &lifespan.escaped {
+        *escaped.entry(label).or_default() += 1;
+    }
+    if !escaped.is_empty() {
+        let escaped: Vec<String> = escaped
+            .into_iter()
+            .map(|(label, count)| format!("{count} {label}"))
+            .collect();
+        parts.push(format!("escaped {}", escaped.join(", ")));
+    }
+    if parts.is_empty() {
+        "nothing".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sweep(target: &str, bullet: &str, offsets: RangeInclusive<isize>) -> Vec<Outcome> {
+        Sweep::new(target.parse().unwrap(), bullet.parse().unwrap())
+            .with_offsets(offsets)
+            .with_max_generations(1000)
+            .run()
+    }
+
+    #[test]
+    fn test_glider_into_block() {
+        let outcomes = sweep("oo/oo", ".o./..o/ooo", -3..=1);
+        assert!(outcomes.len() > 1);
+        assert!(outcomes.iter().all(|outcome| outcome.count >= 1));
+        // Nothing counts as a miss
+        assert!(!outcomes
+            .iter()
+            .any(|outcome| outcome.description == "1 block, escaped 1 glider"));
+        let descriptions: Vec<&str> = outcomes.iter().map(|o| o.description.as_str()).collect();
+        assert!(descriptions.contains(&"nothing"), "{descriptions:?}");
+        // In the order they were first found
+        let firsts: Vec<_> = outcomes
+            .iter()
+            .map(|o| (o.first.offset, o.first.target_phase, o.first.bullet_phase))
+            .collect();
+        let mut sorted = firsts.clone();
+        sorted.sort_unstable();
+        assert_eq!(firsts, sorted);
+        assert!(outcomes.iter().any(|outcome| outcome.count > 1));
+        for outcome in &outcomes {
+            let replay = lifespan::measure(&outcome.start, Rule::default(), 1000);
+            assert_eq!(replay, outcome.lifespan);
+        }
+    }
+
+    #[test]
+    fn test_place() {
+        let block = Phases::of(&"oo/oo".parse().unwrap(), Rule::default());
+        let glider = Phases::of(&".o./..o/ooo".parse().unwrap(), Rule::default());
+        assert_eq!(glider.patterns.len(), 4);
+        assert_eq!(glider.displacement, (1, 1));
+        let placement = Placement {
+            offset: (0, 0),
+            target_phase: 0,
+            bullet_phase: 0,
+        };
+        // Backed up diagonally until there are 2 empty rows between them
+        let start = place(&block, &glider, &placement).unwrap();
+        assert_eq!(start.population(), 9);
+        assert_eq!((start.height(), start.width()), (7, 7));
+        let still = Phases::of(&"oo/oo".parse().unwrap(), Rule::default());
+        assert_eq!(place(&block, &still, &placement), None);
+    }
+}
@@ -0,0 +1,469 @@
+use super::pattern::{signed, Pattern};
+use super::{classify, clusters, names, Classification};
+use crate::data::{Coord, Grid, PeriodKind, Rule, Status};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+pub const DEFAULT_MAX_GENERATIONS: u64 = 100_000;
+
+// Longest cycle whatever's left behind can settle into
+const HISTORY: usize = 1024;
+// Empty space kept around the live cells, so that nothing reaches the board's edges
+// and wraps around
+const MARGIN: isize = 32;
+// The board gets bigger once any live cell is this close to an edge
+const EDGE: isize = 8;
+// How far ahead of everything else a spaceship has to be to have escaped
+const ESCAPE_GAP: isize = 8;
+const ESCAPE_CHECK_EVERY: u64 = 16;
+
+/// A rectangle on an unbounded board, with inclusive bounds
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Bounds {
+    pub top: isize,
+    pub left: isize,
+    pub bottom: isize,
+    pub right: isize,
+}
+
+impl Bounds {
+    pub const fn width(&self) -> usize {
+        (self.right - self.left).unsigned_abs() + 1
+    }
+
+    pub const fn height(&self) -> usize {
+        (self.bottom - self.top).unsigned_abs() + 1
+    }
+
+    /// The smallest rectangle around some cells, or None if there aren't any
+    pub fn around<I>(cells: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = (isize, isize)>,
+    {
+        cells.into_iter().fold(None, |bounds, (i, j)| {
+            let cell = Self {
+                top: i,
+                left: j,
+                bottom: i,
+                right: j,
+            };
+            Some(bounds.map_or(cell, |bounds: Self| bounds.union(&cell)))
+        })
+    }
+
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            top: self.top.min(other.top),
+            left: self.left.min(other.left),
+            bottom: self.bottom.max(other.bottom),
+            right: self.right.max(other.right),
+        }
+    }
+}
+
+/// How a pattern played out on an unbounded board
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Lifespan {
+    /// Generation it settled down by, or None if it hadn't by the limit
+    pub settled: Option<u64>,
+    /// Period of whatever it left behind, apart from spaceships that escaped
+    pub period: Option<u64>,
+    pub max_population: usize,
+    /// First generation with the maximum population
+    pub max_population_at: u64,
+    /// Population once it had settled, or at the limit, counting escaped spaceships
+    pub final_population: usize,
+    /// Every cell that was alive before it settled, relative to the pattern's top left
+    /// corner. Escaping spaceships stop counting once they're clear of everything else.
+    pub bounds: Option<Bounds>,
+    /// Spaceships that got away, by name or apgcode, in the order they did
+    pub escaped: Vec<String>,
+    /// What was left on the board at the end, without the escaped spaceships
+    pub remains: Pattern,
+}
+
+/// Runs a pattern until it settles down, for up to `max_generations`, on a board that
+/// grows to stay bigger than the pattern.
+///
+/// Spaceships are taken off the board once they're heading away from everything else,
+/// since nothing can catch up with them. They still count towards the population, as
+/// they would on an unbounded board, and settling down only depends on what's left.
+pub fn measure(pattern: &Pattern, rule: Rule, max_generations: u64) -> Lifespan {
+    let mut run = Run::new(pattern, rule);
+    loop {
+        if let Some(periodicity) = run.grid.periodicity().copied() {
+            if !run.remove_escapees() {
+                return run.settled(periodicity.period);
+            }
+        }
+        if run.generation >= max_generations {
+            return run.unsettled();
+        }
+        run.advance();
+    }
+}
+
+struct Escapee {
+    label: String,
+    removed_at: u64,
+    // Population of each phase, starting from the one it was removed in
+    populations: Vec<usize>,
+}
+
+impl Escapee {
+    // The spaceship's population at a generation, as if it had been a spaceship then
+    fn population(&self, generation: u64) -> usize {
+        let period = self.populations.len() as u64;
+        let phase = (generation % period + period - self.removed_at % period) % period;
+        usize::try_from(phase).map_or(0, |phase| self.populations[phase])
+    }
+}
+
+struct Run {
+    grid: Grid,
+    rule: Rule,
+    // Where the grid's top left cell is on the unbounded board
+    origin: (isize, isize),
+    generation: u64,
+    escapees: Vec<Escapee>,
+    // Population every generation, counting escapees
+    populations: Vec<usize>,
+    // Rectangle around the cells still on the board every generation
+    bounds: Vec<Option<Bounds>>,
+    classifications: HashMap<Pattern, Option<Classification>>,
+}
+
+impl Run {
+    fn new(pattern: &Pattern, rule: Rule) -> Self {
+        let mut run = Self {
+            grid: Grid::empty(0, 0),
+            rule,
+            origin: (0, 0),
+            generation: 0,
+            escapees: Vec::new(),
+            populations: Vec::new(),
+            bounds: Vec::new(),
+            classifications: HashMap::new(),
+        };
+        run.place(&pattern.cells().iter().copied().map(signed).collect());
+        run.record();
+        run
+    }
+
+    // Live cells on the unbounded board
+    fn cells(&self) -> impl Iterator<Item = (isize, isize)> + '_ {
+        let (top, left) = self.origin;
+        self.grid
+            .live_cells()
+            .map(move |cell| (signed(cell).0 + top, signed(cell).1 + left))
+    }
+
+    // Starts a new grid with room around the given cells
+    fn place(&mut self, cells: &HashSet<(isize, isize)>) {
+        let bounds = Bounds::around(cells.iter().copied()).unwrap_or(Bounds {
+            top: 0,
+            left: 0,
+            bottom: 0,
+            right: 0,
+        });
+        let size = signed((bounds.height(), bounds.width()));
+        let margin = MARGIN + size.0.max(size.1) / 4;
+        self.origin = (bounds.top - margin, bounds.left - margin);
+        let (top, left) = self.origin;
+        self.grid = Grid::from_fn(
+            (size.1 + margin * 2).unsigned_abs(),
+            (size.0 + margin * 2).unsigned_abs(),
+            |&Coord { i, j }| {
+                let (i, j) = signed((i, j));
+                if cells.contains(&(i + top, j + left)) {
+                    Status::Alive
+                } else {
+                    Status::Dead
+                }
+            },
+        )
+        .with_rule(self.rule)
+        .with_history(HISTORY);
+    }
+
+    fn advance(&mut self) {
+        self.grid.advance();
+        self.generation += 1;
+        if self.generation.is_multiple_of(ESCAPE_CHECK_EVERY) {
+            self.remove_escapees();
+        }
+        let bounds = self.record();
+        let (height, width) = signed((self.grid.height(), self.grid.width()));
+        let (top, left) = self.origin;
+        let near_edge = bounds.is_some_and(|b| {
+            b.top - top < EDGE
+                || b.left - left < EDGE
+                || top + height - 1 - b.bottom < EDGE
+                || left + width - 1 - b.right < EDGE
+        });
+        if near_edge {
+            self.place(&self.cells().collect());
+        }
+    }
+
+    fn record(&mut self) -> Option<Bounds> {
+        let escaped: usize = self
+            .escapees
+            .iter()
+            .map(|escapee| escapee.population(self.generation))
+            .sum();
+        let bounds = Bounds::around(self.cells());
+        self.populations.push(self.grid.population() + escaped);
+        self.bounds.push(bounds);
+        bounds
+    }
+
+    // Takes spaceships that are heading away from everything else off the board,
+    // returning whether there were any
+    fn remove_escapees(&mut self) -> bool {
+        let (top, left) = self.origin;
+        let objects: Vec<Vec<(isize, isize)>> = clusters(&self.grid)
+            .into_iter()
+            .map(|cluster| {
+                cluster
+                    .into_iter()
+                    .map(|(cell, _)| (signed(cell).0 + top, signed(cell).1 + left))
+                    .collect()
+            })
+            .collect();
+        let mut removed = vec![false; objects.len()];
+        for (index, cells) in objects.iter().enumerate() {
+            let pattern = Pattern::new(cells.iter().copied());
+            let rule = self.rule;
+            let classification = self
+                .classifications
+                .entry(pattern.clone())
+                .or_insert_with(|| classify(&pattern, rule));
+            let Some(classification) = classification.as_ref() else {
+                continue;
+            };
+            if classification.kind != PeriodKind::Spaceship {
+                continue;
+            }
+            let rest = Bounds::around(
+                objects
+                    .iter()
+                    .enumerate()
+                    .filter(|&(other, _)| other != index && !removed[other])
+                    .flat_map(|(_, cells)| cells.iter().copied()),
+            );
+            let Some(ship) = Bounds::around(cells.iter().copied()) else {
+                continue;
+            };
+            if escaping(&ship, rest.as_ref(), classification.displacement) {
+                removed[index] = true;
+                let label = names::name(&classification.key, rule)
+                    .map_or_else(|| classification.apgcode.to_string(), str::to_string);
+                let period = classification.period;
+                self.escapees.push(Escapee {
+                    label,
+                    removed_at: self.generation,
+                    populations: populations(&pattern, rule, period),
+                });
+            }
+        }
+        if !removed.contains(&true) {
+            return false;
+        }
+        let remaining = objects
+            .into_iter()
+            .zip(removed)
+            .filter(|&(_, removed)| !removed)
+            .flat_map(|(cells, _)| cells)
+            .collect();
+        self.place(&remaining);
+        true
+    }
+
+    // Works back from a cycle to the first generation the population followed it from,
+    // leaving escaped spaceships out
+    fn settled(self, period: u64) -> Lifespan {
+        let escaped = |generation: u64| -> usize {
+            self.escapees
+                .iter()
+                .map(|escapee| escapee.population(generation))
+                .sum()
+        };
+        let population = |generation: u64| population_at(&self.populations, generation);
+        // Whether what's left on the board had the same population a period later
+        let repeats = |generation: u64| {
+            let later = generation + period;
+            population(generation) + escaped(later) == population(later) + escaped(generation)
+        };
+        let mut settled = self.generation.saturating_sub(period);
+        while settled > 0 && repeats(settled - 1) {
+            settled -= 1;
+        }
+        let last = (settled + period - 1).min(self.generation);
+        self.lifespan(Some(settled), Some(period), last)
+    }
+
+    fn unsettled(self) -> Lifespan {
+        let last = self.generation;
+        self.lifespan(None, None, last)
+    }
+
+    fn lifespan(self, settled: Option<u64>, period: Option<u64>, last: u64) -> Lifespan {
+        let last_index = usize::try_from(last).unwrap_or(usize::MAX);
+        let (max_population_at, max_population) =
+            self.populations.iter().copied().enumerate().fold(
+                (0, 0),
+                |max, (generation, population)| {
+                    if population > max.1 {
+                        (generation as u64, population)
+                    } else {
+                        max
+                    }
+                },
+            );
+        let bounds = self
+            .bounds
+            .iter()
+            .take(last_index.saturating_add(1))
+            .flatten()
+            .fold(None, |all: Option<Bounds>, bounds| {
+                Some(all.map_or(*bounds, |all| all.union(bounds)))
+            });
+        let remains = Pattern::new(self.cells());
+        Lifespan {
+            settled,
+            period,
+            max_population,
+            max_population_at,
+            final_population: population_at(&self.populations, settled.unwrap_or(last)),
+            bounds,
+            escaped: self
+                .escapees
+                .into_iter()
+                .map(|escapee| escapee.label)
+                .collect(),
+            remains,
+        }
+    }
+}
+
+fn population_at(populations: &[usize], generation: u64) -> usize {
+    usize::try_from(generation)
+        .ok()
+        .and_then(|generation| populations.get(generation))
+        .copied()
+        .unwrap_or(0)
+}
+
+// Whether a spaceship is moving away from everything else in at least one direction
+const fn escaping(ship: &Bounds, rest: Option<&Bounds>, (rows, columns): (isize, isize)) -> bool {
+    let Some(rest) = rest else {
+        return true;
+    };
+    (rows > 0 && ship.top > rest.bottom + ESCAPE_GAP)
+        || (rows < 0 && ship.bottom < rest.top - ESCAPE_GAP)
+        || (columns > 0 && ship.left > rest.right + ESCAPE_GAP)
+        || (columns < 0 && ship.right < rest.left - ESCAPE_GAP)
+}
+
+// Population of each phase of a spaceship, starting from the given one
+fn populations(pattern: &Pattern, rule: Rule, period: u64) -> Vec<usize> {
+    let mut grid = pattern.to_grid(MARGIN.unsigned_abs(), rule);
+    (0..period.max(1))
+        .map(|_| {
+            let population = grid.population();
+            grid.advance();
+            population
+        })
+        .collect()
+}
+
+impl fmt::Display for Lifespan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.settled, self.period) {
+            (Some(settled), Some(period)) => {
+                writeln!(f, "Settled: generation {settled}, period {period}")?;
+            }
+            _ => writeln!(f, "Settled: not yet")?,
+        }
+        writeln!(
+            f,
+            "Max population: {} at generation {}",
+            self.max_population, self.max_population_at
+        )?;
+        writeln!(f, "Final population: {}", self.final_population)?;
+        match &self.bounds {
+            Some(bounds) => writeln!(
+                f,
+                "Bounding box: {}x{}, from row {} column {}",
+                bounds.width(),
+                bounds.height(),
+                bounds.top,
+                bounds.left
+            )?,
+            None => writeln!(f, "Bounding box: none")?,
+        }
+        let mut escaped: BTreeMap<&str, usize> = BTreeMap::new();
+        for label in &self.escaped {
+            *escaped.entry(label).or_default() += 1;
+        }
+        let escaped: Vec<String> = escaped
+            .into_iter()
+            .map(|(label, count)| format!("{count} {label}"))
+            .collect();
+        if escaped.is_empty() {
+            write!(f, "Escaped: nothing")
+        } else {
+            write!(f, "Escaped: {}", escaped.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measure_cells(cells: &str) -> Lifespan {
+        measure(&cells.parse().unwrap(), Rule::default(), 5000)
+    }
+
+    #[test]
+    fn test_still_life_and_oscillator() {
+        let block = measure_cells("oo/oo");
+        assert_eq!((block.settled, block.period), (Some(0), Some(1)));
+        assert_eq!((block.max_population, block.final_population), (4, 4));
+        let blinker = measure_cells("ooo");
+        assert_eq!((blinker.settled, blinker.period), (Some(0), Some(2)));
+        assert_eq!(
+            blinker.bounds.map(|b| (b.width(), b.height())),
+            Some((3, 3))
+        );
+    }
+
+    #[test]
+    fn test_glider_escapes() {
+        let glider = measure_cells(".o./..o/ooo");
+        assert_eq!(glider.escaped, vec!["glider"]);
+        assert_eq!(glider.settled, Some(0));
+        assert_eq!(glider.final_population, 5);
+        assert!(glider.remains.is_empty());
+    }
+
+    #[test]
+    fn test_r_pentomino() {
+        let r = measure_cells(".oo/oo./.o.");
+        assert_eq!(r.settled, Some(1103));
+        assert_eq!(r.period, Some(2));
+        assert_eq!((r.max_population, r.max_population_at), (319, 821));
+        assert_eq!(r.final_population, 116);
+        assert_eq!(r.escaped, vec!["glider"; 6]);
+    }
+
+    #[test]
+    fn test_unsettled() {
+        let r = measure(&".oo/oo./.o.".parse().unwrap(), Rule::default(), 100);
+        assert_eq!((r.settled, r.period), (None, None));
+        assert!(r.to_string().starts_with("Settled: not yet\n"));
+    }
+}
@@ -0,0 +1,397 @@
+pub mod apgcode;
+pub mod collision;
+pub mod lifespan;
+pub mod names;
+pub mod pattern;
+pub mod rle;
+pub mod search;
+
+use self::apgcode::{Apgcode, Prefix};
+use self::pattern::{signed, Pattern};
+use crate::data::{BoundingBox, Coord, Grid, PeriodKind, Rule, Status};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+/// Longest period objects get followed for when working out what they are
+pub const MAX_PERIOD: usize = 30;
+
+// Room left around an object while it's run on its own, which keeps anything that moves
+// or changes shape clear of the edges until it has had time to repeat
+const MARGIN: usize = MAX_PERIOD / 2 + 2;
+
+// Cells this far apart or closer, in both directions, can end up affecting each other
+const INTERACTION_RANGE: isize = 2;
+
+/// How an object behaves when left on its own
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Classification {
+    pub period: u64,
+    /// Rows and columns the object moves by each period
+    pub displacement: (isize, isize),
+    pub kind: PeriodKind,
+    /// The smallest of the object's phases in canonical form, which is the same whatever
+    /// phase or orientation the object is found in
+    pub key: Pattern,
+    pub apgcode: Apgcode,
+}
+
+/// Runs a pattern on its own to see how it behaves, from the phase it's in. Returns None
+/// if it doesn't come back to that phase within `MAX_PERIOD` generations.
+pub fn classify(pattern: &Pattern, rule: Rule) -> Option<Classification> {
+    if pattern.is_empty() {
+        return None;
+    }
+    let mut grid = pattern.to_grid(MARGIN, rule).with_history(MAX_PERIOD + 1);
+    let mut phases = vec![pattern.clone()];
+    while grid.periodicity().is_none() && grid.generation() < MAX_PERIOD as u64 {
+        grid.advance();
+        phases.push(Pattern::from_grid(&grid));
+    }
+    let periodicity = *grid.periodicity()?;
+    if periodicity.start != 0 {
+        return None;
+    }
+    let (period, kind) = (periodicity.period, periodicity.kind());
+    let prefix = Prefix::new(kind, period, pattern.population())?;
+    phases.truncate(usize::try_from(period).ok()?);
+    Some(Classification {
+        period,
+        displacement: periodicity.displacement,
+        kind,
+        key: phases.iter().map(Pattern::canonical).min()?,
+        apgcode: Apgcode::canonical(prefix, &phases),
+    })
+}
+
+/// Classifies whatever is inside a region of a grid, as if it were on its own
+pub fn classify_region(grid: &Grid, region: &BoundingBox) -> Option<Classification> {
+    classify(&Pattern::from_region(grid, region), *grid.rule())
+}
+
+/// Something found on a board by a `Census`
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Object {
+    /// Where the object's live cells are on the board. It might wrap around the edges.
+    pub cells: Vec<(usize, usize)>,
+    pub pattern: Pattern,
+    /// None for objects that haven't settled down
+    pub classification: Option<Classification>,
+    pub name: Option<&'static str>,
+}
+
+impl Object {
+    /// The object's name if it's a common one, or else its apgcode
+    pub fn label(&self) -> String {
+        match (self.name, &self.classification) {
+            (Some(name), _) => name.to_string(),
+            (None, Some(classification)) => classification.apgcode.to_string(),
+            (None, None) => format!("unsettled object, {} cells", self.pattern.population()),
+        }
+    }
+}
+
+/// The separate objects on a board, and what they are.
+///
+/// Live cells close enough to affect each other are grouped together. Groups made of
+/// separate pieces that would behave exactly the same apart, like two blocks side by
+/// side, get split back up into those pieces, since they aren't really one object.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Census {
+    objects: Vec<Object>,
+}
+
+impl Census {
+    /// Takes a census of a board, which should have settled down first
+    pub fn of(grid: &Grid) -> Self {
+        let rule = *grid.rule();
+        let mut classifications: HashMap<Pattern, Option<Classification>> = HashMap::new();
+        let mut classify = |pattern: &Pattern| {
+            classifications
+                .entry(pattern.clone())
+                .or_insert_with(|| classify(pattern, rule))
+                .clone()
+        };
+        let mut objects = Vec::new();
+        for cluster in clusters(grid) {
+            let pattern = Pattern::new(cluster.iter().map(|&(_, offset)| offset));
+            let classification = classify(&pattern);
+            let pieces = pieces(&cluster);
+            let split = pieces.len() > 1
+                && classification
+                    .as_ref()
+                    .is_some_and(|c| independent(&cluster, &pieces, c.period, rule));
+            let groups = if split { pieces } else { vec![cluster] };
+            for group in groups {
+                let pattern = Pattern::new(group.iter().map(|&(_, offset)| offset));
+                let classification = classify(&pattern);
+                let name = classification
+                    .as_ref()
+                    .and_then(|c| names::name(&c.key, rule));
+                objects.push(Object {
+                    cells: group.into_iter().map(|(cell, _)| cell).collect(),
+                    pattern,
+                    classification,
+                    name,
+                });
+            }
+        }
+        Self { objects }
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    /// How many of each kind of object there are, most common first
+    pub fn counts(&self) -> Vec<(String, usize)> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for object in &self.objects {
+            *counts.entry(object.label()).or_default() += 1;
+        }
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+}
+
+impl fmt::Display for Census {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts: Vec<String> = self
+            .counts()
+            .into_iter()
+            .map(|(label, count)| format!("{count} {label}"))
+            .collect();
+        write!(f, "{}", counts.join(", "))
+    }
+}
+
+// A live cell's position on the board, and where it is relative to the first cell of
+// its cluster once the board's edges are unwrapped
+pub(crate) type Member = ((usize, usize), (isize, isize));
+
+// Groups live cells within `INTERACTION_RANGE` of each other, wrapping around the edges
+// of the board only if the cells themselves do
+pub(crate) fn clusters(grid: &Grid) -> Vec<Vec<Member>> {
+    let (height, width, topology) = (grid.height(), grid.width(), grid.topology());
+    let mut unvisited: HashSet<(usize, usize)> = grid.live_cells().collect();
+    let mut clusters = Vec::new();
+    for start in grid.live_cells() {
+        if !unvisited.remove(&start) {
+            continue;
+        }
+        let mut cluster = vec![(start, (0, 0))];
+        let mut next = 0;
+        while next < cluster.len() {
+            let ((i, j), (offset_i, offset_j)) = cluster[next];
+            next += 1;
+            let (i, j) = signed((i, j));
+            for di in -INTERACTION_RANGE..=INTERACTION_RANGE {
+                for dj in -INTERACTION_RANGE..=INTERACTION_RANGE {
+                    let wrapped = topology.wrap((i + di, j + dj), height, width);
+                    let Some(Coord { i, j }) = wrapped else {
+                        continue;
+                    };
+                    let neighbour = (i, j);
+                    if unvisited.remove(&neighbour) {
+                        cluster.push((neighbour, (offset_i + di, offset_j + dj)));
+                    }
+                }
+            }
+        }
+        clusters.push(cluster);
+    }
+    clusters
+}
+
+// Splits a cluster into pieces whose cells touch, including diagonally
+fn pieces(cluster: &[Member]) -> Vec<Vec<Member>> {
+    let mut unvisited: HashMap<(isize, isize), Member> =
+        cluster.iter().map(|&member| (member.1, member)).collect();
+    let mut pieces = Vec::new();
+    for &(_, start) in cluster {
+        let Some(first) = unvisited.remove(&start) else {
+            continue;
+        };
+        let mut piece = vec![first];
+        let mut next = 0;
+        while next < piece.len() {
+            let (i, j) = piece[next].1;
+            next += 1;
+            for di in -1..=1 {
+                for dj in -1..=1 {
+                    if let Some(member) = unvisited.remove(&(i + di, j + dj)) {
+                        piece.push(member);
+                    }
+                }
+            }
+        }
+        pieces.push(piece);
+    }
+    pieces
+}
+
+// Whether each piece of a cluster carries on exactly as it would on its own, checked
+// over a full period of the cluster as a whole
+fn independent(cluster: &[Member], pieces: &[Vec<Member>], period: u64, rule: Rule) -> bool {
+    let offsets = |members: &[Member]| -> Vec<(isize, isize)> {
+        members.iter().map(|&(_, offset)| offset).collect()
+    };
+    let all = offsets(cluster);
+    let origin = (
+        all.iter().map(|&(i, _)| i).min().unwrap_or(0),
+        all.iter().map(|&(_, j)| j).min().unwrap_or(0),
+    );
+    let bounds = Pattern::new(all.iter().copied());
+    let mut together = place(&all, origin, &bounds, rule);
+    let mut apart: Vec<Grid> = pieces
+        .iter()
+        .map(|piece| place(&offsets(piece), origin, &bounds, rule))
+        .collect();
+    for _ in 0..period {
+        together.advance();
+        let mut combined = Vec::new();
+        for grid in &mut apart {
+            grid.advance();
+            combined.extend(grid.live_cells());
+        }
+        combined.sort_unstable();
+        if !combined.iter().copied().eq(together.live_cells()) {
+            return false;
+        }
+    }
+    true
+}
+
+// A grid with room for a cluster and its margin, with the given cells from it alive
+fn place(cells: &[(isize, isize)], origin: (isize, isize), bounds: &Pattern, rule: Rule) -> Grid {
+    let margin = signed((MARGIN, MARGIN));
+    let alive: HashSet<(isize, isize)> = cells
+        .iter()
+        .map(|&(i, j)| (i - origin.0 + margin.0, j - origin.1 + margin.1))
+        .collect();
+    Grid::from_fn(
+        bounds.width() + MARGIN * 2,
+        bounds.height() + MARGIN * 2,
+        |&Coord { i, j }| {
+            if alive.contains(&signed((i, j))) {
+                Status::Alive
+            } else {
+                Status::Dead
+            }
+        },
+    )
+    .with_rule(rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Topology;
+
+    // Stamps patterns onto an empty board at the given top left corners
+    fn board(width: usize, height: usize, patterns: &[(&str, usize, usize)]) -> Grid {
+        let mut grid = Grid::empty(width, height);
+        for &(cells, top, left) in patterns {
+            let pattern: Pattern = cells.parse().unwrap();
+            pattern.stamp(&mut grid, &Coord { i: top, j: left });
+        }
+        grid
+    }
+
+    #[test]
+    fn test_classify_region() {
+        let grid = board(20, 20, &[("oo/oo", 2, 2), (".o./..o/ooo", 10, 10)]);
+        let region = |min_i, min_j, max_i, max_j| BoundingBox {
+            min_i,
+            min_j,
+            max_i,
+            max_j,
+        };
+        let glider = classify_region(&grid, &region(8, 8, 14, 14)).unwrap();
+        assert_eq!(glider.apgcode.to_string(), "xq4_153");
+        assert_eq!((glider.period, glider.displacement), (4, (1, 1)));
+        let block = classify_region(&grid, &region(0, 0, 5, 5)).unwrap();
+        assert_eq!(block.apgcode.to_string(), "xs4_33");
+        assert_eq!(classify_region(&grid, &region(15, 0, 19, 19)), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let blinker = classify(&"ooo".parse().unwrap(), Rule::default()).unwrap();
+        assert_eq!((blinker.period, blinker.kind), (2, PeriodKind::Oscillator));
+        assert_eq!(blinker.key, "o/o/o".parse::<Pattern>().unwrap().canonical());
+
+        let glider = classify(&".o./..o/ooo".parse().unwrap(), Rule::default()).unwrap();
+        assert_eq!((glider.period, glider.kind), (4, PeriodKind::Spaceship));
+        assert_eq!(glider.displacement, (1, 1));
+        let other_phase = classify(&"o.o/.oo/.o.".parse().unwrap(), Rule::default()).unwrap();
+        assert_eq!(other_phase.key, glider.key);
+
+        // An R-pentomino takes over a thousand generations to settle
+        assert_eq!(
+            classify(&".oo/oo./.o.".parse().unwrap(), Rule::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_census() {
+        let grid = board(
+            40,
+            30,
+            &[
+                ("oo/oo", 2, 2),
+                ("oo/oo", 2, 10),
+                ("ooo", 10, 2),
+                (".oo./o..o/.oo.", 10, 10),
+                (".o./..o/ooo", 20, 20),
+                // Two blocks close enough to be grouped, but which don't affect each other
+                ("oo.oo/oo.oo", 20, 2),
+                // Wrapping around the bottom right corner
+                ("oo/oo", 29, 39),
+            ],
+        );
+        let census = Census::of(&grid);
+        assert_eq!(census.objects().len(), 8);
+        assert_eq!(
+            census.counts(),
+            vec![
+                ("block".to_string(), 5),
+                ("beehive".to_string(), 1),
+                ("blinker".to_string(), 1),
+                ("glider".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            census.to_string(),
+            "5 block, 1 beehive, 1 blinker, 1 glider"
+        );
+        let wrapped = census
+            .objects()
+            .iter()
+            .find(|object| object.cells.contains(&(0, 0)))
+            .unwrap();
+        assert_eq!(wrapped.pattern, "oo/oo".parse().unwrap());
+    }
+
+    #[test]
+    fn test_census_on_a_plane() {
+        // Blocks on opposite edges, which would be one object if the edges wrapped around
+        let grid = board(20, 20, &[("oo/oo", 5, 0), ("oo/oo", 5, 18)]);
+        assert_eq!(Census::of(&grid).to_string(), "1 unsettled object, 8 cells");
+        let plane = grid.with_topology(Topology::Plane);
+        assert_eq!(Census::of(&plane).to_string(), "2 block");
+    }
+
+    #[test]
+    fn test_census_keeps_objects_that_need_each_other() {
+        // An aircraft carrier's two halves would both die on their own
+        let grid = board(20, 20, &[("oo../o..o/..oo", 5, 5), ("ooo/...", 12, 12)]);
+        let census = Census::of(&grid);
+        assert_eq!(census.to_string(), "1 aircraft carrier, 1 blinker");
+        let unknown = board(20, 20, &[("oooo.o/o...../...oo./.oo..o/o.o..o", 5, 5)]);
+        let census = Census::of(&unknown);
+        assert_eq!(census.objects().len(), 1);
+        assert!(census.objects()[0].name.is_none());
+    }
+}
@@ -0,0 +1,95 @@
+use super::classify;
+use super::pattern::Pattern;
+use crate::data::Rule;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Common objects in Conway's Game of Life, each in any one of its phases
+pub const NAMED_OBJECTS: [(&str, &str); 23] = [
+    // Still lifes
+    ("block", "oo/oo"),
+    ("beehive", ".oo./o..o/.oo."),
+    ("loaf", ".oo./o..o/.o.o/..o."),
+    ("boat", "oo./o.o/.o."),
+    ("ship", "oo./o.o/.oo"),
+    ("tub", ".o./o.o/.o."),
+    ("pond", ".oo./o..o/o..o/.oo."),
+    ("long boat", "oo../o.o./.o.o/..o."),
+    ("barge", ".o../o.o./.o.o/..o."),
+    ("mango", ".oo../o..o./.o..o/..oo."),
+    ("aircraft carrier", "oo../o..o/..oo"),
+    ("snake", "oo.o/o.oo"),
+    ("eater 1", "oo../o.o./..o./..oo"),
+    // Oscillators
+    ("blinker", "ooo"),
+    ("toad", ".ooo/ooo."),
+    ("beacon", "oo../oo../..oo/..oo"),
+    ("clock", "..o./o.o./.o.o/.o.."),
+    (
+        "pulsar",
+        "..ooo...ooo../............./o....o.o....o/o....o.o....o/o....o.o....o/..ooo...ooo../\
+         ............./..ooo...ooo../o....o.o....o/o....o.o....o/o....o.o....o/\
+         ............./..ooo...ooo..",
+    ),
+    ("pentadecathlon", "..o....o../oo.oooo.oo/..o....o.."),
+    // Spaceships
+    ("glider", ".o./..o/ooo"),
+    ("lightweight spaceship", ".o..o/o..../o...o/oooo."),
+    (
+        "middleweight spaceship",
+        "...o../.o...o/o...../o....o/ooooo.",
+    ),
+    (
+        "heavyweight spaceship",
+        "...oo../.o....o/o....../o.....o/oooooo.",
+    ),
+];
+
+/// The common name of an object, looked up by its `Classification::key`. Names are only
+/// known for Conway's rule, since the same cells behave differently under others.
+pub fn name(key: &Pattern, rule: Rule) -> Option<&'static str> {
+    if rule != Rule::default() {
+        return None;
+    }
+    table().get(key).copied()
+}
+
+fn table() -> &'static HashMap<Pattern, &'static str> {
+    static TABLE: OnceLock<HashMap<Pattern, &'static str>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        NAMED_OBJECTS
+            .iter()
+            .filter_map(|&(name, cells)| {
+                let pattern: Pattern = cells.parse().ok()?;
+                Some((classify(&pattern, Rule::default())?.key, name))
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_named_object_settles() {
+        for (name, cells) in NAMED_OBJECTS {
+            let pattern: Pattern = cells.parse().unwrap();
+            let classification = classify(&pattern, Rule::default())
+                .unwrap_or_else(|| panic!("{name} doesn't settle"));
+            assert_eq!(
+                super::name(&classification.key, Rule::default()),
+                Some(name)
+            );
+        }
+        assert_eq!(table().len(), NAMED_OBJECTS.len());
+    }
+
+    #[test]
+    fn test_name_needs_conways_rule() {
+        let block = classify(&"oo/oo".parse().unwrap(), Rule::default()).unwrap();
+        assert_eq!(name(&block.key, Rule::default()), Some("block"));
+        let highlife = "B36/S23".parse().unwrap();
+        assert_eq!(name(&block.key, highlife), None);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::data::{BoundingBox, Coord, Grid, Rule, Status, Transform};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A set of live cells in (i, j) notation, moved so that the topmost row and leftmost
+/// column are both 0
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Default)]
+pub struct Pattern {
+    // Fields are in this order so that smaller patterns sort first
+    height: usize,
+    width: usize,
+    // Sorted row by row, without duplicates
+    cells: Vec<(usize, usize)>,
+}
+
+impl Pattern {
+    /// Collects live cells, which can be anywhere, including at negative coordinates
+    pub fn new<I>(cells: I) -> Self
+    where
+        I: IntoIterator<Item = (isize, isize)>,
+    {
+        let cells: Vec<(isize, isize)> = cells.into_iter().collect();
+        let Some(min_i) = cells.iter().map(|&(i, _)| i).min() else {
+            return Self::default();
+        };
+        let min_j = cells.iter().map(|&(_, j)| j).min().unwrap_or(0);
+        let mut cells: Vec<(usize, usize)> = cells
+            .into_iter()
+            .map(|(i, j)| ((i - min_i).unsigned_abs(), (j - min_j).unsigned_abs()))
+            .collect();
+        cells.sort_unstable();
+        cells.dedup();
+        let height = cells.iter().map(|&(i, _)| i + 1).max().unwrap_or(0);
+        let width = cells.iter().map(|&(_, j)| j + 1).max().unwrap_or(0);
+        Self {
+            height,
+            width,
+            cells,
+        }
+    }
+
+    /// Every live cell on a grid, ignoring the way its edges wrap around
+    pub fn from_grid(grid: &Grid) -> Self {
+        Self::new(grid.live_cells().map(signed))
+    }
+
+    /// The live cells inside a region of a grid
+    pub fn from_region(grid: &Grid, region: &BoundingBox) -> Self {
+        let inside = |&(i, j): &(usize, usize)| {
+            (region.min_i..=region.max_i).contains(&i) && (region.min_j..=region.max_j).contains(&j)
+        };
+        Self::new(grid.live_cells().filter(inside).map(signed))
+    }
+
+    pub fn cells(&self) -> &[(usize, usize)] {
+        &self.cells
+    }
+
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Rotates or reflects the pattern
+    #[must_use]
+    pub fn transform(&self, transform: Transform) -> Self {
+        Self::new(self.cells.iter().map(|&cell| transform.apply(signed(cell))))
+    }
+
+    /// The same pattern the same way up whichever way it was rotated or reflected, so
+    /// that patterns can be compared regardless of orientation
+    #[must_use]
+    pub fn canonical(&self) -> Self {
+        Transform::ALL
+            .iter()
+            .map(|&transform| self.transform(transform))
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Brings the pattern's cells to life on a grid, with its top left corner at `at`.
+    /// Cells past the grid's edges wrap around or get left out, depending on its topology.
+    pub fn stamp(&self, grid: &mut Grid, at: &Coord) {
+        let (height, width) = (grid.height(), grid.width());
+        for &(i, j) in &self.cells {
+            let position = signed((at.i + i, at.j + j));
+            if let Some(coord) = grid.topology().wrap(position, height, width) {
+                grid.set(&coord, Status::Alive);
+            }
+        }
+    }
+
+    /// A grid just big enough for the pattern with `margin` empty cells around it
+    pub fn to_grid(&self, margin: usize, rule: Rule) -> Grid {
+        let (top, left) = (margin, margin);
+        Grid::from_fn(
+            self.width + margin * 2,
+            self.height + margin * 2,
+            |&Coord { i, j }| {
+                let inside = i >= top && j >= left;
+                if inside && self.cells.binary_search(&(i - top, j - left)).is_ok() {
+                    Status::Alive
+                } else {
+                    Status::Dead
+                }
+            },
+        )
+        .with_rule(rule)
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+pub(crate) const fn signed((i, j): (usize, usize)) -> (isize, isize) {
+    (i as isize, j as isize)
+}
+
+/// Rows of `.` for dead cells and `o` for live ones, separated by `/` or line breaks,
+/// like the plaintext format that's common for sharing small patterns
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for i in 0..self.height {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            for j in 0..self.width {
+                let alive = self.cells.binary_search(&(i, j)).is_ok();
+                write!(f, "{}", if alive { 'o' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParsePatternError(String);
+
+impl fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid pattern [{}], expected rows of . and o separated by / or new lines",
+            self.0
+        )
+    }
+}
+
+impl Error for ParsePatternError {}
+
+impl FromStr for Pattern {
+    type Err = ParsePatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cells = Vec::new();
+        let rows = s
+            .split(['/', '\n'])
+            .map(str::trim)
+            // Lines starting with ! are comments in plaintext files
+            .filter(|row| !row.starts_with('!'));
+        for (i, row) in rows.enumerate() {
+            for (j, c) in row.chars().enumerate() {
+                match c {
+                    'o' | 'O' | '*' => cells.push(signed((i, j))),
+                    '.' => {}
+                    _ => return Err(ParsePatternError(s.to_string())),
+                }
+            }
+        }
+        Ok(Self::new(cells))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_normalises() {
+        let pattern = Pattern::new([(5, 3), (4, 4), (5, 3), (-1, 3)]);
+        assert_eq!(pattern.cells(), &[(0, 0), (5, 1), (6, 0)]);
+        assert_eq!((pattern.width(), pattern.height()), (2, 7));
+        assert!(Pattern::new([]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let glider: Pattern = ".o./..o/ooo".parse().unwrap();
+        assert_eq!(glider.population(), 5);
+        assert_eq!(glider.to_string(), ".o./..o/ooo");
+        let commented: Pattern = "!Name: Glider\n.O.\n..O\nOOO\n".parse().unwrap();
+        assert_eq!(commented, glider);
+        assert!("oo/ox".parse::<Pattern>().is_err());
+    }
+
+    #[test]
+    fn test_transform() {
+        let l: Pattern = "o./o./oo".parse().unwrap();
+        assert_eq!(l.transform(Transform::Rotate90).to_string(), "ooo/o..");
+        assert_eq!(l.transform(Transform::Rotate180).to_string(), "oo/.o/.o");
+        assert_eq!(l.transform(Transform::Rotate270).to_string(), "..o/ooo");
+        assert_eq!(l.transform(Transform::FlipRows).to_string(), "oo/o./o.");
+        assert_eq!(l.transform(Transform::FlipColumns).to_string(), ".o/.o/oo");
+        assert_eq!(l.transform(Transform::Transpose).to_string(), "ooo/..o");
+        assert_eq!(l.transform(Transform::AntiTranspose).to_string(), "o../ooo");
+    }
+
+    #[test]
+    fn test_canonical() {
+        let glider: Pattern = ".o./..o/ooo".parse().unwrap();
+        let canonical = glider.canonical();
+        for transform in Transform::ALL {
+            assert_eq!(glider.transform(transform).canonical(), canonical);
+        }
+        let other_phase: Pattern = "o.o/.oo/.o.".parse().unwrap();
+        assert_ne!(other_phase.canonical(), canonical);
+    }
+
+    #[test]
+    fn test_grid_round_trip() {
+        let glider: Pattern = ".o./..o/ooo".parse().unwrap();
+        let grid = glider.to_grid(2, Rule::default());
+        assert_eq!((grid.width(), grid.height()), (7, 7));
+        assert_eq!(grid.population(), 5);
+        assert_eq!(Pattern::from_grid(&grid), glider);
+    }
+
+    #[test]
+    fn test_stamp_and_region() {
+        let glider: Pattern = ".o./..o/ooo".parse().unwrap();
+        let mut grid = Grid::empty(5, 5);
+        glider.stamp(&mut grid, &Coord { i: 3, j: 1 });
+        assert_eq!(grid.population(), 5);
+        let top = BoundingBox {
+            min_i: 0,
+            min_j: 0,
+            max_i: 1,
+            max_j: 4,
+        };
+        // Only the bottom row wraps around to the top
+        assert_eq!(Pattern::from_region(&grid, &top), "ooo".parse().unwrap());
+    }
+}
@@ -0,0 +1,154 @@
+use super::pattern::{signed, Pattern};
+use crate::data::Rule;
+use std::error::Error;
+use std::fmt;
+
+// Longest line written, as recommended for the format
+const LINE_LENGTH: usize = 70;
+
+/// Writes a pattern in run length encoded format, the usual way of sharing patterns: a
+/// header with its size and rule, then runs of `b` for dead cells and `o` for live ones,
+/// with `$` between rows and `!` at the end
+pub fn encode(pattern: &Pattern, rule: Rule) -> String {
+    let mut tokens = Vec::new();
+    let run = |count: usize, tag: char| {
+        if count == 1 {
+            tag.to_string()
+        } else {
+            format!("{count}{tag}")
+        }
+    };
+    let (mut row, mut column) = (0, 0);
+    let cells = pattern.cells();
+    let mut index = 0;
+    while index < cells.len() {
+        let (i, j) = cells[index];
+        // Live cells in a row, one after the other
+        let length = cells[index..]
+            .iter()
+            .zip(j..)
+            .take_while(|&(&(cell_i, cell_j), expected)| cell_i == i && cell_j == expected)
+            .count();
+        if i > row {
+            tokens.push(run(i - row, '$'));
+            (row, column) = (i, 0);
+        }
+        if j > column {
+            tokens.push(run(j - column, 'b'));
+        }
+        tokens.push(run(length, 'o'));
+        column = j + length;
+        index += length;
+    }
+    tokens.push("!".to_string());
+
+    let mut encoded = format!(
+        "x = {}, y = {}, rule = {rule}\n",
+        pattern.width(),
+        pattern.height()
+    );
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length + token.len() > LINE_LENGTH {
+            encoded.push('\n');
+            line_length = 0;
+        }
+        line_length += token.len();
+        encoded.push_str(&token);
+    }
+    encoded.push('\n');
+    encoded
+}
+
+/// Returned when run length encoded text can't be read
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseRleError(String);
+
+impl fmt::Display for ParseRleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid RLE, {}, expected runs of b and o separated by $ and ending with !",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseRleError {}
+
+/// Reads a run length encoded pattern, see `encode`. Comment lines starting with `#` and
+/// the header are skipped, as is anything after the `!`.
+#[allow(clippy::missing_errors_doc)]
+pub fn decode(text: &str) -> Result<Pattern, ParseRleError> {
+    let body = text.lines().map(str::trim).filter(|line| {
+        !line.starts_with('#') && !line.starts_with("x ") && !line.starts_with("x=")
+    });
+    let mut cells = Vec::new();
+    let (mut row, mut column) = (0, 0);
+    let mut count: Option<usize> = None;
+    for c in body.flat_map(str::chars) {
+        if let Some(digit) = c.to_digit(10) {
+            let digits = count.unwrap_or(0).checked_mul(10);
+            count = digits.and_then(|digits| digits.checked_add(digit as usize));
+            if count.is_none() {
+                return Err(ParseRleError("a run is too long".to_string()));
+            }
+            continue;
+        }
+        let run = count.take().unwrap_or(1);
+        match c {
+            'b' | '.' => column += run,
+            // Anything other than dead counts as alive in patterns with more states
+            'o' | 'A'..='X' => {
+                cells.extend((column..column + run).map(|j| signed((row, j))));
+                column += run;
+            }
+            '$' => (row, column) = (row + run, 0),
+            '!' => return Ok(Pattern::new(cells)),
+            c if c.is_whitespace() => {}
+            c => return Err(ParseRleError(format!("unexpected [{c}]"))),
+        }
+    }
+    Err(ParseRleError("no ! at the end".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let glider: Pattern = ".o./..o/ooo".parse().unwrap();
+        assert_eq!(
+            encode(&glider, Rule::default()),
+            "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+        );
+        let gap: Pattern = "oo..o/...../...../..o..".parse().unwrap();
+        assert_eq!(
+            encode(&gap, "B36/S23".parse().unwrap()),
+            "x = 5, y = 4, rule = B36/S23\n2o2bo3$2bo!\n"
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let glider: Pattern = ".o./..o/ooo".parse().unwrap();
+        let rle = "#N Glider\n#C The smallest spaceship\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!";
+        assert_eq!(decode(rle), Ok(glider.clone()));
+        assert_eq!(decode(&encode(&glider, Rule::default())), Ok(glider));
+        let gap: Pattern = "oo..o/...../...../..o..".parse().unwrap();
+        assert_eq!(decode("2o2b\no3$2bo!junk"), Ok(gap));
+        assert!(decode("3o$2bo").is_err());
+        assert!(decode("3o$2xo!").is_err());
+    }
+
+    #[test]
+    fn test_long_lines_wrap() {
+        let dotted = Pattern::new((0..100).map(|j| (0, j * 2)));
+        let rle = encode(&dotted, Rule::default());
+        let lines: Vec<&str> = rle.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines.iter().all(|line| line.len() <= LINE_LENGTH));
+        assert!(lines[3].ends_with("bo!"));
+    }
+}
@@ -0,0 +1,451 @@
+use super::Census;
+use crate::data::{Coord, Grid, PeriodKind, Rule, Status};
+use rand::{ChaChaRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+pub const DEFAULT_SOUP_SIZE: usize = 16;
+pub const DEFAULT_BOARD_SIZE: usize = 64;
+pub const DEFAULT_GENERATIONS: u64 = 20_000;
+pub const DEFAULT_HISTORY: usize = 4096;
+pub const DEFAULT_RARE_PERIOD: u64 = 4;
+pub const DEFAULT_METHUSELAH: u64 = 5000;
+
+// Spaceships that turn up often enough not to be worth flagging
+const COMMON_SPACESHIPS: [&str; 4] = [
+    "glider",
+    "lightweight spaceship",
+    "middleweight spaceship",
+    "heavyweight spaceship",
+];
+
+/// Runs seeded random soups until they settle, counting what's left and flagging
+/// anything rare.
+///
+/// Each soup is a square of random cells in the middle of an otherwise empty square
+/// board. The board wraps around, so anything that escapes comes back around and might
+/// crash into what's left. The same seed and settings always give the same soup.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Search {
+    rule: Rule,
+    soup_size: usize,
+    board_size: usize,
+    generations: u64,
+    history: usize,
+    rare_period: u64,
+    methuselah: u64,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            rule: Rule::default(),
+            soup_size: DEFAULT_SOUP_SIZE,
+            board_size: DEFAULT_BOARD_SIZE,
+            generations: DEFAULT_GENERATIONS,
+            history: DEFAULT_HISTORY,
+            rare_period: DEFAULT_RARE_PERIOD,
+            methuselah: DEFAULT_METHUSELAH,
+        }
+    }
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub const fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Width and height of the random square each soup starts from
+    #[must_use]
+    pub const fn with_soup_size(mut self, size: usize) -> Self {
+        self.soup_size = size;
+        self
+    }
+
+    /// Width and height of the board soups are run on, which is at least as big as the
+    /// soups themselves
+    #[must_use]
+    pub const fn with_board_size(mut self, size: usize) -> Self {
+        self.board_size = size;
+        self
+    }
+
+    /// Most generations to wait for a soup to settle
+    #[must_use]
+    pub const fn with_generations(mut self, generations: u64) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    /// Generations to look back through for repeats, which limits how long a cycle a
+    /// settled board can be in
+    #[must_use]
+    pub const fn with_history(mut self, generations: usize) -> Self {
+        self.history = generations;
+        self
+    }
+
+    /// Flags oscillators with at least this period
+    #[must_use]
+    pub const fn with_rare_period(mut self, period: u64) -> Self {
+        self.rare_period = period;
+        self
+    }
+
+    /// Flags soups that take at least this many generations to settle
+    #[must_use]
+    pub const fn with_methuselah(mut self, generations: u64) -> Self {
+        self.methuselah = generations;
+        self
+    }
+
+    /// The starting board for a seed
+    pub fn soup(&self, seed: u64) -> Grid {
+        #[allow(clippy::cast_possible_truncation)]
+        let mut rng = ChaChaRng::from_seed(&[seed as u32, (seed >> 32) as u32]);
+        let board_size = self.board_size.max(self.soup_size);
+        let offset = (board_size - self.soup_size) / 2;
+        let soup = offset..offset + self.soup_size;
+        Grid::from_fn(board_size, board_size, |Coord { i, j }| {
+            if soup.contains(i) && soup.contains(j) && rng.gen() {
+                Status::Alive
+            } else {
+                Status::Dead
+            }
+        })
+        .with_rule(self.rule)
+    }
+
+    /// Runs the soup for one seed and takes its census
+    pub fn run_soup(&self, seed: u64) -> Summary {
+        let mut grid = self.soup(seed).with_history(self.history);
+        while grid.periodicity().is_none() && grid.generation() < self.generations {
+            grid.advance();
+        }
+        let mut summary = self.summary(seed..seed + 1);
+        let mut reasons = BTreeSet::new();
+        match grid.periodicity() {
+            None => {
+                reasons.insert(format!(
+                    "didn't settle within {} generations",
+                    self.generations
+                ));
+            }
+            Some(periodicity) if periodicity.start >= self.methuselah => {
+                reasons.insert(format!("settled after {} generations", periodicity.start));
+            }
+            Some(_) => {}
+        }
+        for object in Census::of(&grid).objects() {
+            let label = object.label();
+            *summary.counts.entry(label.clone()).or_default() += 1;
+            let reason = match &object.classification {
+                None => Some(label),
+                Some(classification) => {
+                    let period = classification.period;
+                    match classification.kind {
+                        PeriodKind::Oscillator if period >= self.rare_period => {
+                            Some(format!("{label} (period {period} oscillator)"))
+                        }
+                        PeriodKind::Spaceship
+                            if !object.name.is_some_and(|n| COMMON_SPACESHIPS.contains(&n)) =>
+                        {
+                            Some(format!("{label} (period {period} spaceship)"))
+                        }
+                        _ => None,
+                    }
+                }
+            };
+            reasons.extend(reason);
+        }
+        summary.finds = reasons
+            .into_iter()
+            .map(|reason| Find { seed, reason })
+            .collect();
+        summary
+    }
+
+    /// Runs the soups for a range of seeds, spread across threads
+    pub fn run(&self, seeds: Range<u64>) -> Summary {
+        let start = seeds.start;
+        seeds
+            .into_par_iter()
+            .map(|seed| self.run_soup(seed))
+            .reduce(|| self.summary(start..start), Summary::merge)
+    }
+
+    /// An empty summary for these settings
+    pub fn summary(&self, seeds: Range<u64>) -> Summary {
+        Summary {
+            rule: self.rule,
+            soup_size: self.soup_size,
+            board_size: self.board_size,
+            generations: self.generations,
+            history: self.history,
+            rare_period: self.rare_period,
+            methuselah: self.methuselah,
+            seeds,
+            counts: BTreeMap::new(),
+            finds: Vec::new(),
+        }
+    }
+}
+
+/// Something worth a closer look, along with the seed of the soup it came from
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Find {
+    pub seed: u64,
+    pub reason: String,
+}
+
+/// What a search has found in a range of seeds, which can be saved and added to later
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Summary {
+    pub rule: Rule,
+    pub soup_size: usize,
+    pub board_size: usize,
+    pub generations: u64,
+    pub history: usize,
+    pub rare_period: u64,
+    pub methuselah: u64,
+    pub seeds: Range<u64>,
+    /// How many of each kind of object there were, by `Object::label`
+    pub counts: BTreeMap<String, u64>,
+    pub finds: Vec<Find>,
+}
+
+impl Summary {
+    /// Whether more soups from the given search can be added to this summary, which
+    /// needs every setting that changes what a soup comes out as to be the same
+    pub fn matches(&self, search: &Search) -> bool {
+        search.summary(self.seeds.clone()).settings() == self.settings()
+    }
+
+    const fn settings(&self) -> (Rule, usize, usize, u64, usize, u64, u64) {
+        (
+            self.rule,
+            self.soup_size,
+            self.board_size,
+            self.generations,
+            self.history,
+            self.rare_period,
+            self.methuselah,
+        )
+    }
+
+    /// Adds the results for the seeds straight after these ones
+    #[must_use]
+    pub fn merge(mut self, other: Self) -> Self {
+        if self.seeds.is_empty() {
+            self.seeds = other.seeds;
+        } else if !other.seeds.is_empty() {
+            self.seeds.end = other.seeds.end;
+        }
+        for (label, count) in other.counts {
+            *self.counts.entry(label).or_default() += count;
+        }
+        self.finds.extend(other.finds);
+        self
+    }
+
+    /// Number of soups searched
+    pub fn soups(&self) -> u64 {
+        self.seeds.end - self.seeds.start
+    }
+}
+
+/// Settings and seeds, then counts of each object most common first, then anything
+/// rare, one per line
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "rule {}", self.rule)?;
+        writeln!(f, "soup {}", self.soup_size)?;
+        writeln!(f, "board {}", self.board_size)?;
+        writeln!(f, "generations {}", self.generations)?;
+        writeln!(f, "history {}", self.history)?;
+        writeln!(f, "rare-period {}", self.rare_period)?;
+        writeln!(f, "methuselah {}", self.methuselah)?;
+        writeln!(f, "seeds {}..{}", self.seeds.start, self.seeds.end)?;
+        writeln!(f)?;
+        writeln!(f, "census")?;
+        let mut counts: Vec<_> = self.counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        for (label, count) in counts {
+            writeln!(f, "{count:>10}  {label}")?;
+        }
+        writeln!(f)?;
+        writeln!(f, "rare")?;
+        for Find { seed, reason } in &self.finds {
+            writeln!(f, "{seed:>10}  {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseSummaryError(String);
+
+impl fmt::Display for ParseSummaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid search summary, couldn't read [{}]", self.0)
+    }
+}
+
+impl Error for ParseSummaryError {}
+
+impl FromStr for Summary {
+    type Err = ParseSummaryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        let mut setting = |name: &str| {
+            let line = lines.next().unwrap_or_default();
+            line.strip_prefix(name)
+                .and_then(|value| value.strip_prefix(' '))
+                .map(str::to_string)
+                .ok_or_else(|| ParseSummaryError(line.to_string()))
+        };
+        let rule = setting("rule")?;
+        let soup_size = setting("soup")?;
+        let board_size = setting("board")?;
+        let generations = setting("generations")?;
+        let history = setting("history")?;
+        let rare_period = setting("rare-period")?;
+        let methuselah = setting("methuselah")?;
+        let seeds = setting("seeds")?;
+        let mut summary = Self {
+            rule: rule.parse().map_err(|_| ParseSummaryError(rule))?,
+            soup_size: number(soup_size)?,
+            board_size: number(board_size)?,
+            generations: number(generations)?,
+            history: number(history)?,
+            rare_period: number(rare_period)?,
+            methuselah: number(methuselah)?,
+            seeds: seeds
+                .split_once("..")
+                .and_then(|(start, end)| Some(start.parse().ok()?..end.parse().ok()?))
+                .ok_or(ParseSummaryError(seeds))?,
+            counts: BTreeMap::new(),
+            finds: Vec::new(),
+        };
+        let mut section = "";
+        for line in lines {
+            match line.trim() {
+                "" => {}
+                "census" | "rare" => section = line.trim(),
+                entry => {
+                    let error = || ParseSummaryError(line.to_string());
+                    let (number, rest) = entry.split_once(' ').ok_or_else(error)?;
+                    let (number, rest) = (number.parse().map_err(|_| error())?, rest.trim());
+                    match section {
+                        "census" => {
+                            summary.counts.insert(rest.to_string(), number);
+                        }
+                        "rare" => summary.finds.push(Find {
+                            seed: number,
+                            reason: rest.to_string(),
+                        }),
+                        _ => return Err(error()),
+                    }
+                }
+            }
+        }
+        Ok(summary)
+    }
+}
+
+// Parses a number from a setting line, keeping the value in the error if it isn't one
+fn number<T: FromStr>(value: String) -> Result<T, ParseSummaryError> {
+    value.parse().map_err(|_| ParseSummaryError(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search() -> Search {
+        Search::new()
+            .with_soup_size(8)
+            .with_board_size(32)
+            .with_generations(2000)
+    }
+
+    #[test]
+    fn test_soups_are_seeded() {
+        let search = search();
+        let soup = search.soup(7);
+        assert_eq!((soup.width(), soup.height()), (32, 32));
+        assert!(soup
+            .live_cells()
+            .all(|(i, j)| (12..20).contains(&i) && (12..20).contains(&j)));
+        let same: Vec<_> = search.soup(7).live_cells().collect();
+        assert_eq!(soup.live_cells().collect::<Vec<_>>(), same);
+        let other: Vec<_> = search.soup(8).live_cells().collect();
+        assert_ne!(same, other);
+    }
+
+    #[test]
+    fn test_run_is_the_same_in_parts() {
+        let search = search();
+        let whole = search.run(0..24);
+        assert_eq!(whole.soups(), 24);
+        assert!(!whole.counts.is_empty());
+        let parts = search.run(0..10).merge(search.run(10..24));
+        assert_eq!(parts, whole);
+        let mut sequential = search.summary(0..0);
+        for seed in 0..24 {
+            sequential = sequential.merge(search.run_soup(seed));
+        }
+        assert_eq!(sequential, whole);
+    }
+
+    #[test]
+    fn test_rare_finds() {
+        // Every oscillator counts as rare, and every soup as a methuselah
+        let search = search().with_rare_period(2).with_methuselah(0);
+        let summary = search.run(0..8);
+        assert!(summary.finds.len() >= 8);
+        assert!(summary
+            .finds
+            .iter()
+            .any(|find| find.reason.starts_with("settled after")));
+        assert!(summary
+            .finds
+            .windows(2)
+            .all(|pair| pair[0].seed <= pair[1].seed));
+    }
+
+    #[test]
+    fn test_summary_round_trip() {
+        let search = search();
+        let mut summary = search.run(3..11);
+        summary.finds.push(Find {
+            seed: 5,
+            reason: "aircraft carrier (period 2 oscillator)".to_string(),
+        });
+        let written = summary.to_string();
+        assert!(written.starts_with(
+            "rule B3/S23\nsoup 8\nboard 32\ngenerations 2000\nhistory 4096\n\
+             rare-period 4\nmethuselah 5000\nseeds 3..11\n"
+        ));
+        let read: Summary = written.parse().unwrap();
+        assert_eq!(read, summary);
+        assert!(read.matches(&search));
+        assert!(!read.matches(&search.clone().with_board_size(64)));
+        assert!(!read.matches(&search.clone().with_generations(3000)));
+        assert!(!read.matches(&search.clone().with_history(1024)));
+        assert!(!read.matches(&search.clone().with_rare_period(8)));
+        assert!(!read.matches(&search.with_methuselah(100)));
+        assert!("rule B3/S23\nsoup x".parse::<Summary>().is_err());
+    }
+}
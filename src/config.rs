@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Settings read from a config file.
+///
+/// The format is one `key = value` pair per line. Blank lines and lines starting with `#`
+/// are ignored. Where a setting can also be given on the command line, its key is the
+/// long name of that option.
+#[derive(Debug, Default, Clone)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+/// Returned when a config file can't be parsed
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ConfigError {
+    /// 1-based line number the problem is on
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ConfigError {}
+
+impl Config {
+    /// Reads and parses the config file at the given path
+    #[allow(clippy::missing_errors_doc)]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read config file {}: {e}", path.display()))?;
+        Ok(Self::parse(&contents)
+            .map_err(|e| format!("Invalid config file {}: {e}", path.display()))?)
+    }
+
+    /// Parses config file contents
+    #[allow(clippy::missing_errors_doc)]
+    pub fn parse(contents: &str) -> Result<Self, ConfigError> {
+        let mut values = HashMap::new();
+        for (idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |message: String| ConfigError {
+                line: idx + 1,
+                message,
+            };
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| err(format!("expected `key = value`, got [{line}]")))?;
+            let (key, value) = (key.trim(), value.trim());
+            if key.is_empty() {
+                return Err(err("missing key".to_string()));
+            }
+            if values.insert(key.to_string(), value.to_string()).is_some() {
+                return Err(err(format!("[{key}] is set more than once")));
+            }
+        }
+        Ok(Self { values })
+    }
+
+    /// Checks that every key in this config is one of the given ones, so that typos
+    /// don't go unnoticed
+    #[allow(clippy::missing_errors_doc)]
+    pub fn check_keys(&self, known_keys: &[&str]) -> Result<(), String> {
+        let mut unknown: Vec<_> = self
+            .values
+            .keys()
+            .filter(|key| !known_keys.contains(&key.as_str()))
+            .map(String::as_str)
+            .collect();
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            unknown.sort_unstable();
+            Err(format!(
+                "Unknown config keys [{}], expected any of {}",
+                unknown.join(", "),
+                known_keys.join(", ")
+            ))
+        }
+    }
+
+    /// Returns the value set for a key, if any
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config =
+            Config::parse("# A comment\n\ntheme = dark\n  alive=#ff0000  \nempty =\n").unwrap();
+        assert_eq!(config.get("theme"), Some("dark"));
+        assert_eq!(config.get("alive"), Some("#ff0000"));
+        assert_eq!(config.get("empty"), Some(""));
+        assert_eq!(config.get("dead"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Config::parse("theme = dark\nnonsense").unwrap_err().line, 2);
+        assert_eq!(Config::parse("= dark").unwrap_err().line, 1);
+        assert_eq!(
+            Config::parse("theme = dark\n\ntheme = light")
+                .unwrap_err()
+                .line,
+            3
+        );
+    }
+
+    #[test]
+    fn test_check_keys() {
+        let config = Config::parse("theme = dark\nalive = #fff\nb = 1").unwrap();
+        assert!(config.check_keys(&["theme", "alive", "b"]).is_ok());
+        let err = config.check_keys(&["theme"]).unwrap_err();
+        assert!(err.contains("[alive, b]"));
+    }
+}
@@ -0,0 +1,137 @@
+use super::cell::Cell;
+use super::grid::{Grid, GridIdx};
+use rayon::prelude::*;
+
+/// Age given to cells that have been dead for as long as we've been tracking them
+pub const NEVER_ALIVE: u16 = u16::MAX;
+
+/// Tracks how long each cell of a grid has been in its current state.
+///
+/// For live cells that's the number of generations survived since the cell was born, and
+/// for dead cells it's the number of generations since the cell died. Both saturate
+/// rather than wrap around.
+#[derive(Debug, Clone)]
+pub struct Ages {
+    ages: Vec<u16>,
+    alive: Vec<bool>,
+}
+
+impl Ages {
+    /// Starts tracking a grid. Cells that are alive now are treated as newly born, and
+    /// dead ones as having never been alive.
+    pub fn new(grid: &Grid) -> Self {
+        let alive: Vec<bool> = (0..grid.area()).map(|idx| is_alive(grid, idx)).collect();
+        let ages = alive
+            .iter()
+            .map(|&alive| if alive { 0 } else { NEVER_ALIVE })
+            .collect();
+        Self { ages, alive }
+    }
+
+    /// Brings ages up to date with the grid. Should be called after every advance so
+    /// that no generation is missed.
+    pub fn update(&mut self, grid: &Grid) {
+        self.catch_up(grid, 1);
+    }
+
+    /// Brings ages up to date after cells were edited rather than the grid being advanced,
+    /// so cells that weren't changed keep the ages they had
+    pub fn update_edited(&mut self, grid: &Grid) {
+        self.catch_up(grid, 0);
+    }
+
+    // Ages cells that haven't changed by `generations`, and starts the others over
+    fn catch_up(&mut self, grid: &Grid, generations: u16) {
+        let op = |(idx, (age, was_alive)): (usize, (&mut u16, &mut bool))| {
+            let alive = is_alive(grid, idx);
+            *age = if alive == *was_alive {
+                age.saturating_add(generations)
+            } else {
+                // Cells that were just born start at 0, ones that just died at 1
+                u16::from(!alive)
+            };
+            *was_alive = alive;
+        };
+        if grid.area_requires_bool() {
+            self.ages
+                .par_iter_mut()
+                .zip(self.alive.par_iter_mut())
+                .enumerate()
+                .for_each(op);
+        } else {
+            for pair in self.ages.iter_mut().zip(self.alive.iter_mut()).enumerate() {
+                op(pair);
+            }
+        }
+    }
+
+    /// Returns the age of the cell at the given index, see `Ages` for what that means
+    pub fn get_idx(&self, &GridIdx(idx): &GridIdx) -> Option<u16> {
+        self.ages.get(idx).copied()
+    }
+
+    /// Ages of every cell, in the same order as the grid's flattened cells
+    pub fn as_slice(&self) -> &[u16] {
+        &self.ages
+    }
+}
+
+fn is_alive(grid: &Grid, idx: usize) -> bool {
+    grid.get_idx(&GridIdx(idx)).is_some_and(Cell::alive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Coord, Status};
+
+    #[test]
+    fn test_ages_new() {
+        let grid = Grid::new(5, 5);
+        let ages = Ages::new(&grid);
+        for idx in 0..grid.area() {
+            let grid_idx = GridIdx(idx);
+            let expected = if grid.get_idx(&grid_idx).unwrap().alive() {
+                0
+            } else {
+                NEVER_ALIVE
+            };
+            assert_eq!(ages.get_idx(&grid_idx), Some(expected));
+        }
+        assert_eq!(ages.get_idx(&GridIdx(grid.area())), None);
+    }
+
+    #[test]
+    fn test_ages_update() {
+        let mut grid = Grid::new(8, 8);
+        let mut ages = Ages::new(&grid);
+        for _ in 0..3 {
+            let before: Vec<bool> = (0..grid.area()).map(|idx| is_alive(&grid, idx)).collect();
+            let previous = ages.clone();
+            grid.advance();
+            ages.update(&grid);
+            for (idx, &was_alive) in before.iter().enumerate() {
+                let grid_idx = GridIdx(idx);
+                let previous_age = previous.get_idx(&grid_idx).unwrap();
+                let age = ages.get_idx(&grid_idx).unwrap();
+                match (was_alive, is_alive(&grid, idx)) {
+                    (true, false) => assert_eq!(age, 1),
+                    (false, true) => assert_eq!(age, 0),
+                    _ => assert_eq!(age, previous_age.saturating_add(1)),
+                }
+            }
+        }
+        assert_eq!(ages.as_slice().len(), grid.area());
+    }
+
+    #[test]
+    fn test_ages_update_edited() {
+        let mut grid = Grid::empty(3, 1);
+        grid.set(&Coord { i: 0, j: 0 }, Status::Alive);
+        let mut ages = Ages::new(&grid);
+        ages.update(&grid);
+        grid.set(&Coord { i: 0, j: 1 }, Status::Alive);
+        ages.update_edited(&grid);
+        assert_eq!(ages.as_slice(), [1, 0, NEVER_ALIVE]);
+    }
+}
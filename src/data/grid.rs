@@ -1,7 +1,14 @@
 use super::cell::{Cell, Status};
+use super::period::{Fingerprint, History, Periodicity};
+use super::rule::Rule;
+use super::stats::{BoundingBox, Stats};
+use super::topology::Topology;
+use super::transform::{Combine, Transform};
 use rand;
 use rand::Rng;
 use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::mem;
 
 const PAR_THRESHOLD_AREA: usize = 250_000;
@@ -31,6 +38,14 @@ pub struct Grid {
     area_requires_bool: bool,
     // Cache of where the neighbours are for each point
     neighbours: Vec<[GridIdx; 8]>,
+    // Number of times this grid has been advanced
+    generation: u64,
+    rule: Rule,
+    topology: Topology,
+    // Figures for the current generation, kept up to date as the grid changes
+    stats: Stats,
+    // Recent generations, for noticing when the grid starts repeating itself
+    history: History,
 }
 
 #[derive(PartialEq, Eq, Debug, PartialOrd, Ord, Clone)]
@@ -43,31 +58,46 @@ impl Grid {
     /// Creates a grid with the given width and height
     pub fn new(width: usize, height: usize) -> Self {
         let mut rng = rand::thread_rng();
+        Self::from_fn(width, height, |_| {
+            if rng.gen() {
+                Status::Alive
+            } else {
+                Status::Dead
+            }
+        })
+    }
+
+    /// Creates a grid with the given width and height where every cell is dead
+    pub fn empty(width: usize, height: usize) -> Self {
+        Self::from_fn(width, height, |_| Status::Dead)
+    }
+
+    /// Creates a grid with the given width and height, where `status` decides what each
+    /// cell starts off as
+    pub fn from_fn<F>(width: usize, height: usize, mut status: F) -> Self
+    where
+        F: FnMut(&Coord) -> Status,
+    {
         // Grid is a matrix with {height} rows and {width} columns, addressed
         // via (i, j) (row, column) convention. Used for finding neightbours because it's
         // just an easier mental model to work with for that problem. It gets flattened later.
         let mut grid = Vec::with_capacity(height);
-        for _ in 0..height {
+        for i in 0..height {
             let mut row = Vec::with_capacity(width);
-            for _ in 0..width {
-                let status = if rng.gen() {
-                    Status::Alive
-                } else {
-                    Status::Dead
-                };
-                let cell = Cell(status);
-                row.push(cell);
+            for j in 0..width {
+                row.push(Cell(status(&Coord { i, j })));
             }
             grid.push(row);
         }
 
         let max_i = if height == 0 { 0 } else { height - 1 };
         let max_j = if width == 0 { 0 } else { width - 1 };
-        let neighbours = neighbours(max_i, max_j, &grid);
+        let neighbours = neighbours(max_i, max_j, Topology::default());
         let cells: Vec<Cell> = grid.into_iter().flatten().collect();
         let scratchpad_cells = cells.clone();
         let area = width * height;
         let area_requires_bool = area >= PAR_THRESHOLD_AREA;
+        let population = cells.iter().filter(|cell| cell.alive()).count();
         Self {
             cells,
             scratchpad_cells,
@@ -76,6 +106,265 @@ impl Grid {
             area,
             area_requires_bool,
             neighbours,
+            generation: 0,
+            rule: Rule::default(),
+            topology: Topology::default(),
+            stats: Stats {
+                population,
+                ..Stats::default()
+            },
+            history: History::default(),
+        }
+    }
+
+    /// Uses the given rule instead of Conway's when advancing this grid
+    #[must_use]
+    pub const fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Uses the given topology at the edges instead of wrapping around
+    #[must_use]
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self.neighbours = neighbours(self.max_i, self.max_j, topology);
+        self
+    }
+
+    /// Remembers up to the given number of generations from now on, so that
+    /// `periodicity` can say when the grid starts repeating itself
+    #[must_use]
+    pub fn with_history(mut self, generations: usize) -> Self {
+        self.history = History::new(generations);
+        self.history.record(self.fingerprint());
+        self
+    }
+
+    /// The cycle this grid has fallen into, if it has been keeping a history and has
+    /// come back to a generation it still remembers
+    pub const fn periodicity(&self) -> Option<&Periodicity> {
+        self.history.periodicity()
+    }
+
+    /// Hashes the live cells relative to the top left of the smallest rectangle around
+    /// them, see `Fingerprint`
+    pub fn fingerprint(&self) -> Fingerprint {
+        let origin = self
+            .bounding_box()
+            .map_or((0, 0), |bounds| (bounds.min_i, bounds.min_j));
+        let mut hasher = DefaultHasher::new();
+        for (i, j) in self.live_cells() {
+            (i - origin.0, j - origin.1).hash(&mut hasher);
+        }
+        Fingerprint {
+            generation: self.generation,
+            hash: hasher.finish(),
+            origin,
+            population: self.population(),
+        }
+    }
+
+    /// Number of live cells
+    pub const fn population(&self) -> usize {
+        self.stats.population
+    }
+
+    /// Figures for the current generation, including how many cells were born and died
+    /// getting to it. Editing cells with `set` counts towards these too.
+    pub const fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// The smallest rectangle containing every live cell, or None if there aren't any
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.live_cells().fold(None, |bounds, (i, j)| {
+            Some(match bounds {
+                None => BoundingBox {
+                    min_i: i,
+                    min_j: j,
+                    max_i: i,
+                    max_j: j,
+                },
+                Some(BoundingBox {
+                    min_i,
+                    min_j,
+                    max_i,
+                    max_j,
+                }) => BoundingBox {
+                    min_i: min_i.min(i),
+                    min_j: min_j.min(j),
+                    max_i: max_i.max(i),
+                    max_j: max_j.max(j),
+                },
+            })
+        })
+    }
+
+    /// (i, j) of every live cell, row by row
+    pub fn live_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let width = self.width();
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.alive())
+            .map(move |(idx, _)| (idx / width, idx % width))
+    }
+
+    /// The rule this grid advances by
+    pub const fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// What happens at this grid's edges
+    pub const fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Rotates or reflects the cells in a region, keeping its top left corner where it
+    /// is. Cells that end up past the grid's edges wrap around or get dropped, depending
+    /// on its topology.
+    pub fn transform(&mut self, region: &BoundingBox, transform: Transform) {
+        let size = (signed(region.height()), signed(region.width()));
+        self.move_region(region, |i, j| {
+            let (i, j) = transform.apply_within((i, j), size);
+            (i + signed(region.min_i), j + signed(region.min_j))
+        });
+    }
+
+    /// Moves the cells in a region by a number of rows and columns, which can be
+    /// negative. Cells that end up past the grid's edges wrap around or get dropped,
+    /// depending on its topology.
+    pub fn translate(&mut self, region: &BoundingBox, (rows, columns): (isize, isize)) {
+        self.move_region(region, |i, j| {
+            (
+                i + signed(region.min_i) + rows,
+                j + signed(region.min_j) + columns,
+            )
+        });
+    }
+
+    /// A new grid with just the cells in a region, e.g. `bounding_box` to trim off empty
+    /// space around a pattern. Its rule and topology are the same as this one's.
+    #[must_use]
+    pub fn crop(&self, region: &BoundingBox) -> Self {
+        Self::from_fn(region.width(), region.height(), |&Coord { i, j }| {
+            let inside = Coord {
+                i: region.min_i + i,
+                j: region.min_j + j,
+            };
+            self.status(&inside)
+        })
+        .with_rule(self.rule)
+        .with_topology(self.topology)
+    }
+
+    /// Combines another grid's cells with this one's, cell by cell, with the other grid's
+    /// top left corner at `at`. Cells outside the other grid count as dead in it.
+    pub fn combine(&mut self, other: &Self, at: &Coord, combine: Combine) {
+        let mut covered = vec![false; self.area];
+        for (i, j) in other.live_cells() {
+            let position = (signed(at.i + i), signed(at.j + j));
+            if let Some(coord) = self.topology.wrap(position, self.height(), self.width()) {
+                covered[self.width() * coord.i + coord.j] = true;
+            }
+        }
+        let alive: Vec<bool> = self
+            .cells
+            .iter()
+            .zip(covered)
+            .map(|(cell, covered)| combine.apply(cell.alive(), covered))
+            .collect();
+        self.replace(&alive);
+    }
+
+    /// Kills every cell in a region
+    pub fn clear(&mut self, region: &BoundingBox) {
+        self.fill(region, || false);
+    }
+
+    /// Brings each cell in a region to life or kills it at random, with even odds like `new`
+    pub fn randomise(&mut self, region: &BoundingBox) {
+        let mut rng = rand::thread_rng();
+        self.fill(region, || rng.gen());
+    }
+
+    /// Sets every cell at once, in the same order as `get_idx`, and puts the grid on the
+    /// given generation, e.g. to take it back to how it was earlier
+    pub fn restore(&mut self, alive: &[bool], generation: u64) {
+        self.replace(alive);
+        self.generation = generation;
+        self.stats.generation = generation;
+    }
+
+    // Sets every cell in a region to whatever `alive` says
+    fn fill<F>(&mut self, region: &BoundingBox, mut alive: F)
+    where
+        F: FnMut() -> bool,
+    {
+        // max_i and max_j are 0 even without any cells to fill
+        if self.area == 0 {
+            return;
+        }
+        let width = self.width();
+        let mut cells: Vec<bool> = self.cells.iter().map(Cell::alive).collect();
+        for i in region.min_i..=region.max_i.min(self.max_i) {
+            for j in region.min_j..=region.max_j.min(self.max_j) {
+                cells[width * i + j] = alive();
+            }
+        }
+        self.replace(&cells);
+    }
+
+    // Lifts the live cells out of a region, then puts each one back down wherever
+    // `to` says, given its row and column within the region
+    fn move_region<F>(&mut self, region: &BoundingBox, to: F)
+    where
+        F: Fn(isize, isize) -> (isize, isize),
+    {
+        let inside = |&(i, j): &(usize, usize)| {
+            (region.min_i..=region.max_i).contains(&i) && (region.min_j..=region.max_j).contains(&j)
+        };
+        let mut alive: Vec<bool> = self.cells.iter().map(Cell::alive).collect();
+        let moving: Vec<(usize, usize)> = self.live_cells().filter(inside).collect();
+        for &(i, j) in &moving {
+            alive[self.width() * i + j] = false;
+        }
+        for (i, j) in moving {
+            let position = to(signed(i - region.min_i), signed(j - region.min_j));
+            if let Some(coord) = self.topology.wrap(position, self.height(), self.width()) {
+                alive[self.width() * coord.i + coord.j] = true;
+            }
+        }
+        self.replace(&alive);
+    }
+
+    // Sets every cell at once, counting the changes like `set` does
+    fn replace(&mut self, alive: &[bool]) {
+        for (cell, &alive) in self.cells.iter_mut().zip(alive) {
+            match (cell.alive(), alive) {
+                (false, true) => {
+                    self.stats.population += 1;
+                    self.stats.births += 1;
+                }
+                (true, false) => {
+                    self.stats.population -= 1;
+                    self.stats.deaths += 1;
+                }
+                _ => continue,
+            }
+            cell.update(if alive { Status::Alive } else { Status::Dead });
+        }
+        if self.history.is_enabled() {
+            self.history.clear();
+            self.history.record(self.fingerprint());
+        }
+    }
+
+    fn status(&self, coord: &Coord) -> Status {
+        match self.to_grid_idx(coord) {
+            Some(GridIdx(idx)) if self.cells[idx].alive() => Status::Alive,
+            _ => Status::Dead,
         }
     }
 
@@ -87,9 +376,35 @@ impl Grid {
         self.cells.get(idx)
     }
 
-    // TODO delete if not used
+    /// Sets the status of the cell at the given coordinates, returning false if they
+    /// are outside the grid
+    pub fn set(&mut self, coord: &Coord, status: Status) -> bool {
+        match self.to_grid_idx(coord) {
+            Some(GridIdx(idx)) => {
+                match (self.cells[idx].alive(), status == Status::Alive) {
+                    (false, true) => {
+                        self.stats.population += 1;
+                        self.stats.births += 1;
+                    }
+                    (true, false) => {
+                        self.stats.population -= 1;
+                        self.stats.deaths += 1;
+                    }
+                    _ => {}
+                }
+                self.cells[idx].update(status);
+                if self.history.is_enabled() {
+                    self.history.clear();
+                    self.history.record(self.fingerprint());
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
     pub const fn to_grid_idx(&self, &Coord { i, j }: &Coord) -> Option<GridIdx> {
-        if i <= self.max_i && j <= self.max_j {
+        if self.area > 0 && i <= self.max_i && j <= self.max_j {
             Some(GridIdx(self.width() * i + j))
         } else {
             None
@@ -111,12 +426,21 @@ impl Grid {
         rows
     }
 
+    // A grid with no cells has no rows or columns, even though max_i and max_j are 0
     pub const fn height(&self) -> usize {
-        self.max_i + 1
+        if self.area == 0 {
+            0
+        } else {
+            self.max_i + 1
+        }
     }
 
     pub const fn width(&self) -> usize {
-        self.max_j + 1
+        if self.area == 0 {
+            0
+        } else {
+            self.max_j + 1
+        }
     }
 
     pub const fn area(&self) -> usize {
@@ -127,13 +451,21 @@ impl Grid {
         self.area_requires_bool
     }
 
-    pub fn advance(&mut self) {
-        {
+    /// How many times this grid has been advanced since it was created
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Moves the grid on a generation, returning its figures for the new one, see `stats`
+    pub fn advance(&mut self) -> Stats {
+        let (births, deaths) = {
             let neighbours = &self.neighbours;
             let last_gen = &self.cells;
             let area_requires_par = self.area_requires_bool();
+            let rule = &self.rule;
             let cells = &mut self.scratchpad_cells;
-            let cell_op = |(i, cell): (usize, &mut Cell)| {
+            // Works out a cell's next status, returning whether it was born and whether it died
+            let cell_op = |(i, cell): (usize, &mut Cell)| -> (usize, usize) {
                 if let Some(neighbours_vec) = neighbours.get(i) {
                     let alives = neighbours_vec.iter().fold(0, |acc, &GridIdx(idx)| {
                         if let Some(last_gen_status) = last_gen.get(idx) {
@@ -147,90 +479,88 @@ impl Grid {
                         }
                     });
                     if let Some(last_gen_cell) = last_gen.get(i) {
-                        let next_status = last_gen_cell.next_status(alives);
+                        let next_status = rule.next_status(&last_gen_cell.0, alives);
+                        let change = match (&last_gen_cell.0, &next_status) {
+                            (Status::Dead, Status::Alive) => (1, 0),
+                            (Status::Alive, Status::Dead) => (0, 1),
+                            _ => (0, 0),
+                        };
                         cell.update(next_status);
+                        return change;
                     }
                 }
+                (0, 0)
             };
+            let sum = |(births, deaths), (born, died)| (births + born, deaths + died);
             if area_requires_par {
-                cells.par_iter_mut().enumerate().for_each(cell_op);
+                cells
+                    .par_iter_mut()
+                    .enumerate()
+                    .map(cell_op)
+                    .reduce(|| (0, 0), sum)
             } else {
-                for (i, cell) in cells.iter_mut().enumerate() {
-                    cell_op((i, cell));
-                }
+                cells.iter_mut().enumerate().map(cell_op).fold((0, 0), sum)
             }
-        }
+        };
         mem::swap(&mut self.cells, &mut self.scratchpad_cells);
+        self.generation += 1;
+        self.stats = Stats {
+            generation: self.generation,
+            population: self.stats.population + births - deaths,
+            births,
+            deaths,
+        };
+        if self.history.is_enabled() {
+            let fingerprint = self.fingerprint();
+            self.history.record(fingerprint);
+        }
+        self.stats
     }
 }
 
-fn neighbours(max_i: usize, max_j: usize, cells: &[Vec<Cell>]) -> Vec<[GridIdx; 8]> {
+fn neighbours(max_i: usize, max_j: usize, topology: Topology) -> Vec<[GridIdx; 8]> {
     let mut v = Vec::with_capacity((max_i + 1) * (max_j + 1));
-    for (i, row) in cells.iter().enumerate() {
-        for (j, _) in row.iter().enumerate() {
-            let coord = Coord { i, j };
-            v.push(neighbour_coords(max_i, max_j, &coord));
+    for i in 0..=max_i {
+        for j in 0..=max_j {
+            v.push(neighbour_coords(max_i, max_j, topology, &Coord { i, j }));
         }
     }
     v
 }
 
-fn neighbour_coords(max_i: usize, max_j: usize, coord: &Coord) -> [GridIdx; 8] {
-    let width = max_j + 1;
-    let Coord { i, j } = *coord;
-    let to_grid_idx = |Coord { i, j }: Coord| GridIdx(width * i + j);
-
-    let i_up = match i {
-        0 => max_i,
-        _ => i - 1,
-    };
-
-    let i_down = match i {
-        _ if i == max_i => 0,
-        _ => i + 1,
-    };
-
-    let j_left = match j {
-        0 => max_j,
-        _ => j - 1,
-    };
-    let j_right = match j {
-        _ if j == max_j => 0,
-        _ => j + 1,
-    };
-
-    let north = Coord { i: i_up, j };
-    let north_east = Coord {
-        i: i_up,
-        j: j_right,
-    };
-    let east = Coord { i, j: j_right };
-    let south_east = Coord {
-        i: i_down,
-        j: j_right,
-    };
-    let south = Coord { i: i_down, j };
-    let south_west = Coord {
-        i: i_down,
-        j: j_left,
-    };
-    let west = Coord { i, j: j_left };
-    let north_west = Coord { i: i_up, j: j_left };
-    [
-        to_grid_idx(north),
-        to_grid_idx(north_east),
-        to_grid_idx(east),
-        to_grid_idx(south_east),
-        to_grid_idx(south),
-        to_grid_idx(south_west),
-        to_grid_idx(west),
-        to_grid_idx(north_west),
-    ]
+// Clockwise from north
+const NEIGHBOUR_OFFSETS: [(isize, isize); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+// Neighbours off the edge of a plane point past the end of the grid, where there aren't
+// any cells to count as alive
+fn neighbour_coords(max_i: usize, max_j: usize, topology: Topology, coord: &Coord) -> [GridIdx; 8] {
+    let (height, width) = (max_i + 1, max_j + 1);
+    let (i, j) = (signed(coord.i), signed(coord.j));
+    NEIGHBOUR_OFFSETS.map(|(di, dj)| {
+        topology
+            .wrap((i + di, j + dj), height, width)
+            .map_or(GridIdx(usize::MAX), |Coord { i, j }| GridIdx(width * i + j))
+    })
+}
+
+#[allow(clippy::cast_possible_wrap)]
+const fn signed(n: usize) -> isize {
+    n as isize
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::{BoundingBox, PeriodKind, Stats};
 
     #[test]
     fn test_grid_new() {
@@ -239,6 +569,40 @@ mod tests {
         assert_eq!(grid.cells()[0].len(), 10);
     }
 
+    #[test]
+    fn test_empty_grid() {
+        for (width, height) in [(0, 0), (5, 0), (0, 5)] {
+            let grid = Grid::empty(width, height);
+            assert_eq!((grid.width(), grid.height()), (0, 0));
+            assert!(grid.cells().is_empty());
+            assert_eq!(grid.to_grid_idx(&Coord { i: 0, j: 0 }), None);
+            assert_eq!(grid.bounding_box(), None);
+        }
+    }
+
+    #[test]
+    fn test_grid_from_fn_and_set() {
+        let mut grid = Grid::from_fn(
+            4,
+            3,
+            |&Coord { i, j }| {
+                if i == j {
+                    Status::Alive
+                } else {
+                    Status::Dead
+                }
+            },
+        );
+        assert_eq!(grid.population(), 3);
+        assert!(grid.set(&Coord { i: 2, j: 3 }, Status::Alive));
+        assert!(grid.set(&Coord { i: 0, j: 0 }, Status::Dead));
+        assert!(!grid.set(&Coord { i: 3, j: 0 }, Status::Alive));
+        let idx = grid.to_grid_idx(&Coord { i: 2, j: 3 }).unwrap();
+        assert!(grid.get_idx(&idx).unwrap().alive());
+        assert_eq!(grid.population(), 3);
+        assert_eq!(Grid::empty(4, 3).population(), 0);
+    }
+
     #[test]
     fn test_neighbour_coords() {
         let grid = Grid::new(3, 3);
@@ -249,7 +613,7 @@ mod tests {
          * [ (1,0) (1,1) (1,2) ]
          * [ (2,0) (2,1) (2,2) ]
          */
-        let n0 = neighbour_coords(max_i, max_j, &Coord { i: 0, j: 0 });
+        let n0 = neighbour_coords(max_i, max_j, Topology::Torus, &Coord { i: 0, j: 0 });
         assert_eq!(n0[0], grid.to_grid_idx(&Coord { i: 2, j: 0 }).unwrap()); // N
         assert_eq!(n0[1], grid.to_grid_idx(&Coord { i: 2, j: 1 }).unwrap()); // NE
         assert_eq!(n0[2], grid.to_grid_idx(&Coord { i: 0, j: 1 }).unwrap()); // E
@@ -258,7 +622,7 @@ mod tests {
         assert_eq!(n0[5], grid.to_grid_idx(&Coord { i: 1, j: 2 }).unwrap()); // SW
         assert_eq!(n0[6], grid.to_grid_idx(&Coord { i: 0, j: 2 }).unwrap()); // W
         assert_eq!(n0[7], grid.to_grid_idx(&Coord { i: 2, j: 2 }).unwrap()); // NW
-        let n1 = neighbour_coords(max_i, max_j, &Coord { i: 1, j: 1 });
+        let n1 = neighbour_coords(max_i, max_j, Topology::Torus, &Coord { i: 1, j: 1 });
         assert_eq!(n1[0], grid.to_grid_idx(&Coord { i: 0, j: 1 }).unwrap()); // N
         assert_eq!(n1[1], grid.to_grid_idx(&Coord { i: 0, j: 2 }).unwrap()); // NE
         assert_eq!(n1[2], grid.to_grid_idx(&Coord { i: 1, j: 2 }).unwrap()); // E
@@ -267,7 +631,7 @@ mod tests {
         assert_eq!(n1[5], grid.to_grid_idx(&Coord { i: 2, j: 0 }).unwrap()); // SW
         assert_eq!(n1[6], grid.to_grid_idx(&Coord { i: 1, j: 0 }).unwrap()); // W
         assert_eq!(n1[7], grid.to_grid_idx(&Coord { i: 0, j: 0 }).unwrap()); // NW
-        let n2 = neighbour_coords(max_i, max_j, &Coord { i: 2, j: 2 });
+        let n2 = neighbour_coords(max_i, max_j, Topology::Torus, &Coord { i: 2, j: 2 });
         assert_eq!(n2[0], grid.to_grid_idx(&Coord { i: 1, j: 2 }).unwrap()); // N
         assert_eq!(n2[1], grid.to_grid_idx(&Coord { i: 1, j: 0 }).unwrap()); // NE
         assert_eq!(n2[2], grid.to_grid_idx(&Coord { i: 2, j: 0 }).unwrap()); // E
@@ -278,6 +642,65 @@ mod tests {
         assert_eq!(n2[7], grid.to_grid_idx(&Coord { i: 1, j: 1 }).unwrap()); // NW
     }
 
+    fn grid_with(width: usize, height: usize, live: &[(usize, usize)]) -> Grid {
+        Grid::from_fn(width, height, |&Coord { i, j }| {
+            if live.contains(&(i, j)) {
+                Status::Alive
+            } else {
+                Status::Dead
+            }
+        })
+    }
+
+    fn periodicity_after(mut grid: Grid, generations: u64) -> Option<Periodicity> {
+        for _ in 0..generations {
+            grid.advance();
+        }
+        grid.periodicity().copied()
+    }
+
+    #[test]
+    fn test_periodicity() {
+        let block = grid_with(6, 6, &[(2, 2), (2, 3), (3, 2), (3, 3)]).with_history(16);
+        let still = periodicity_after(block, 1).unwrap();
+        assert_eq!((still.start, still.period), (0, 1));
+        assert_eq!(still.kind(), PeriodKind::StillLife);
+
+        let blinker = grid_with(5, 5, &[(2, 1), (2, 2), (2, 3)]).with_history(16);
+        assert_eq!(periodicity_after(blinker, 1), None);
+        let blinker = grid_with(5, 5, &[(2, 1), (2, 2), (2, 3)]).with_history(16);
+        let oscillator = periodicity_after(blinker, 2).unwrap();
+        assert_eq!((oscillator.start, oscillator.period), (0, 2));
+        assert_eq!(oscillator.kind(), PeriodKind::Oscillator);
+
+        // Takes a generation to die out, and stays dead from then on
+        let lonely = grid_with(5, 5, &[(2, 2)]).with_history(16);
+        let extinct = periodicity_after(lonely, 2).unwrap();
+        assert_eq!((extinct.start, extinct.period), (1, 1));
+        assert_eq!(extinct.kind(), PeriodKind::Extinct);
+    }
+
+    #[test]
+    fn test_periodicity_of_spaceships() {
+        let glider = grid_with(20, 20, &[(5, 6), (6, 7), (7, 5), (7, 6), (7, 7)]);
+        let spaceship = periodicity_after(glider.with_history(16), 4).unwrap();
+        assert_eq!((spaceship.start, spaceship.period), (0, 4));
+        assert_eq!(spaceship.displacement, (1, 1));
+        assert_eq!(spaceship.kind(), PeriodKind::Spaceship);
+        // Without a history nothing gets noticed
+        let glider = grid_with(20, 20, &[(5, 6), (6, 7), (7, 5), (7, 6), (7, 7)]);
+        assert_eq!(periodicity_after(glider, 8), None);
+    }
+
+    #[test]
+    fn test_set_resets_history() {
+        let mut grid = grid_with(6, 6, &[(2, 2), (2, 3), (3, 2), (3, 3)]).with_history(16);
+        grid.advance();
+        assert!(grid.periodicity().is_some());
+        grid.set(&Coord { i: 0, j: 0 }, Status::Alive);
+        assert_eq!(grid.periodicity(), None);
+    }
+
     // Just a test to make sure advance can run for a large number of iterations
     #[test]
     fn test_advance() {
@@ -286,29 +709,75 @@ mod tests {
         for _ in 0..100 {
             grid.advance();
         }
+        assert_eq!(grid.generation(), 100);
     }
 
     #[test]
-    fn test_alive_count() {
-        let mut grid = Grid::new(3, 3);
-        let new_cells = vec![
-            vec![
-                Cell(Status::Alive),
-                Cell(Status::Alive),
-                Cell(Status::Alive),
-            ],
-            vec![Cell(Status::Alive), Cell(Status::Dead), Cell(Status::Alive)],
-            vec![
-                Cell(Status::Alive),
-                Cell(Status::Alive),
-                Cell(Status::Alive),
-            ],
-        ]
-        .into_iter()
-        .flat_map(|v| v)
-        .collect();
-        grid.cells = new_cells;
-        assert_eq!(alive_count(&grid), 8)
+    fn test_advance_with_rule() {
+        let mut grid = Grid::new(10, 10).with_rule(Rule::new(&[], &[]));
+        assert_eq!(grid.rule(), &Rule::new(&[], &[]));
+        grid.advance();
+        assert_eq!(grid.population(), 0);
+    }
+
+    #[test]
+    fn test_stats() {
+        // A blinker, standing up
+        let mut grid = grid_with(5, 5, &[(1, 2), (2, 2), (3, 2)]);
+        assert_eq!(
+            grid.stats(),
+            Stats {
+                generation: 0,
+                population: 3,
+                births: 0,
+                deaths: 0,
+            }
+        );
+        let stats = grid.advance();
+        assert_eq!(stats, grid.stats());
+        assert_eq!(
+            stats,
+            Stats {
+                generation: 1,
+                population: 3,
+                births: 2,
+                deaths: 2,
+            }
+        );
+        assert_eq!(stats.changed(), 4);
+        grid.set(&Coord { i: 0, j: 0 }, Status::Alive);
+        grid.set(&Coord { i: 2, j: 2 }, Status::Dead);
+        assert_eq!(grid.population(), 3);
+        assert_eq!((grid.stats().births, grid.stats().deaths), (3, 3));
+    }
+
+    #[test]
+    fn test_stats_in_parallel() {
+        let mut grid = Grid::new(500, 500);
+        assert!(grid.area_requires_bool());
+        for _ in 0..3 {
+            let before = grid.population();
+            let stats = grid.advance();
+            assert_eq!(stats.population, before + stats.births - stats.deaths);
+            assert_eq!(stats.population, alive_cells(&grid).len());
+        }
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        assert_eq!(Grid::empty(5, 5).bounding_box(), None);
+        let grid = grid_with(8, 6, &[(1, 4), (3, 2), (4, 5)]);
+        let bounds = grid.bounding_box().unwrap();
+        assert_eq!(
+            bounds,
+            BoundingBox {
+                min_i: 1,
+                min_j: 2,
+                max_i: 4,
+                max_j: 5,
+            }
+        );
+        assert_eq!((bounds.width(), bounds.height()), (4, 4));
     }
 
     #[test]
@@ -328,7 +797,7 @@ mod tests {
             ],
         ]
         .into_iter()
-        .flat_map(|v| v)
+        .flatten()
         .collect();
         grid.cells = new_cells;
         for idx in 0..9 {
@@ -341,6 +810,152 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_plane_topology() {
+        // A blinker against the left edge
+        let blinker = &[(1, 0), (2, 0), (3, 0)];
+        let mut torus = grid_with(5, 5, blinker);
+        torus.advance();
+        let live: Vec<_> = torus.live_cells().collect();
+        assert_eq!(live, [(2, 0), (2, 1), (2, 4)]);
+        let mut plane = grid_with(5, 5, blinker).with_topology(Topology::Plane);
+        plane.advance();
+        let live: Vec<_> = plane.live_cells().collect();
+        assert_eq!(live, [(2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn test_transform() {
+        // o.
+        // o.
+        // oo
+        let mut grid = grid_with(5, 5, &[(1, 1), (2, 1), (3, 1), (3, 2)]);
+        let region = grid.bounding_box().unwrap();
+        grid.transform(&region, Transform::Rotate90);
+        let live: Vec<_> = grid.live_cells().collect();
+        assert_eq!(live, [(1, 1), (1, 2), (1, 3), (2, 1)]);
+        let region = grid.bounding_box().unwrap();
+        grid.transform(&region, Transform::FlipColumns);
+        let live: Vec<_> = grid.live_cells().collect();
+        assert_eq!(live, [(1, 1), (1, 2), (1, 3), (2, 3)]);
+        assert_eq!(grid.population(), 4);
+    }
+
+    #[test]
+    fn test_translate() {
+        let cells = &[(0, 0), (0, 1), (3, 3)];
+        let mut torus = grid_with(4, 4, cells).with_history(4);
+        let everything = torus.bounding_box().unwrap();
+        torus.translate(&everything, (-1, 2));
+        let live: Vec<_> = torus.live_cells().collect();
+        assert_eq!(live, [(2, 1), (3, 2), (3, 3)]);
+        assert_eq!(torus.periodicity(), None);
+
+        let mut plane = grid_with(4, 4, cells).with_topology(Topology::Plane);
+        plane.translate(&everything, (1, -1));
+        let live: Vec<_> = plane.live_cells().collect();
+        assert_eq!(live, [(1, 0)]);
+        assert_eq!(plane.stats().population, 1);
+        assert_eq!(plane.stats().deaths, 3);
+        assert_eq!(plane.stats().births, 1);
+
+        // Only the region moves
+        let mut grid = grid_with(4, 4, cells);
+        let corner = BoundingBox {
+            min_i: 0,
+            min_j: 0,
+            max_i: 1,
+            max_j: 1,
+        };
+        grid.translate(&corner, (1, 1));
+        let live: Vec<_> = grid.live_cells().collect();
+        assert_eq!(live, [(1, 1), (1, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn test_crop() {
+        let grid = grid_with(6, 6, &[(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)])
+            .with_topology(Topology::Plane);
+        let cropped = grid.crop(&grid.bounding_box().unwrap());
+        assert_eq!((cropped.width(), cropped.height()), (3, 3));
+        let live: Vec<_> = cropped.live_cells().collect();
+        assert_eq!(live, [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        assert_eq!(cropped.population(), 5);
+        assert_eq!(cropped.topology(), Topology::Plane);
+    }
+
+    #[test]
+    fn test_combine() {
+        let block = grid_with(2, 2, &[(0, 0), (0, 1), (1, 0), (1, 1)]);
+        let at = Coord { i: 1, j: 1 };
+        let combined = |combine: Combine| {
+            let mut grid = grid_with(3, 3, &[(0, 0), (1, 1), (1, 2)]);
+            grid.combine(&block, &at, combine);
+            grid.live_cells().collect::<Vec<_>>()
+        };
+        assert_eq!(
+            combined(Combine::Or),
+            [(0, 0), (1, 1), (1, 2), (2, 1), (2, 2)]
+        );
+        assert_eq!(combined(Combine::And), [(1, 1), (1, 2)]);
+        assert_eq!(combined(Combine::Xor), [(0, 0), (2, 1), (2, 2)]);
+        assert_eq!(combined(Combine::Difference), [(0, 0)]);
+        // Past the edges, the block wraps around to the other side of a torus
+        let mut grid = Grid::empty(3, 3);
+        grid.combine(&block, &Coord { i: 2, j: 2 }, Combine::Or);
+        let live: Vec<_> = grid.live_cells().collect();
+        assert_eq!(live, [(0, 0), (0, 2), (2, 0), (2, 2)]);
+    }
+
+    #[test]
+    fn test_clear_and_randomise() {
+        let mut grid = grid_with(4, 4, &[(0, 0), (1, 1), (2, 2), (3, 3)]);
+        let middle = BoundingBox {
+            min_i: 1,
+            min_j: 1,
+            max_i: 2,
+            max_j: 5,
+        };
+        grid.clear(&middle);
+        let live: Vec<_> = grid.live_cells().collect();
+        assert_eq!(live, [(0, 0), (3, 3)]);
+        assert_eq!(grid.stats().deaths, 2);
+        // The odds of every cell staying dead every time are tiny
+        let everything = BoundingBox {
+            min_i: 0,
+            min_j: 0,
+            max_i: 3,
+            max_j: 3,
+        };
+        let mut filled = 0;
+        for _ in 0..4 {
+            grid.clear(&everything);
+            grid.randomise(&everything);
+            filled += grid.population();
+        }
+        assert!(filled > 0);
+        assert_eq!(grid.population(), grid.live_cells().count());
+        // Nothing to do on a grid with no cells
+        let mut empty = Grid::empty(0, 0);
+        empty.clear(&everything);
+        empty.randomise(&everything);
+        assert_eq!(empty.population(), 0);
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut grid = grid_with(3, 3, &[(1, 0), (1, 1), (1, 2)]);
+        let before: Vec<bool> = grid.cells.iter().map(Cell::alive).collect();
+        grid.advance();
+        grid.advance();
+        grid.advance();
+        grid.restore(&before, 1);
+        assert_eq!(grid.generation(), 1);
+        assert_eq!(grid.stats().generation, 1);
+        let live: Vec<_> = grid.live_cells().collect();
+        assert_eq!(live, [(1, 0), (1, 1), (1, 2)]);
+    }
+
     /// Given
     ///
     /// [ (0,0) (0,1) (0,2) (0, 3) ]
@@ -367,8 +982,4 @@ mod tests {
         }
         v
     }
-
-    fn alive_count(grid: &Grid) -> usize {
-        alive_cells(grid).len()
-    }
 }
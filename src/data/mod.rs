@@ -1,5 +1,17 @@
+pub mod age;
 pub mod cell;
 pub mod grid;
+pub mod period;
+pub mod rule;
+pub mod stats;
+pub mod topology;
+pub mod transform;
 
+pub use self::age::*;
 pub use self::cell::*;
 pub use self::grid::*;
+pub use self::period::*;
+pub use self::rule::*;
+pub use self::stats::*;
+pub use self::topology::*;
+pub use self::transform::*;
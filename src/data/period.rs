@@ -0,0 +1,231 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+/// How many generations grids remember by default when looking for cycles, which is
+/// also the longest period that can be found
+pub const DEFAULT_HISTORY: usize = 1024;
+
+/// Summary of a generation's live cells that's cheap to compare with other generations.
+///
+/// The hash only covers where cells are relative to the top left corner of the smallest
+/// rectangle containing them, so a pattern that has moved hashes the same as it did
+/// before, and the corner says how far it moved.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Fingerprint {
+    pub generation: u64,
+    pub hash: u64,
+    /// Row and column of the top left corner of the live cells, or (0, 0) if there are none
+    pub origin: (usize, usize),
+    pub population: usize,
+}
+
+/// What sort of cycle a board has fallen into
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PeriodKind {
+    /// Every cell has died
+    Extinct,
+    /// Nothing changes from one generation to the next
+    StillLife,
+    /// The board repeats itself in place
+    Oscillator,
+    /// The board repeats itself, but moved. Grids wrap around at their edges, so this
+    /// only gets noticed while a pattern isn't straddling one.
+    Spaceship,
+}
+
+/// A cycle a board has fallen into
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Periodicity {
+    /// First generation of the cycle, which the board first came back to a period later
+    pub start: u64,
+    pub period: u64,
+    /// Rows and columns the live cells move by each period
+    pub displacement: (isize, isize),
+    pub population: usize,
+}
+
+impl Periodicity {
+    pub const fn kind(&self) -> PeriodKind {
+        if self.population == 0 {
+            PeriodKind::Extinct
+        } else if self.displacement.0 != 0 || self.displacement.1 != 0 {
+            PeriodKind::Spaceship
+        } else if self.period == 1 {
+            PeriodKind::StillLife
+        } else {
+            PeriodKind::Oscillator
+        }
+    }
+}
+
+impl fmt::Display for Periodicity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (period, start) = (self.period, self.start);
+        match self.kind() {
+            PeriodKind::Extinct => write!(f, "died out at generation {start}"),
+            PeriodKind::StillLife => write!(f, "still life from generation {start}"),
+            PeriodKind::Oscillator => {
+                write!(f, "period {period} oscillator from generation {start}")
+            }
+            PeriodKind::Spaceship => {
+                let (rows, columns) = self.displacement;
+                write!(
+                    f,
+                    "period {period} spaceship moving {rows} rows and {columns} columns, from generation {start}"
+                )
+            }
+        }
+    }
+}
+
+/// The most recent fingerprints of a grid, for spotting when it starts repeating itself
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    capacity: usize,
+    fingerprints: VecDeque<Fingerprint>,
+    periodicity: Option<Periodicity>,
+}
+
+impl History {
+    /// Remembers up to the given number of generations, where 0 turns history off
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            fingerprints: VecDeque::with_capacity(capacity),
+            periodicity: None,
+        }
+    }
+
+    pub const fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// The cycle found so far, if any. Once a board is periodic it stays that way, so
+    /// this sticks until the history is cleared.
+    pub const fn periodicity(&self) -> Option<&Periodicity> {
+        self.periodicity.as_ref()
+    }
+
+    /// Adds the latest generation, checking it against the ones before it.
+    ///
+    /// Generations are added one after the other, so the first match is always with the
+    /// start of the cycle: any earlier start would have repeated a generation sooner.
+    pub fn record(&mut self, latest: Fingerprint) {
+        if !self.is_enabled() {
+            return;
+        }
+        if self.periodicity.is_none() {
+            self.periodicity = self
+                .fingerprints
+                .iter()
+                .rev()
+                .find(|earlier| {
+                    earlier.hash == latest.hash && earlier.population == latest.population
+                })
+                .map(|earlier| Periodicity {
+                    start: earlier.generation,
+                    period: latest.generation - earlier.generation,
+                    displacement: (
+                        offset(earlier.origin.0, latest.origin.0),
+                        offset(earlier.origin.1, latest.origin.1),
+                    ),
+                    population: latest.population,
+                });
+        }
+        if self.fingerprints.len() == self.capacity {
+            self.fingerprints.pop_front();
+        }
+        self.fingerprints.push_back(latest);
+    }
+
+    /// Forgets every generation, e.g. after the grid has been edited
+    pub fn clear(&mut self) {
+        self.fingerprints.clear();
+        self.periodicity = None;
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+const fn offset(from: usize, to: usize) -> isize {
+    to.wrapping_sub(from) as isize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint(generation: u64, hash: u64, origin: (usize, usize)) -> Fingerprint {
+        Fingerprint {
+            generation,
+            hash,
+            origin,
+            population: 5,
+        }
+    }
+
+    #[test]
+    fn test_history_finds_start_of_cycle() {
+        let mut history = History::new(8);
+        for (generation, hash) in [(0, 1), (1, 2), (2, 3), (3, 4), (4, 3)] {
+            history.record(fingerprint(generation, hash, (2, 2)));
+        }
+        let periodicity = *history.periodicity().unwrap();
+        assert_eq!(periodicity.start, 2);
+        assert_eq!(periodicity.period, 2);
+        assert_eq!(periodicity.kind(), PeriodKind::Oscillator);
+        // Later matches don't replace the first one found
+        history.record(fingerprint(5, 4, (2, 2)));
+        assert_eq!(history.periodicity(), Some(&periodicity));
+        history.clear();
+        assert_eq!(history.periodicity(), None);
+    }
+
+    #[test]
+    fn test_history_displacement() {
+        let mut history = History::new(8);
+        history.record(fingerprint(0, 7, (5, 3)));
+        history.record(fingerprint(1, 8, (5, 3)));
+        history.record(fingerprint(2, 7, (4, 4)));
+        let periodicity = history.periodicity().unwrap();
+        assert_eq!(periodicity.displacement, (-1, 1));
+        assert_eq!(periodicity.kind(), PeriodKind::Spaceship);
+    }
+
+    #[test]
+    fn test_history_forgets_old_generations() {
+        let mut history = History::new(2);
+        for (generation, hash) in [(0, 1), (1, 2), (2, 3), (3, 1)] {
+            history.record(fingerprint(generation, hash, (0, 0)));
+        }
+        assert_eq!(history.periodicity(), None);
+        let mut disabled = History::default();
+        disabled.record(fingerprint(0, 1, (0, 0)));
+        disabled.record(fingerprint(1, 1, (0, 0)));
+        assert_eq!(disabled.periodicity(), None);
+    }
+
+    #[test]
+    fn test_periodicity_display() {
+        let still = Periodicity {
+            start: 4,
+            period: 1,
+            displacement: (0, 0),
+            population: 4,
+        };
+        assert_eq!(still.to_string(), "still life from generation 4");
+        let dead = Periodicity {
+            population: 0,
+            ..still
+        };
+        assert_eq!(dead.kind(), PeriodKind::Extinct);
+        let glider = Periodicity {
+            period: 4,
+            displacement: (-1, 1),
+            ..still
+        };
+        assert_eq!(
+            glider.to_string(),
+            "period 4 spaceship moving -1 rows and 1 columns, from generation 4"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use super::cell::Status;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A Life-like rule, describing how many live neighbours it takes for a dead cell to be
+/// born and for a live cell to survive.
+///
+/// Written in B/S notation, e.g. `B3/S23` for Conway's Game of Life.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct Rule {
+    // Bit n is set when n neighbours cause a birth
+    birth: u16,
+    // Bit n is set when n neighbours let a live cell survive
+    survival: u16,
+}
+
+impl Rule {
+    /// Conway's Game of Life, B3/S23
+    pub const CONWAY: Self = Self {
+        birth: 1 << 3,
+        survival: 1 << 2 | 1 << 3,
+    };
+
+    /// Creates a rule from the neighbour counts that cause births and allow survival.
+    /// Counts above 8 are ignored.
+    pub fn new(birth: &[usize], survival: &[usize]) -> Self {
+        let mask = |counts: &[usize]| {
+            counts
+                .iter()
+                .filter(|&&n| n <= 8)
+                .fold(0, |acc, &n| acc | 1 << n)
+        };
+        Self {
+            birth: mask(birth),
+            survival: mask(survival),
+        }
+    }
+
+    /// Whether a dead cell with the given number of live neighbours comes alive
+    pub const fn births(&self, neighbours_cnt: usize) -> bool {
+        neighbours_cnt <= 8 && self.birth & (1 << neighbours_cnt) != 0
+    }
+
+    /// Whether a live cell with the given number of live neighbours stays alive
+    pub const fn survives(&self, neighbours_cnt: usize) -> bool {
+        neighbours_cnt <= 8 && self.survival & (1 << neighbours_cnt) != 0
+    }
+
+    /// Returns the next status of a cell given its current one and a number of neighbours
+    pub const fn next_status(&self, status: &Status, neighbours_cnt: usize) -> Status {
+        let alive = match status {
+            Status::Alive => self.survives(neighbours_cnt),
+            Status::Dead => self.births(neighbours_cnt),
+        };
+        if alive {
+            Status::Alive
+        } else {
+            Status::Dead
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::CONWAY
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = |mask: u16| -> String {
+            (0..=8)
+                .filter(|n| mask & (1 << n) != 0)
+                .map(|n| n.to_string())
+                .collect()
+        };
+        write!(f, "B{}/S{}", digits(self.birth), digits(self.survival))
+    }
+}
+
+/// Returned when a rule string can't be parsed
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseRuleError(String);
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid rule [{}], expected B/S notation such as B3/S23",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseRuleError {}
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    /// Parses rules in B/S notation, e.g. `B3/S23` or `b36/s23`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseRuleError(s.to_string());
+        let mut parts = s.trim().split('/');
+        let (Some(birth), Some(survival), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(err());
+        };
+        let counts = |part: &str, prefix: char| -> Result<Vec<usize>, ParseRuleError> {
+            let mut chars = part.chars();
+            if !chars
+                .next()
+                .is_some_and(|c| c.eq_ignore_ascii_case(&prefix))
+            {
+                return Err(err());
+            }
+            chars
+                .map(|c| match c.to_digit(10) {
+                    Some(n) if n <= 8 => Ok(n as usize),
+                    _ => Err(err()),
+                })
+                .collect()
+        };
+        Ok(Self::new(&counts(birth, 'B')?, &counts(survival, 'S')?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conway() {
+        let rule = Rule::default();
+        assert_eq!(rule, Rule::CONWAY);
+        assert_eq!(rule.next_status(&Status::Alive, 0), Status::Dead);
+        assert_eq!(rule.next_status(&Status::Alive, 2), Status::Alive);
+        assert_eq!(rule.next_status(&Status::Alive, 3), Status::Alive);
+        assert_eq!(rule.next_status(&Status::Alive, 4), Status::Dead);
+        assert_eq!(rule.next_status(&Status::Dead, 2), Status::Dead);
+        assert_eq!(rule.next_status(&Status::Dead, 3), Status::Alive);
+        assert_eq!(rule.next_status(&Status::Dead, 9), Status::Dead);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Rule::CONWAY.to_string(), "B3/S23");
+        assert_eq!(Rule::new(&[3, 6], &[2, 3]).to_string(), "B36/S23");
+        assert_eq!(Rule::new(&[], &[]).to_string(), "B/S");
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("B3/S23".parse(), Ok(Rule::CONWAY));
+        assert_eq!("b36/s23".parse(), Ok(Rule::new(&[3, 6], &[2, 3])));
+        assert_eq!(" B/S ".parse(), Ok(Rule::new(&[], &[])));
+        assert!("B3".parse::<Rule>().is_err());
+        assert!("B3/S9".parse::<Rule>().is_err());
+        assert!("S23/B3".parse::<Rule>().is_err());
+        assert!("B3/S23/C2".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let rule = Rule::new(&[1, 3, 5, 7], &[0, 8]);
+        assert_eq!(rule.to_string().parse(), Ok(rule));
+    }
+}
@@ -0,0 +1,47 @@
+use super::grid::Coord;
+
+/// Figures for a generation of a grid, and how it differed from the one before
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Stats {
+    pub generation: u64,
+    /// Number of live cells
+    pub population: usize,
+    /// Cells that came alive in this generation
+    pub births: usize,
+    /// Cells that died in this generation
+    pub deaths: usize,
+}
+
+impl Stats {
+    /// Number of cells that changed state in this generation
+    pub const fn changed(&self) -> usize {
+        self.births + self.deaths
+    }
+}
+
+/// The smallest rectangle containing every live cell, with inclusive bounds in (i, j)
+/// notation like `Coord`
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct BoundingBox {
+    pub min_i: usize,
+    pub min_j: usize,
+    pub max_i: usize,
+    pub max_j: usize,
+}
+
+impl BoundingBox {
+    /// Whether a cell is inside the box
+    pub const fn contains(&self, &Coord { i, j }: &Coord) -> bool {
+        self.min_i <= i && i <= self.max_i && self.min_j <= j && j <= self.max_j
+    }
+
+    /// Number of columns the box covers
+    pub const fn width(&self) -> usize {
+        self.max_j - self.min_j + 1
+    }
+
+    /// Number of rows the box covers
+    pub const fn height(&self) -> usize {
+        self.max_i - self.min_i + 1
+    }
+}
@@ -0,0 +1,106 @@
+use super::grid::Coord;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// What happens at the edges of a grid
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Topology {
+    /// Each edge wraps around to the opposite one, like the surface of a doughnut
+    #[default]
+    Torus,
+    /// Everything past the edges is dead
+    Plane,
+}
+
+impl Topology {
+    pub const ALL: [Self; 2] = [Self::Torus, Self::Plane];
+
+    /// The cell on a grid of the given size that a position refers to, which might be
+    /// past its edges, or None if it's off the grid altogether
+    pub fn wrap(self, (i, j): (isize, isize), height: usize, width: usize) -> Option<Coord> {
+        match self {
+            Self::Torus => {
+                let wrap = |n: isize, size: usize| {
+                    let size = isize::try_from(size).ok()?;
+                    usize::try_from(n.checked_rem_euclid(size)?).ok()
+                };
+                Some(Coord {
+                    i: wrap(i, height)?,
+                    j: wrap(j, width)?,
+                })
+            }
+            Self::Plane => Some(Coord {
+                i: usize::try_from(i).ok().filter(|&i| i < height)?,
+                j: usize::try_from(j).ok().filter(|&j| j < width)?,
+            }),
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Torus => "torus",
+            Self::Plane => "plane",
+        }
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returned when a topology name isn't recognised
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseTopologyError(String);
+
+impl fmt::Display for ParseTopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = Topology::ALL.iter().map(|t| t.name()).collect();
+        write!(
+            f,
+            "Unknown topology [{}], expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for ParseTopologyError {}
+
+impl FromStr for Topology {
+    type Err = ParseTopologyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|topology| topology.name().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| ParseTopologyError(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        let torus = Topology::Torus;
+        assert_eq!(torus.wrap((-1, 5), 4, 5), Some(Coord { i: 3, j: 0 }));
+        assert_eq!(torus.wrap((9, -6), 4, 5), Some(Coord { i: 1, j: 4 }));
+        assert_eq!(torus.wrap((0, 0), 0, 5), None);
+        let plane = Topology::Plane;
+        assert_eq!(plane.wrap((3, 4), 4, 5), Some(Coord { i: 3, j: 4 }));
+        assert_eq!(plane.wrap((-1, 4), 4, 5), None);
+        assert_eq!(plane.wrap((3, 5), 4, 5), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("Plane".parse(), Ok(Topology::Plane));
+        assert_eq!(Topology::default().to_string().parse(), Ok(Topology::Torus));
+        assert!("sphere".parse::<Topology>().is_err());
+    }
+}
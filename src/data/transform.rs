@@ -0,0 +1,238 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// One of the 8 ways of rotating and reflecting a pattern onto itself
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Transform {
+    Identity,
+    /// A quarter turn clockwise
+    Rotate90,
+    Rotate180,
+    /// A quarter turn anticlockwise
+    Rotate270,
+    /// Upside down, so the first row becomes the last
+    FlipRows,
+    /// Left to right, so the first column becomes the last
+    FlipColumns,
+    /// Rows become columns, reflecting across the main diagonal
+    Transpose,
+    /// Reflects across the other diagonal
+    AntiTranspose,
+}
+
+impl Transform {
+    pub const ALL: [Self; 8] = [
+        Self::Identity,
+        Self::Rotate90,
+        Self::Rotate180,
+        Self::Rotate270,
+        Self::FlipRows,
+        Self::FlipColumns,
+        Self::Transpose,
+        Self::AntiTranspose,
+    ];
+
+    /// Where a cell ends up, before the result gets moved back to the origin
+    pub const fn apply(self, (i, j): (isize, isize)) -> (isize, isize) {
+        match self {
+            Self::Identity => (i, j),
+            Self::Rotate90 => (j, -i),
+            Self::Rotate180 => (-i, -j),
+            Self::Rotate270 => (-j, i),
+            Self::FlipRows => (-i, j),
+            Self::FlipColumns => (i, -j),
+            Self::Transpose => (j, i),
+            Self::AntiTranspose => (-j, -i),
+        }
+    }
+
+    /// Where a cell in a box with the given height and width ends up, moving the result so
+    /// that the transformed box's top left corner is where the box's was
+    pub fn apply_within(
+        self,
+        cell: (isize, isize),
+        (height, width): (isize, isize),
+    ) -> (isize, isize) {
+        let (a, b) = (self.apply((0, 0)), self.apply((height - 1, width - 1)));
+        let (i, j) = self.apply(cell);
+        (i - a.0.min(b.0), j - a.1.min(b.1))
+    }
+
+    /// Whether rows end up as columns
+    pub const fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Self::Rotate90 | Self::Rotate270 | Self::Transpose | Self::AntiTranspose
+        )
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Identity => "identity",
+            Self::Rotate90 => "rotate90",
+            Self::Rotate180 => "rotate180",
+            Self::Rotate270 => "rotate270",
+            Self::FlipRows => "flip-rows",
+            Self::FlipColumns => "flip-columns",
+            Self::Transpose => "transpose",
+            Self::AntiTranspose => "anti-transpose",
+        }
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returned when a transform name isn't recognised
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseTransformError(String);
+
+impl fmt::Display for ParseTransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = Transform::ALL.iter().map(|t| t.name()).collect();
+        write!(
+            f,
+            "Unknown transform [{}], expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for ParseTransformError {}
+
+impl FromStr for Transform {
+    type Err = ParseTransformError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|transform| transform.name().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| ParseTransformError(s.to_string()))
+    }
+}
+
+/// A way of combining two sets of live cells, cell by cell
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Combine {
+    /// Alive in either
+    Or,
+    /// Alive in both
+    And,
+    /// Alive in one but not the other
+    Xor,
+    /// Alive in the first but not the second
+    Difference,
+}
+
+impl Combine {
+    pub const ALL: [Self; 4] = [Self::Or, Self::And, Self::Xor, Self::Difference];
+
+    pub const fn apply(self, first: bool, second: bool) -> bool {
+        match self {
+            Self::Or => first || second,
+            Self::And => first && second,
+            Self::Xor => first != second,
+            Self::Difference => first && !second,
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Or => "or",
+            Self::And => "and",
+            Self::Xor => "xor",
+            Self::Difference => "difference",
+        }
+    }
+}
+
+impl fmt::Display for Combine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returned when a way of combining cells isn't recognised
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseCombineError(String);
+
+impl fmt::Display for ParseCombineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = Combine::ALL.iter().map(|c| c.name()).collect();
+        write!(
+            f,
+            "Unknown way of combining [{}], expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for ParseCombineError {}
+
+impl FromStr for Combine {
+    type Err = ParseCombineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|combine| combine.name().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| ParseCombineError(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_within() {
+        // The top right corner of a 2 by 3 box
+        let corner = (0, 2);
+        let expected = [
+            (Transform::Identity, (0, 2)),
+            (Transform::Rotate90, (2, 1)),
+            (Transform::Rotate180, (1, 0)),
+            (Transform::Rotate270, (0, 0)),
+            (Transform::FlipRows, (1, 2)),
+            (Transform::FlipColumns, (0, 0)),
+            (Transform::Transpose, (2, 0)),
+            (Transform::AntiTranspose, (0, 1)),
+        ];
+        for (transform, cell) in expected {
+            assert_eq!(transform.apply_within(corner, (2, 3)), cell, "{transform}");
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        for transform in Transform::ALL {
+            assert_eq!(transform.to_string().parse(), Ok(transform));
+        }
+        assert!("sideways".parse::<Transform>().is_err());
+        for combine in Combine::ALL {
+            assert_eq!(combine.to_string().parse(), Ok(combine));
+        }
+        assert_eq!("XOR".parse(), Ok(Combine::Xor));
+        assert!("nand".parse::<Combine>().is_err());
+    }
+
+    #[test]
+    fn test_combine() {
+        let table = |combine: Combine| {
+            [(false, false), (false, true), (true, false), (true, true)]
+                .map(|(first, second)| combine.apply(first, second))
+        };
+        assert_eq!(table(Combine::Or), [false, true, true, true]);
+        assert_eq!(table(Combine::And), [false, false, false, true]);
+        assert_eq!(table(Combine::Xor), [false, true, true, false]);
+        assert_eq!(table(Combine::Difference), [false, false, true, false]);
+    }
+}
@@ -0,0 +1,488 @@
+use crate::data::{Ages, Grid};
+use crate::rendering::software::{Image, SoftwareRenderer};
+use crate::simulation::headless::Observer;
+use crate::simulation::Frame;
+use color_quant::NeuQuant;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+// Trade-off between quantisation speed and quality, 1 being the slowest and best
+const QUANTISE_SAMPLE_FACTOR: i32 = 10;
+
+const NO_FRAMES: &str = "Can't save an animation without any frames";
+
+/// Where animations are saved when no path is given
+pub const DEFAULT_RECORDING_PATH: &str = "gol-{gen}.gif";
+
+/// File formats animations can be saved in
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum AnimationFormat {
+    Gif,
+    /// Animated PNG, which keeps every colour exactly but makes bigger files
+    Apng,
+}
+
+impl AnimationFormat {
+    /// Picks a format from a file's extension, `.gif` or `.png`/`.apng`
+    #[allow(clippy::missing_errors_doc)]
+    pub fn for_path(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("gif") => Ok(Self::Gif),
+            Some("png" | "apng") => Ok(Self::Apng),
+            _ => Err(format!(
+                "Can't tell what format to record {} in, expected a .gif, .png or .apng file",
+                path.display()
+            )),
+        }
+    }
+}
+
+/// Colours animation frames get reduced to
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Palette {
+    /// Colours are kept as they are where possible. GIFs can only have 256 colours per
+    /// frame, so frames with more than that get quantised.
+    Auto,
+    /// Shades of grey
+    Greyscale,
+    /// At most this many colours, from 2 to 256, which makes for smaller files
+    Colours(u16),
+}
+
+impl Palette {
+    /// Reduces an image's colours to this palette
+    pub fn apply(self, image: &Image) -> Image {
+        let mut reduced = image.clone();
+        let pixels = reduced.as_raw_mut();
+        match self {
+            Self::Auto => {}
+            Self::Greyscale => {
+                for pixel in pixels.chunks_exact_mut(4) {
+                    let grey = luma(pixel);
+                    pixel[..3].copy_from_slice(&[grey; 3]);
+                }
+            }
+            Self::Colours(colours) => {
+                let quantiser = NeuQuant::new(QUANTISE_SAMPLE_FACTOR, colours.into(), pixels);
+                let map = quantiser.color_map_rgba();
+                for pixel in pixels.chunks_exact_mut(4) {
+                    let idx = quantiser.index_of(pixel);
+                    pixel.copy_from_slice(&map[idx * 4..idx * 4 + 4]);
+                }
+            }
+        }
+        reduced
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn luma(pixel: &[u8]) -> u8 {
+    let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(f32::from);
+    (0.299 * r + 0.587 * g + 0.114 * b).round().clamp(0., 255.) as u8
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => f.write_str("auto"),
+            Self::Greyscale => f.write_str("greyscale"),
+            Self::Colours(colours) => write!(f, "{colours}"),
+        }
+    }
+}
+
+/// Returned when a palette can't be parsed
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParsePaletteError(String);
+
+impl fmt::Display for ParsePaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown palette [{}], expected auto, greyscale, or a number of colours from 2 to 256",
+            self.0
+        )
+    }
+}
+
+impl Error for ParsePaletteError {}
+
+impl FromStr for Palette {
+    type Err = ParsePaletteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        if trimmed.eq_ignore_ascii_case("auto") {
+            Ok(Self::Auto)
+        } else if trimmed.eq_ignore_ascii_case("greyscale")
+            || trimmed.eq_ignore_ascii_case("grayscale")
+        {
+            Ok(Self::Greyscale)
+        } else {
+            match trimmed.parse() {
+                Ok(colours @ 2..=256) => Ok(Self::Colours(colours)),
+                _ => Err(ParsePaletteError(s.to_string())),
+            }
+        }
+    }
+}
+
+/// Settings shared by every recording
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct AnimationOptions {
+    pub palette: Palette,
+    /// Only every this many generations gets recorded
+    pub skip: u64,
+    /// How long each frame is shown for when played back
+    pub frame_delay: Duration,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            palette: Palette::Auto,
+            skip: 1,
+            frame_delay: Duration::from_millis(100),
+        }
+    }
+}
+
+enum Encoder {
+    // Waiting for the first frame to say how big the GIF is
+    PendingGif(Option<File>),
+    Gif(gif::Encoder<BufWriter<File>>),
+    // APNGs need to know how many frames there are up front, so they're only written
+    // once recording is finished
+    Apng(Vec<Image>, File),
+}
+
+/// Writes a sequence of images to an animated GIF or APNG
+pub struct Animation {
+    path: PathBuf,
+    options: AnimationOptions,
+    encoder: Encoder,
+    // Size of the first frame, which every other one has to match
+    size: Option<(u32, u32)>,
+    frames: usize,
+}
+
+impl Animation {
+    /// Creates the file at the given path, picking the format from its extension
+    #[allow(clippy::missing_errors_doc)]
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        options: AnimationOptions,
+    ) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let format = AnimationFormat::for_path(path)?;
+        let file =
+            File::create(path).map_err(|e| format!("Could not create {}: {e}", path.display()))?;
+        let encoder = match format {
+            AnimationFormat::Gif => Encoder::PendingGif(Some(file)),
+            AnimationFormat::Apng => Encoder::Apng(Vec::new(), file),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            options,
+            encoder,
+            size: None,
+            frames: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of frames added so far
+    pub const fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Appends a frame, which has to be the same size as any before it
+    #[allow(clippy::missing_errors_doc)]
+    pub fn add(&mut self, image: &Image) -> Result<(), Box<dyn Error>> {
+        let size = (image.width(), image.height());
+        if *self.size.get_or_insert(size) != size {
+            return Err("Every frame of an animation has to be the same size".into());
+        }
+        let image = self.options.palette.apply(image);
+        if let Encoder::PendingGif(file) = &mut self.encoder {
+            let file = file.take().ok_or("GIF file has gone missing")?;
+            let (width, height) = gif_size(&image)?;
+            let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &[])?;
+            encoder.set_repeat(gif::Repeat::Infinite)?;
+            self.encoder = Encoder::Gif(encoder);
+        }
+        match &mut self.encoder {
+            Encoder::PendingGif(_) => unreachable!("the GIF encoder was just created"),
+            Encoder::Gif(encoder) => {
+                let (width, height) = gif_size(&image)?;
+                let mut frame = gif_frame(&image, width, height);
+                frame.delay = centiseconds(self.options.frame_delay);
+                encoder.write_frame(&frame)?;
+            }
+            Encoder::Apng(frames, _) => frames.push(image),
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Finishes writing the file, returning the number of frames in it
+    #[allow(clippy::missing_errors_doc)]
+    pub fn finish(self) -> Result<usize, Box<dyn Error>> {
+        match self.encoder {
+            Encoder::PendingGif(_) => return Err(NO_FRAMES.into()),
+            Encoder::Gif(encoder) => encoder.into_inner()?.flush()?,
+            Encoder::Apng(frames, file) => write_apng(&frames, file, self.options.frame_delay)?,
+        }
+        Ok(self.frames)
+    }
+}
+
+fn gif_size(image: &Image) -> Result<(u16, u16), Box<dyn Error>> {
+    match (u16::try_from(image.width()), u16::try_from(image.height())) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(format!("{}x{} is too big for a GIF", image.width(), image.height()).into()),
+    }
+}
+
+// Indexes a frame's pixels into a palette, using its exact colours if there are few
+// enough of them, and quantising it otherwise
+fn gif_frame(image: &Image, width: u16, height: u16) -> gif::Frame<'static> {
+    let mut colours: HashMap<[u8; 3], u8> = HashMap::new();
+    let mut palette = Vec::new();
+    let mut indices = Vec::with_capacity(image.as_raw().len() / 4);
+    for pixel in image.as_raw().chunks_exact(4) {
+        let rgb = [pixel[0], pixel[1], pixel[2]];
+        let idx = if let Some(&idx) = colours.get(&rgb) {
+            idx
+        } else {
+            let Ok(idx) = u8::try_from(colours.len()) else {
+                // Too many colours for a GIF palette
+                let mut pixels = image.as_raw().to_vec();
+                return gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+            };
+            colours.insert(rgb, idx);
+            palette.extend_from_slice(&rgb);
+            idx
+        };
+        indices.push(idx);
+    }
+    gif::Frame {
+        width,
+        height,
+        buffer: indices.into(),
+        palette: Some(palette),
+        ..gif::Frame::default()
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn centiseconds(delay: Duration) -> u16 {
+    (delay.as_millis() / 10).clamp(1, u128::from(u16::MAX)) as u16
+}
+
+fn write_apng(frames: &[Image], file: File, delay: Duration) -> Result<(), Box<dyn Error>> {
+    let Some(first) = frames.first() else {
+        return Err(NO_FRAMES.into());
+    };
+    let mut encoder = ::png::Encoder::new(BufWriter::new(file), first.width(), first.height());
+    encoder.set_color(::png::ColorType::Rgba);
+    encoder.set_depth(::png::BitDepth::Eight);
+    encoder.set_animated(u32::try_from(frames.len())?, 0)?;
+    let millis = u16::try_from(delay.as_millis()).unwrap_or(u16::MAX);
+    encoder.set_frame_delay(millis, 1000)?;
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(frame.as_raw())?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Records generations from `from` to `to` of a headless run to an animation
+pub struct Recording {
+    renderer: SoftwareRenderer,
+    from: u64,
+    to: u64,
+    skip: u64,
+    animation: Option<Animation>,
+}
+
+impl Recording {
+    pub fn new(renderer: SoftwareRenderer, animation: Animation, from: u64, to: u64) -> Self {
+        Self {
+            renderer,
+            from,
+            to,
+            skip: animation.options.skip.max(1),
+            animation: Some(animation),
+        }
+    }
+}
+
+impl Observer for Recording {
+    fn observe(&mut self, grid: &Grid, ages: &Ages) -> Result<(), Box<dyn Error>> {
+        let generation = grid.generation();
+        if (self.from..=self.to).contains(&generation)
+            && (generation - self.from).is_multiple_of(self.skip)
+        {
+            if let Some(animation) = &mut self.animation {
+                animation.add(&self.renderer.render(&Frame::new(grid, ages)))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        match self.animation.take() {
+            Some(animation) => animation.finish().map(|_| ()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::headless::Headless;
+    use std::env;
+    use std::fs;
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("gol-{}-{name}", std::process::id()))
+    }
+
+    fn test_image(colours: u8) -> Image {
+        let pixels = (0..colours)
+            .flat_map(|c| [c, c.wrapping_mul(7), 255 - c, 255])
+            .collect();
+        Image::from_raw(u32::from(colours), 1, pixels).unwrap()
+    }
+
+    #[test]
+    fn test_format_for_path() {
+        let format = |path: &str| AnimationFormat::for_path(Path::new(path));
+        assert_eq!(format("run.gif"), Ok(AnimationFormat::Gif));
+        assert_eq!(format("run.GIF"), Ok(AnimationFormat::Gif));
+        assert_eq!(format("run.png"), Ok(AnimationFormat::Apng));
+        assert_eq!(format("run.apng"), Ok(AnimationFormat::Apng));
+        assert!(format("run.mp4").is_err());
+        assert!(format("run").is_err());
+    }
+
+    #[test]
+    fn test_palette_from_str() {
+        assert_eq!("auto".parse(), Ok(Palette::Auto));
+        assert_eq!("Greyscale".parse(), Ok(Palette::Greyscale));
+        assert_eq!("grayscale".parse(), Ok(Palette::Greyscale));
+        assert_eq!("16".parse(), Ok(Palette::Colours(16)));
+        assert!("1".parse::<Palette>().is_err());
+        assert!("257".parse::<Palette>().is_err());
+        assert!("sepia".parse::<Palette>().is_err());
+        for palette in [Palette::Auto, Palette::Greyscale, Palette::Colours(8)] {
+            assert_eq!(palette.to_string().parse(), Ok(palette));
+        }
+    }
+
+    #[test]
+    fn test_palette_apply() {
+        let image = test_image(64);
+        assert_eq!(Palette::Auto.apply(&image), image);
+        let grey = Palette::Greyscale.apply(&image);
+        assert!(grey
+            .as_raw()
+            .chunks_exact(4)
+            .all(|p| p[0] == p[1] && p[1] == p[2] && p[3] == 255));
+        let reduced = Palette::Colours(4).apply(&image);
+        let mut distinct: Vec<_> = reduced.as_raw().chunks_exact(4).collect();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert!(distinct.len() <= 4);
+    }
+
+    #[test]
+    fn test_gif_frame() {
+        let exact = gif_frame(&test_image(3), 3, 1);
+        assert_eq!(exact.buffer.as_ref(), &[0, 1, 2]);
+        assert_eq!(exact.palette.unwrap().len(), 9);
+        // More colours than fit in a GIF palette
+        let pixels = (0..300u32)
+            .flat_map(|c| [(c % 256) as u8, (c / 256) as u8 * 100, 0, 255])
+            .collect();
+        let image = Image::from_raw(300, 1, pixels).unwrap();
+        let quantised = gif_frame(&image, 300, 1);
+        assert_eq!(quantised.buffer.len(), 300);
+        assert!(quantised.palette.unwrap().len() <= 256 * 3);
+    }
+
+    #[test]
+    fn test_record_gif() {
+        let path = temp_path("record.gif");
+        let options = AnimationOptions {
+            skip: 2,
+            ..AnimationOptions::default()
+        };
+        let animation = Animation::create(&path, options).unwrap();
+        let recording = Recording::new(SoftwareRenderer::new(8, 6, 16, 12), animation, 1, 6);
+        Headless::new(Grid::new(8, 6), 10)
+            .with_observer(Box::new(recording))
+            .run()
+            .unwrap();
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (16, 12));
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 10);
+            frames += 1;
+        }
+        // Generations 1, 3 and 5
+        assert_eq!(frames, 3);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_record_apng() {
+        let path = temp_path("record.png");
+        let mut animation = Animation::create(&path, AnimationOptions::default()).unwrap();
+        let renderer = SoftwareRenderer::new(8, 6, 16, 12);
+        let mut grid = Grid::new(8, 6);
+        for _ in 0..4 {
+            animation.add(&renderer.render_grid(&grid)).unwrap();
+            grid.advance();
+        }
+        assert!(animation
+            .add(&SoftwareRenderer::new(8, 6, 8, 6).render_grid(&grid))
+            .is_err());
+        assert_eq!(animation.frames(), 4);
+        assert_eq!(animation.finish().unwrap(), 4);
+        let decoder = ::png::Decoder::new(File::open(&path).unwrap());
+        let reader = decoder.read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!(control.num_frames, 4);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_empty_animation_is_an_error() {
+        for name in ["empty.gif", "empty.png"] {
+            let path = temp_path(name);
+            let animation = Animation::create(&path, AnimationOptions::default()).unwrap();
+            assert!(animation.finish().is_err());
+            fs::remove_file(&path).unwrap();
+        }
+    }
+}
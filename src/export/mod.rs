@@ -0,0 +1,158 @@
+pub mod animation;
+pub mod png;
+pub mod runlog;
+pub mod stats;
+pub mod svg;
+pub mod y4m;
+
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Placeholder in output paths that gets replaced with the generation number
+pub const GENERATION_PLACEHOLDER: &str = "{gen}";
+
+/// Where screenshots are saved when no path is given
+pub const DEFAULT_SCREENSHOT_PATH: &str = "gol-{gen}.png";
+
+/// Fills the generation number into an output path, see `GENERATION_PLACEHOLDER`
+pub fn output_path(template: &str, generation: u64) -> PathBuf {
+    PathBuf::from(template.replace(GENERATION_PLACEHOLDER, &generation.to_string()))
+}
+
+/// How big an exported image of a board should be
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ImageSize {
+    /// Each cell is this many pixels across, so 1 gives one pixel per cell
+    PerCell(u32),
+    /// A fixed width and height in pixels, regardless of the board's dimensions
+    Pixels(u32, u32),
+}
+
+impl ImageSize {
+    /// Width and height in pixels of an image of a board with the given dimensions
+    #[allow(clippy::missing_errors_doc)]
+    pub fn dimensions(self, columns: usize, rows: usize) -> Result<(u32, u32), Box<dyn Error>> {
+        let (width, height) = match self {
+            Self::PerCell(scale) => {
+                let scaled = |count: usize| {
+                    u32::try_from(count)
+                        .ok()
+                        .and_then(|count| count.checked_mul(scale))
+                        .ok_or_else(|| {
+                            format!("{count} cells at {scale} pixels each is too big an image")
+                        })
+                };
+                (scaled(columns)?, scaled(rows)?)
+            }
+            Self::Pixels(width, height) => (width, height),
+        };
+        if width == 0 || height == 0 {
+            return Err(format!("Can't export a {width}x{height} image").into());
+        }
+        Ok((width, height))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseImageSizeError(String);
+
+impl fmt::Display for ParseImageSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown image size [{}], expected pixels per cell like 4, or a size like 1920x1080",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseImageSizeError {}
+
+impl FromStr for ImageSize {
+    type Err = ParseImageSizeError;
+
+    /// Parses either a number of pixels per cell, or a width and height like `640x480`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseImageSizeError(s.to_string());
+        let trimmed = s.trim();
+        match trimmed.split_once(['x', 'X']) {
+            Some((width, height)) => Ok(Self::Pixels(
+                width.trim().parse().map_err(|_| error())?,
+                height.trim().parse().map_err(|_| error())?,
+            )),
+            None => trimmed.parse().map(Self::PerCell).map_err(|_| error()),
+        }
+    }
+}
+
+/// A writer for tests that can still be read after it's been handed over to an observer,
+/// since every clone writes to the same buffer
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+#[cfg(test)]
+impl SharedBuffer {
+    pub(crate) fn written(&self) -> Vec<u8> {
+        self.0.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+impl std::io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_path() {
+        assert_eq!(
+            output_path("gol-{gen}.png", 42),
+            PathBuf::from("gol-42.png")
+        );
+        assert_eq!(
+            output_path("out/board.png", 42),
+            PathBuf::from("out/board.png")
+        );
+    }
+
+    #[test]
+    fn test_image_size_dimensions() {
+        assert_eq!(
+            ImageSize::PerCell(1).dimensions(100, 80).unwrap(),
+            (100, 80)
+        );
+        assert_eq!(
+            ImageSize::PerCell(4).dimensions(100, 80).unwrap(),
+            (400, 320)
+        );
+        assert_eq!(
+            ImageSize::Pixels(640, 480).dimensions(100, 80).unwrap(),
+            (640, 480)
+        );
+        assert!(ImageSize::PerCell(0).dimensions(100, 80).is_err());
+        assert!(ImageSize::PerCell(u32::MAX).dimensions(100, 80).is_err());
+        assert!(ImageSize::Pixels(0, 480).dimensions(100, 80).is_err());
+    }
+
+    #[test]
+    fn test_parse_image_size() {
+        assert_eq!("4".parse(), Ok(ImageSize::PerCell(4)));
+        assert_eq!("1920x1080".parse(), Ok(ImageSize::Pixels(1920, 1080)));
+        assert_eq!(" 640 X 480 ".parse(), Ok(ImageSize::Pixels(640, 480)));
+        assert!("".parse::<ImageSize>().is_err());
+        assert!("640x".parse::<ImageSize>().is_err());
+        assert!("big".parse::<ImageSize>().is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use super::output_path;
+use super::svg::{self, Svg};
+use crate::data::{Ages, Grid};
+use crate::rendering::software::{Image, SoftwareRenderer};
+use crate::simulation::headless::Observer;
+use crate::simulation::Frame;
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Encodes an image as a PNG
+#[allow(clippy::missing_errors_doc)]
+pub fn write<W: Write>(image: &Image, writer: W) -> Result<(), Box<dyn Error>> {
+    let mut encoder = ::png::Encoder::new(writer, image.width(), image.height());
+    encoder.set_color(::png::ColorType::Rgba);
+    encoder.set_depth(::png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image.as_raw())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Saves an image as a PNG file, replacing anything already at that path
+#[allow(clippy::missing_errors_doc)]
+pub fn save<P: AsRef<Path>>(image: &Image, path: P) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    let file =
+        File::create(path).map_err(|e| format!("Could not create {}: {e}", path.display()))?;
+    write(image, BufWriter::new(file))
+}
+
+/// Saves screenshots of particular generations of a headless run
+pub struct Screenshots {
+    renderer: SoftwareRenderer,
+    path: String,
+    at: BTreeSet<u64>,
+    svg: Svg,
+}
+
+impl Screenshots {
+    /// Takes screenshots of the given generations, saving them to `path` with the
+    /// generation filled in, see `export::output_path`
+    pub fn new<I>(renderer: SoftwareRenderer, path: &str, at: I) -> Self
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        Self {
+            renderer,
+            path: path.to_string(),
+            at: at.into_iter().collect(),
+            svg: Svg::new(),
+        }
+    }
+
+    /// Draws screenshots with the given settings when their path ends in `.svg`, rather
+    /// than saving them as PNGs
+    #[must_use]
+    pub fn with_svg(mut self, svg: Svg) -> Self {
+        self.svg = svg;
+        self
+    }
+}
+
+impl Observer for Screenshots {
+    fn observe(&mut self, grid: &Grid, ages: &Ages) -> Result<(), Box<dyn Error>> {
+        let generation = grid.generation();
+        if self.at.contains(&generation) {
+            let path = output_path(&self.path, generation);
+            if svg::is_svg(&path) {
+                svg::save(&self.svg.render(grid), &path)?;
+            } else {
+                save(&self.renderer.render(&Frame::new(grid, ages)), &path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::layout::Spacing;
+    use crate::simulation::headless::Headless;
+    use std::env;
+    use std::fs;
+
+    fn decode<R: std::io::Read>(reader: R) -> (u32, u32, Vec<u8>) {
+        let mut reader = ::png::Decoder::new(reader).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(info.color_type, ::png::ColorType::Rgba);
+        pixels.truncate(info.buffer_size());
+        (info.width, info.height, pixels)
+    }
+
+    #[test]
+    fn test_write() {
+        let grid = Grid::new(5, 3);
+        let renderer = SoftwareRenderer::new(5, 3, 5, 3).with_spacing(Spacing::Gaps(0.));
+        let image = renderer.render_grid(&grid);
+        let mut encoded = Vec::new();
+        write(&image, &mut encoded).unwrap();
+        let (width, height, pixels) = decode(&encoded[..]);
+        assert_eq!((width, height), (5, 3));
+        assert_eq!(pixels, image.into_raw());
+    }
+
+    #[test]
+    fn test_screenshots() {
+        let dir = env::temp_dir().join(format!("gol-screenshots-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let template = dir.join("shot-{gen}.png");
+        let screenshots = Screenshots::new(
+            SoftwareRenderer::new(6, 4, 12, 8),
+            template.to_str().unwrap(),
+            [0, 2],
+        );
+        Headless::new(Grid::new(6, 4), 3)
+            .with_observer(Box::new(screenshots))
+            .run()
+            .unwrap();
+        let mut saved: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        saved.sort();
+        assert_eq!(saved, vec!["shot-0.png", "shot-2.png"]);
+        let (width, height, _) = decode(File::open(dir.join("shot-2.png")).unwrap());
+        assert_eq!((width, height), (12, 8));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,433 @@
+use crate::data::{Ages, Grid, Rule, Topology};
+use crate::simulation::headless::Observer;
+use crate::simulation::timeline::{alive, pack, unpack};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+// First line of every run log, followed by the board's size, rule and topology
+const MAGIC: &str = "gol-run-log 1";
+
+// At least every this many frames is kept in full, so seeking to any frame never means
+// going through more than this many others
+const KEYFRAME_INTERVAL: usize = 64;
+
+const KEYFRAME: u8 = b'K';
+const DELTA: u8 = b'D';
+
+/// Writes every state a board goes through to a run log, which `RunLog` can read back
+/// to get any of them again.
+///
+/// After a line of text saying how big the board is and what it runs by, each frame is
+/// either the whole board, a bit per cell, or the cells that flipped since the frame
+/// before, as the gaps between them. Numbers are LEB128 varints.
+pub struct RunLogWriter<W: Write> {
+    writer: W,
+    // Live cells in the last frame written
+    previous: Option<Vec<bool>>,
+    since_keyframe: usize,
+}
+
+impl<W: Write> RunLogWriter<W> {
+    pub const fn new(writer: W) -> Self {
+        Self {
+            writer,
+            previous: None,
+            since_keyframe: 0,
+        }
+    }
+
+    /// Adds the state the grid is in now, writing the header first if this is the first
+    #[allow(clippy::missing_errors_doc)]
+    pub fn write(&mut self, grid: &Grid) -> io::Result<()> {
+        let cells = alive(grid);
+        let mut frame = Vec::new();
+        write_varint(&mut frame, grid.generation());
+        let keyframe = pack(&cells);
+        let delta = self.previous.as_ref().map(|previous| {
+            let mut delta = Vec::new();
+            let flipped: Vec<usize> = (0..cells.len())
+                .filter(|&idx| cells[idx] != previous[idx])
+                .collect();
+            write_varint(&mut delta, flipped.len() as u64);
+            let mut last = 0;
+            for idx in flipped {
+                write_varint(&mut delta, (idx - last) as u64);
+                last = idx;
+            }
+            delta
+        });
+        match delta {
+            Some(delta)
+                if self.since_keyframe + 1 < KEYFRAME_INTERVAL
+                    && delta.len() < keyframe.len() * 8 =>
+            {
+                self.writer.write_all(&[DELTA])?;
+                self.writer.write_all(&frame)?;
+                self.writer.write_all(&delta)?;
+                self.since_keyframe += 1;
+            }
+            _ => {
+                if self.previous.is_none() {
+                    writeln!(
+                        self.writer,
+                        "{MAGIC} {}x{} {} {}",
+                        grid.width(),
+                        grid.height(),
+                        grid.rule(),
+                        grid.topology()
+                    )?;
+                }
+                self.writer.write_all(&[KEYFRAME])?;
+                self.writer.write_all(&frame)?;
+                for word in keyframe {
+                    self.writer.write_all(&word.to_le_bytes())?;
+                }
+                self.since_keyframe = 0;
+            }
+        }
+        self.previous = Some(cells);
+        Ok(())
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W: Write> Observer for RunLogWriter<W> {
+    fn observe(&mut self, grid: &Grid, _: &Ages) -> Result<(), Box<dyn Error>> {
+        self.write(grid)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.flush()?;
+        Ok(())
+    }
+}
+
+/// Returned when a run log can't be read
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseRunLogError(String);
+
+impl fmt::Display for ParseRunLogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid run log, {}", self.0)
+    }
+}
+
+impl Error for ParseRunLogError {}
+
+// Where a frame is in the log
+#[derive(Debug, Clone, Copy)]
+struct FrameIndex {
+    generation: u64,
+    keyframe: bool,
+    // Start of the cells, after the generation
+    offset: usize,
+}
+
+/// A run log read back into memory, see `RunLogWriter`, for going to any of its frames
+#[derive(Debug)]
+pub struct RunLog {
+    width: usize,
+    height: usize,
+    rule: Rule,
+    topology: Topology,
+    bytes: Vec<u8>,
+    frames: Vec<FrameIndex>,
+}
+
+impl RunLog {
+    #[allow(clippy::missing_errors_doc)]
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::from_bytes(fs::read(path)?)?)
+    }
+
+    #[allow(clippy::missing_errors_doc)]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, ParseRunLogError> {
+        let invalid = |reason: &str| ParseRunLogError(reason.to_string());
+        let header_end = bytes
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .ok_or_else(|| invalid("there's no header"))?;
+        let header = std::str::from_utf8(&bytes[..header_end])
+            .ok()
+            .and_then(|header| header.strip_prefix(MAGIC))
+            .ok_or_else(|| invalid(&format!("it doesn't start with {MAGIC}")))?;
+        let fields: Vec<&str> = header.split_whitespace().collect();
+        let [size, rule, topology] = fields[..] else {
+            return Err(invalid("the header should have a size, rule and topology"));
+        };
+        let (width, height) = size
+            .split_once('x')
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+            .ok_or_else(|| invalid(&format!("the size [{size}] should be like 100x80")))?;
+        let rule: Rule = rule.parse().map_err(|err| invalid(&format!("{err}")))?;
+        let topology: Topology = topology.parse().map_err(|err| invalid(&format!("{err}")))?;
+        let mut log = Self {
+            width,
+            height,
+            rule,
+            topology,
+            bytes,
+            frames: Vec::new(),
+        };
+        log.frames = log
+            .index(header_end + 1)
+            .ok_or_else(|| invalid("a frame is cut off or of an unknown kind"))?;
+        if log.frames.is_empty() {
+            return Err(invalid("there are no frames"));
+        }
+        Ok(log)
+    }
+
+    // Finds where every frame starts, or None if one of them is cut off, or isn't a
+    // keyframe or a delta following another frame
+    fn index(&self, mut at: usize) -> Option<Vec<FrameIndex>> {
+        let keyframe_len = self.area().div_ceil(64) * 8;
+        let mut frames = Vec::new();
+        while at < self.bytes.len() {
+            let kind = self.bytes[at];
+            at += 1;
+            let generation = read_varint(&self.bytes, &mut at)?;
+            let offset = at;
+            match kind {
+                KEYFRAME => at += keyframe_len,
+                DELTA if !frames.is_empty() => {
+                    for _ in 0..read_varint(&self.bytes, &mut at)? {
+                        read_varint(&self.bytes, &mut at)?;
+                    }
+                }
+                _ => return None,
+            }
+            if at > self.bytes.len() {
+                return None;
+            }
+            frames.push(FrameIndex {
+                generation,
+                keyframe: kind == KEYFRAME,
+                offset,
+            });
+        }
+        Some(frames)
+    }
+
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    pub const fn area(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub const fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub const fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Number of frames
+    pub const fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Logs always have at least one frame, so this is only here to go with `len`
+    pub const fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The generation the board was on in a frame
+    pub fn generation(&self, frame: usize) -> u64 {
+        self.frames[frame].generation
+    }
+
+    /// The lowest and highest generations in the log
+    pub fn generations(&self) -> (u64, u64) {
+        let generations = self.frames.iter().map(|frame| frame.generation);
+        let first = generations.clone().min().unwrap_or(0);
+        (first, generations.max().unwrap_or(0))
+    }
+
+    /// The last frame at a generation, after any edits made to it, or failing that the
+    /// last frame at the closest generation before it
+    pub fn find(&self, generation: u64) -> Option<usize> {
+        (0..self.frames.len())
+            .filter(|&n| self.frames[n].generation <= generation)
+            .max_by_key(|&n| (self.frames[n].generation, n))
+    }
+
+    /// Whether each cell is alive in a frame, in the same order as `Grid::get_idx`
+    pub fn cells(&self, frame: usize) -> Vec<bool> {
+        let start = (0..=frame)
+            .rev()
+            .find(|&n| self.frames[n].keyframe)
+            .unwrap_or(0);
+        let mut cells = Vec::new();
+        for n in start..=frame {
+            self.apply(n, &mut cells);
+        }
+        cells
+    }
+
+    /// Moves cells from the frame before on to the given one
+    pub fn apply(&self, frame: usize, cells: &mut Vec<bool>) {
+        let FrameIndex {
+            keyframe, offset, ..
+        } = self.frames[frame];
+        if keyframe {
+            let words: Vec<u64> = self.bytes[offset..offset + self.area().div_ceil(64) * 8]
+                .chunks_exact(8)
+                .map(|chunk| {
+                    let mut word = [0; 8];
+                    word.copy_from_slice(chunk);
+                    u64::from_le_bytes(word)
+                })
+                .collect();
+            *cells = unpack(&words, self.area());
+            return;
+        }
+        // Frames were checked to be whole when the log was read
+        let mut at = offset;
+        let count = read_varint(&self.bytes, &mut at).unwrap_or(0);
+        let mut idx = 0;
+        for _ in 0..count {
+            idx += read_varint(&self.bytes, &mut at).unwrap_or(0);
+            if let Some(cell) = usize::try_from(idx).ok().and_then(|idx| cells.get_mut(idx)) {
+                *cell = !*cell;
+            }
+        }
+    }
+
+    /// A grid in the state the board was in at a frame
+    pub fn grid(&self, frame: usize) -> Grid {
+        let mut grid = Grid::empty(self.width, self.height)
+            .with_rule(self.rule)
+            .with_topology(self.topology);
+        grid.restore(&self.cells(frame), self.generation(frame));
+        grid
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn write_varint(bytes: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        bytes.push((n & 0x7f) as u8 | 0x80);
+        n >>= 7;
+    }
+    bytes.push(n as u8);
+}
+
+// Reads a varint starting at `at`, moving it past the end
+fn read_varint(bytes: &[u8], at: &mut usize) -> Option<u64> {
+    let mut n = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*at)?;
+        *at += 1;
+        n |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(n);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::BoundingBox;
+    use crate::export::SharedBuffer;
+    use crate::simulation::headless::Headless;
+
+    fn log_of(grids: &mut dyn FnMut(&mut RunLogWriter<&mut Vec<u8>>)) -> RunLog {
+        let mut bytes = Vec::new();
+        grids(&mut RunLogWriter::new(&mut bytes));
+        RunLog::from_bytes(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut states = Vec::new();
+        let log = log_of(&mut |writer| {
+            let mut grid = Grid::new(30, 20).with_topology(Topology::Plane);
+            for _ in 0..150 {
+                writer.write(&grid).unwrap();
+                states.push(alive(&grid));
+                grid.advance();
+            }
+            // An edit comes in as another frame at the same generation
+            grid.clear(&BoundingBox {
+                min_i: 0,
+                min_j: 0,
+                max_i: 9,
+                max_j: 29,
+            });
+            writer.write(&grid).unwrap();
+            states.push(alive(&grid));
+        });
+        assert_eq!((log.width(), log.height()), (30, 20));
+        assert_eq!(
+            (log.rule(), log.topology()),
+            (Rule::CONWAY, Topology::Plane)
+        );
+        assert_eq!(log.len(), 151);
+        assert_eq!(log.generations(), (0, 150));
+        assert!(log.frames.iter().filter(|frame| frame.keyframe).count() >= 3);
+        for (frame, state) in states.iter().enumerate() {
+            assert_eq!(&log.cells(frame), state, "frame {frame}");
+        }
+        assert_eq!(log.find(150), Some(150));
+        assert_eq!(log.find(1000), Some(150));
+        assert_eq!(log.find(12), Some(12));
+        let grid = log.grid(12);
+        assert_eq!((grid.generation(), alive(&grid)), (12, states[12].clone()));
+    }
+
+    #[test]
+    fn test_headless() {
+        let output = SharedBuffer::default();
+        Headless::new(Grid::new(8, 8), 10)
+            .with_observer(Box::new(RunLogWriter::new(output.clone())))
+            .run()
+            .unwrap();
+        let log = RunLog::from_bytes(output.written()).unwrap();
+        assert_eq!((log.len(), log.generations()), (11, (0, 10)));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut bytes = Vec::new();
+        RunLogWriter::new(&mut bytes)
+            .write(&Grid::new(8, 8))
+            .unwrap();
+        assert!(RunLog::from_bytes(bytes.clone()).is_ok());
+        for broken in [&bytes[..bytes.len() - 1], &bytes[4..], &[][..]] {
+            assert!(RunLog::from_bytes(broken.to_vec()).is_err());
+        }
+        let header = format!("{MAGIC} 8x8 B3/S23 sphere\n");
+        let err = RunLog::from_bytes(header.into_bytes()).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Invalid run log, Unknown topology"));
+    }
+
+    #[test]
+    fn test_varint() {
+        for n in [0, 1, 127, 128, 300, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, n);
+            assert_eq!(read_varint(&bytes, &mut 0), Some(n));
+        }
+        assert_eq!(read_varint(&[0x80], &mut 0), None);
+    }
+}
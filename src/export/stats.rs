@@ -0,0 +1,180 @@
+use crate::data::{Ages, BoundingBox, Grid, Stats};
+use crate::simulation::headless::Observer;
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+const CSV_HEADER: &str = "generation,population,births,deaths,changed,min_i,min_j,max_i,max_j";
+
+/// How per-generation figures are written out
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum StatsFormat {
+    /// A header, then a row per generation, leaving the bounding box empty when there
+    /// are no live cells
+    #[default]
+    Csv,
+    /// A JSON object per line, with a null bounding box when there are no live cells
+    Json,
+}
+
+impl fmt::Display for StatsFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseStatsFormatError(String);
+
+impl fmt::Display for ParseStatsFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown stats format [{}], expected csv or json", self.0)
+    }
+}
+
+impl Error for ParseStatsFormatError {}
+
+impl FromStr for StatsFormat {
+    type Err = ParseStatsFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" | "jsonl" => Ok(Self::Json),
+            _ => Err(ParseStatsFormatError(s.to_string())),
+        }
+    }
+}
+
+/// Formats a generation's figures as a line, without the line break
+pub fn format_line(format: StatsFormat, stats: &Stats, bounds: Option<&BoundingBox>) -> String {
+    let Stats {
+        generation,
+        population,
+        births,
+        deaths,
+    } = *stats;
+    let changed = stats.changed();
+    match format {
+        StatsFormat::Csv => {
+            let bounds = bounds.map_or_else(
+                || ",,,".to_string(),
+                |b| format!("{},{},{},{}", b.min_i, b.min_j, b.max_i, b.max_j),
+            );
+            format!("{generation},{population},{births},{deaths},{changed},{bounds}")
+        }
+        StatsFormat::Json => {
+            let bounds = bounds.map_or_else(
+                || "null".to_string(),
+                |b| {
+                    format!(
+                        r#"{{"min_i":{},"min_j":{},"max_i":{},"max_j":{}}}"#,
+                        b.min_i, b.min_j, b.max_i, b.max_j
+                    )
+                },
+            );
+            format!(
+                concat!(
+                    r#"{{"generation":{},"population":{},"births":{},"deaths":{},"#,
+                    r#""changed":{},"bounding_box":{}}}"#
+                ),
+                generation, population, births, deaths, changed, bounds
+            )
+        }
+    }
+}
+
+/// Writes figures for every generation of a headless run, for plotting
+pub struct StatsWriter<W: Write> {
+    writer: W,
+    format: StatsFormat,
+    started: bool,
+}
+
+impl<W: Write> StatsWriter<W> {
+    pub const fn new(writer: W, format: StatsFormat) -> Self {
+        Self {
+            writer,
+            format,
+            started: false,
+        }
+    }
+}
+
+impl<W: Write> Observer for StatsWriter<W> {
+    fn observe(&mut self, grid: &Grid, _: &Ages) -> Result<(), Box<dyn Error>> {
+        if !self.started && self.format == StatsFormat::Csv {
+            writeln!(self.writer, "{CSV_HEADER}")?;
+        }
+        self.started = true;
+        let line = format_line(self.format, &grid.stats(), grid.bounding_box().as_ref());
+        writeln!(self.writer, "{line}")?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Coord, Status};
+    use crate::export::SharedBuffer;
+    use crate::simulation::headless::Headless;
+
+    fn run(format: StatsFormat) -> String {
+        // A lone cell, which dies straight away
+        let grid = Grid::from_fn(4, 4, |&Coord { i, j }| {
+            if (i, j) == (1, 2) {
+                Status::Alive
+            } else {
+                Status::Dead
+            }
+        });
+        let output = SharedBuffer::default();
+        Headless::new(grid, 1)
+            .with_observer(Box::new(StatsWriter::new(output.clone(), format)))
+            .run()
+            .unwrap();
+        let written = output.written();
+        String::from_utf8(written).unwrap()
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            run(StatsFormat::Csv),
+            format!("{CSV_HEADER}\n0,1,0,0,0,1,2,1,2\n1,0,0,1,1,,,,\n")
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            run(StatsFormat::Json),
+            concat!(
+                r#"{"generation":0,"population":1,"births":0,"deaths":0,"changed":0,"#,
+                r#""bounding_box":{"min_i":1,"min_j":2,"max_i":1,"max_j":2}}"#,
+                "\n",
+                r#"{"generation":1,"population":0,"births":0,"deaths":1,"changed":1,"#,
+                r#""bounding_box":null}"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_stats_format() {
+        assert_eq!("CSV".parse(), Ok(StatsFormat::Csv));
+        assert_eq!("jsonl".parse(), Ok(StatsFormat::Json));
+        assert!("xml".parse::<StatsFormat>().is_err());
+    }
+}
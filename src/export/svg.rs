@@ -0,0 +1,500 @@
+use crate::data::{Cell, Grid, GridIdx};
+use crate::rendering::layout::Spacing;
+use crate::rendering::major_gridline_colour;
+use crate::rendering::software::to_rgba8;
+use crate::rendering::theme::Theme;
+use crate::simulation::Frame;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// Pixels each cell takes up when an SVG is shown at its natural size
+pub const DEFAULT_CELL_SIZE: f32 = 10.;
+
+// Everything inside an SVG is measured in cells, with the board's top left corner at the
+// origin, and only scaled to pixels by its width and height
+const GRIDLINE_WIDTH: f32 = 0.1;
+const RULER_FONT_SIZE: f32 = 0.8;
+const RULER_TICK: f32 = 0.3;
+// Rough width of a digit as a share of the font size, to leave room for row labels
+const DIGIT_WIDTH: f32 = 0.6;
+
+/// Whether a path looks like it's meant to be an SVG
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"))
+}
+
+/// Saves an SVG document, replacing anything already at that path
+#[allow(clippy::missing_errors_doc)]
+pub fn save<P: AsRef<Path>>(document: &str, path: P) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+    fs::write(path, document).map_err(|e| format!("Could not write {}: {e}", path.display()).into())
+}
+
+/// A rectangle of cells, in columns and rows from the top left of the drawing
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+struct Rect {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+/// Draws boards, or regions of them, as SVG vector images.
+///
+/// Runs of live cells that share edges get merged into larger rectangles and all of them
+/// go into a single path, so big patterns stay compact.
+#[derive(Debug, Clone)]
+pub struct Svg {
+    cell_size: f32,
+    spacing: Spacing,
+    theme: Theme,
+    ruler: Option<usize>,
+    region: Option<(Range<usize>, Range<usize>)>,
+}
+
+impl Default for Svg {
+    fn default() -> Self {
+        Self {
+            cell_size: DEFAULT_CELL_SIZE,
+            spacing: Spacing::default(),
+            theme: Theme::default(),
+            ruler: None,
+            region: None,
+        }
+    }
+}
+
+impl Svg {
+    /// Draws whole boards with the default theme, spacing and cell size, and no ruler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes each cell this many pixels across at the image's natural size
+    #[must_use]
+    pub const fn with_cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Separates cells with the given spacing, rather than the default gaps
+    #[must_use]
+    pub const fn with_spacing(mut self, spacing: Spacing) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Uses the given theme rather than the default one
+    #[must_use]
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Labels every this many rows and columns with their coordinates along the top and
+    /// left edges
+    #[must_use]
+    pub const fn with_ruler(mut self, every: usize) -> Self {
+        self.ruler = if every == 0 { None } else { Some(every) };
+        self
+    }
+
+    /// Only draws the given rows and columns of the board, which get clipped to its size
+    #[must_use]
+    pub const fn with_region(mut self, rows: Range<usize>, columns: Range<usize>) -> Self {
+        self.region = Some((rows, columns));
+        self
+    }
+
+    /// Draws a grid as an SVG document
+    pub fn render(&self, grid: &Grid) -> String {
+        let width = grid.width();
+        self.draw(width, grid.height(), &|i, j| {
+            grid.get_idx(&GridIdx(i * width + j))
+                .is_some_and(Cell::alive)
+        })
+    }
+
+    /// Draws a frame captured from the simulation of a board with the given dimensions
+    pub fn render_frame(&self, frame: &Frame, columns: usize, rows: usize) -> String {
+        self.draw(columns, rows, &|i, j| {
+            frame.alive(&GridIdx(i * columns + j))
+        })
+    }
+
+    // Draws a board where `alive_at` says whether the cell in row i and column j is alive
+    #[allow(clippy::cast_precision_loss)]
+    fn draw(&self, columns: usize, rows: usize, alive_at: &dyn Fn(usize, usize) -> bool) -> String {
+        let (rows, columns) = self.region(columns, rows);
+        let gap = match self.spacing {
+            Spacing::Gaps(ratio) => ratio,
+            Spacing::Gridlines(_) => 0.,
+        };
+        let width = columns.len() as f32 + gap;
+        let height = rows.len() as f32 + gap;
+        let (left, top) = self.margins(&rows);
+        let (total_width, total_height) = (left + width, top + height);
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+            num(total_width * self.cell_size),
+            num(total_height * self.cell_size),
+            num(-left),
+            num(-top),
+            num(total_width),
+            num(total_height),
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}"{}/>"#,
+            num(-left),
+            num(-top),
+            num(total_width),
+            num(total_height),
+            fill(self.theme.background),
+        );
+        // Merging only works if neighbouring cells actually touch
+        let merge = gap == 0.;
+        for (alive, colour) in [(false, self.theme.dead), (true, self.theme.alive)] {
+            let rects = rects(alive_at, &rows, &columns, alive, merge);
+            if !rects.is_empty() {
+                let _ = writeln!(
+                    svg,
+                    r#"<path d="{}"{}/>"#,
+                    cells_path(&rects, gap),
+                    fill(colour)
+                );
+            }
+        }
+        if let Spacing::Gridlines(major_every) = self.spacing {
+            self.write_gridlines(&mut svg, columns.len(), rows.len(), major_every);
+        }
+        if let Some(every) = self.ruler {
+            self.write_ruler(&mut svg, &rows, &columns, every, gap);
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn region(&self, columns: usize, rows: usize) -> (Range<usize>, Range<usize>) {
+        let clip = |range: &Range<usize>, limit: usize| {
+            let end = range.end.min(limit);
+            range.start.min(end)..end
+        };
+        match &self.region {
+            Some((region_rows, region_columns)) => {
+                (clip(region_rows, rows), clip(region_columns, columns))
+            }
+            None => (0..rows, 0..columns),
+        }
+    }
+
+    // Room left of and above the board for ruler labels
+    #[allow(clippy::cast_precision_loss)]
+    fn margins(&self, rows: &Range<usize>) -> (f32, f32) {
+        if self.ruler.is_none() {
+            return (0., 0.);
+        }
+        let digits = rows.end.saturating_sub(1).to_string().len() as f32;
+        (
+            digits * DIGIT_WIDTH * RULER_FONT_SIZE + RULER_TICK * 2.,
+            RULER_FONT_SIZE + RULER_TICK * 2.,
+        )
+    }
+
+    // Lines on every boundary between cells, the same as on screen, but centred on the
+    // boundaries since there are no pixels to line up with
+    fn write_gridlines(&self, svg: &mut String, columns: usize, rows: usize, major_every: usize) {
+        for major in [false, true] {
+            let mut d = String::new();
+            for k in (0..=columns).filter(|k| (k % major_every == 0) == major) {
+                let _ = write!(d, "M{k} 0V{rows}");
+            }
+            for k in (0..=rows).filter(|k| (k % major_every == 0) == major) {
+                // Rows count up from the bottom, like they do on screen
+                let _ = write!(d, "M0 {}H{columns}", rows - k);
+            }
+            if !d.is_empty() {
+                let colour = if major {
+                    major_gridline_colour(&self.theme)
+                } else {
+                    self.theme.background
+                };
+                let _ = writeln!(
+                    svg,
+                    r#"<path d="{d}" fill="none" stroke-width="{}"{}/>"#,
+                    num(GRIDLINE_WIDTH),
+                    stroke(colour),
+                );
+            }
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn write_ruler(
+        &self,
+        svg: &mut String,
+        rows: &Range<usize>,
+        columns: &Range<usize>,
+        every: usize,
+        gap: f32,
+    ) {
+        let centre = |k: usize| num(k as f32 + f32::midpoint(gap, 1.));
+        let mut ticks = String::new();
+        let mut labels = String::new();
+        for j in columns.clone().filter(|j| j % every == 0) {
+            let x = centre(j - columns.start);
+            let _ = write!(ticks, "M{x} 0v{}", num(-RULER_TICK));
+            let _ = writeln!(
+                labels,
+                r#"<text x="{x}" y="{}" text-anchor="middle">{j}</text>"#,
+                num(-RULER_TICK * 2.)
+            );
+        }
+        for i in rows.clone().filter(|i| i % every == 0) {
+            let y = centre(rows.end - 1 - i);
+            let _ = write!(ticks, "M0 {y}h{}", num(-RULER_TICK));
+            let _ = writeln!(
+                labels,
+                r#"<text x="{}" y="{y}" text-anchor="end" dominant-baseline="central">{i}</text>"#,
+                num(-RULER_TICK * 2.)
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<path d="{ticks}" fill="none" stroke-width="{}"{}/>"#,
+            num(GRIDLINE_WIDTH),
+            stroke(self.theme.text),
+        );
+        let _ = writeln!(
+            svg,
+            r#"<g font-family="sans-serif" font-size="{}"{}>"#,
+            num(RULER_FONT_SIZE),
+            fill(self.theme.text),
+        );
+        svg.push_str(&labels);
+        svg.push_str("</g>\n");
+    }
+}
+
+// Rectangles covering the cells in a region that are alive, or dead, ordered by where
+// they end. Rows are flipped so the first one ends up at the bottom, like on screen.
+//
+// When merging, each row is split into runs of cells, and runs that line up exactly
+// with one in the row above extend its rectangle rather than starting a new one.
+fn rects(
+    alive_at: &dyn Fn(usize, usize) -> bool,
+    rows: &Range<usize>,
+    columns: &Range<usize>,
+    alive: bool,
+    merge: bool,
+) -> Vec<Rect> {
+    let mut done = Vec::new();
+    // Rectangles still being extended, from their columns to the row they started on
+    let mut open: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for y in 0..rows.len() {
+        let i = rows.end - 1 - y;
+        let matches = |x: usize| alive_at(i, columns.start + x) == alive;
+        let mut runs = Vec::new();
+        let mut x = 0;
+        while x < columns.len() {
+            if matches(x) {
+                let start = x;
+                x += 1;
+                while merge && x < columns.len() && matches(x) {
+                    x += 1;
+                }
+                runs.push((start, x));
+            } else {
+                x += 1;
+            }
+        }
+        let mut next = BTreeMap::new();
+        for run in runs {
+            next.insert(run, open.remove(&run).unwrap_or(y));
+        }
+        close(&mut done, open, y);
+        open = if merge {
+            next
+        } else {
+            close(&mut done, next, y + 1);
+            BTreeMap::new()
+        };
+    }
+    close(&mut done, open, rows.len());
+    done
+}
+
+fn close(done: &mut Vec<Rect>, open: BTreeMap<(usize, usize), usize>, y: usize) {
+    done.extend(open.into_iter().map(|((start, end), top)| Rect {
+        x: start,
+        y: top,
+        width: end - start,
+        height: y - top,
+    }));
+}
+
+// Path data for a set of rectangles, each shrunk by the gap so they sit where cells do
+// on screen, with a gap before the first cell in each direction as well as between them
+#[allow(clippy::cast_precision_loss)]
+fn cells_path(rects: &[Rect], gap: f32) -> String {
+    let mut d = String::new();
+    for rect in rects {
+        let width = num(rect.width as f32 - gap);
+        let _ = write!(
+            d,
+            "M{} {}h{width}v{}h-{width}z",
+            num(rect.x as f32 + gap),
+            num(rect.y as f32 + gap),
+            num(rect.height as f32 - gap),
+        );
+    }
+    d
+}
+
+fn fill(colour: [f32; 4]) -> String {
+    paint("fill", colour)
+}
+
+fn stroke(colour: [f32; 4]) -> String {
+    paint("stroke", colour)
+}
+
+// A colour as an attribute, leaving out the opacity unless it's needed
+fn paint(attribute: &str, colour: [f32; 4]) -> String {
+    let [r, g, b, a] = to_rgba8(colour);
+    let mut paint = format!(r##" {attribute}="#{r:02x}{g:02x}{b:02x}""##);
+    if a < u8::MAX {
+        let _ = write!(paint, r#" {attribute}-opacity="{}""#, num(colour[3]));
+    }
+    paint
+}
+
+// Formats a number with as few digits as it needs, up to a thousandth
+fn num(value: f32) -> String {
+    let formatted = format!("{value:.3}");
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    if trimmed == "-0" {
+        "0".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Coord, Status};
+
+    fn grid(rows: &[&str]) -> Grid {
+        // Written top row first, the way they're drawn
+        let height = rows.len();
+        Grid::from_fn(rows[0].len(), height, |&Coord { i, j }| {
+            if rows[height - 1 - i].as_bytes()[j] == b'#' {
+                Status::Alive
+            } else {
+                Status::Dead
+            }
+        })
+    }
+
+    fn alive(grid: &Grid, i: usize, j: usize) -> bool {
+        grid.cells()[i][j].alive()
+    }
+
+    fn rect(x: usize, y: usize, width: usize, height: usize) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_rects_merge_runs_that_line_up() {
+        let grid = grid(&["##..", "##..", ".###", "...."]);
+        let mut live = rects(&|i, j| alive(&grid, i, j), &(0..4), &(0..4), true, true);
+        live.sort_by_key(|r| (r.y, r.x));
+        assert_eq!(live, vec![rect(0, 0, 2, 2), rect(1, 2, 3, 1)]);
+        let mut unmerged = rects(&|i, j| alive(&grid, i, j), &(0..4), &(0..4), true, false);
+        unmerged.sort_by_key(|r| (r.y, r.x));
+        assert_eq!(unmerged.len(), 7);
+        assert!(unmerged.iter().all(|r| r.width == 1 && r.height == 1));
+    }
+
+    #[test]
+    fn test_rects_in_a_region() {
+        let grid = grid(&["#...", ".##.", ".##.", "...#"]);
+        let live = rects(&|i, j| alive(&grid, i, j), &(1..3), &(1..3), true, true);
+        assert_eq!(live, vec![rect(0, 0, 2, 2)]);
+    }
+
+    #[test]
+    fn test_render() {
+        let grid = grid(&["##", ".#"]);
+        let svg = Svg::new()
+            .with_spacing(Spacing::Gaps(0.))
+            .with_theme(Theme::named("high-contrast").unwrap())
+            .render(&grid);
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20" viewBox="0 0 2 2">"#
+        ));
+        assert!(svg.contains(r##"<path d="M0 1h1v1h-1z" fill="#000000"/>"##));
+        // The live cell in the bottom row doesn't line up with the top row's run, so it
+        // stays a rectangle of its own
+        assert!(
+            svg.contains(r##"<path d="M0 0h2v1h-2zM1 1h1v1h-1z" fill="#ffffff"/>"##),
+            "{svg}"
+        );
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_render_with_gaps_gridlines_and_ruler() {
+        let grid = grid(&["#.", ".."]);
+        let gapped = Svg::new().with_spacing(Spacing::Gaps(0.5)).render(&grid);
+        assert!(gapped.contains(r#"viewBox="0 0 2.5 2.5""#));
+        assert!(gapped.contains(r#"<path d="M0.5 0.5h0.5v0.5h-0.5z""#));
+
+        let lined = Svg::new().with_spacing(Spacing::Gridlines(2)).render(&grid);
+        assert!(lined.contains(r#"<path d="M1 0V2M0 1H2" fill="none""#));
+        assert!(lined.contains(r#"<path d="M0 0V2M2 0V2M0 2H2M0 0H2" fill="none""#));
+
+        let ruled = Svg::new()
+            .with_spacing(Spacing::Gaps(0.))
+            .with_ruler(1)
+            .with_region(0..2, 1..5)
+            .render(&grid);
+        assert!(ruled.contains(r#"text-anchor="middle">1</text>"#));
+        assert!(!ruled.contains(r#"text-anchor="middle">0</text>"#));
+        assert!(ruled.contains(r#"dominant-baseline="central">0</text>"#));
+    }
+
+    #[test]
+    fn test_num_and_paint() {
+        assert_eq!(num(2.), "2");
+        assert_eq!(num(0.25), "0.25");
+        assert_eq!(num(-0.0001), "0");
+        assert_eq!(fill([1., 0., 0., 1.]), r##" fill="#ff0000""##);
+        assert_eq!(
+            stroke([0., 0., 1., 0.5]),
+            r##" stroke="#0000ff" stroke-opacity="0.5""##
+        );
+    }
+
+    #[test]
+    fn test_is_svg() {
+        assert!(is_svg(Path::new("board.svg")));
+        assert!(is_svg(Path::new("out/board-{gen}.SVG")));
+        assert!(!is_svg(Path::new("board.png")));
+    }
+}
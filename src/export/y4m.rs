@@ -0,0 +1,305 @@
+use crate::data::{Ages, Grid};
+use crate::rendering::software::{Image, SoftwareRenderer};
+use crate::simulation::headless::Observer;
+use crate::simulation::Frame;
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+/// How much colour detail each frame of a stream keeps
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Chroma {
+    /// One colour sample for every 2x2 block of pixels, which is what most encoders and
+    /// players expect
+    #[default]
+    C420,
+    /// A colour sample for every pixel, which keeps cell edges sharp
+    C444,
+}
+
+impl Chroma {
+    // Colour space tag in the stream header. Samples are full range, converted the way
+    // JPEG does it, which ffmpeg knows to expect from the `jpeg` siting and range tags.
+    const fn tag(self) -> &'static str {
+        match self {
+            Self::C420 => "C420jpeg XCOLORRANGE=FULL",
+            Self::C444 => "C444 XCOLORRANGE=FULL",
+        }
+    }
+
+    // Pixels across and down each colour sample covers
+    const fn block_size(self) -> usize {
+        match self {
+            Self::C420 => 2,
+            Self::C444 => 1,
+        }
+    }
+}
+
+impl fmt::Display for Chroma {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::C420 => "420",
+            Self::C444 => "444",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseChromaError(String);
+
+impl fmt::Display for ParseChromaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown chroma subsampling [{}], expected 420 or 444",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseChromaError {}
+
+impl FromStr for Chroma {
+    type Err = ParseChromaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "420" => Ok(Self::C420),
+            "444" => Ok(Self::C444),
+            _ => Err(ParseChromaError(s.to_string())),
+        }
+    }
+}
+
+/// Writes frames as an uncompressed YUV4MPEG2 stream, which encoders like ffmpeg can read
+/// straight from a pipe
+pub struct Y4mWriter<W: Write> {
+    writer: W,
+    width: u32,
+    height: u32,
+    frames_per_second: u16,
+    chroma: Chroma,
+    frames: usize,
+    // Planes of the frame being written, kept around to save reallocating them
+    planes: [Vec<u8>; 3],
+}
+
+impl<W: Write> Y4mWriter<W> {
+    /// Streams frames of the given size, which play back at the given rate
+    pub fn new(writer: W, width: u32, height: u32, frames_per_second: u16, chroma: Chroma) -> Self {
+        Self {
+            writer,
+            width,
+            height,
+            frames_per_second,
+            chroma,
+            frames: 0,
+            planes: Default::default(),
+        }
+    }
+
+    /// Number of frames written so far
+    pub const fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Writes an image as the next frame, and the stream header before the first one.
+    ///
+    /// Every image must be the size the stream was created with.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn write(&mut self, image: &Image) -> Result<(), Box<dyn Error>> {
+        if (image.width(), image.height()) != (self.width, self.height) {
+            return Err(format!(
+                "Can't add a {}x{} frame to a {}x{} video",
+                image.width(),
+                image.height(),
+                self.width,
+                self.height
+            )
+            .into());
+        }
+        if self.frames == 0 {
+            writeln!(
+                self.writer,
+                "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 {}",
+                self.width,
+                self.height,
+                self.frames_per_second,
+                self.chroma.tag()
+            )?;
+        }
+        self.fill_planes(image);
+        self.writer.write_all(b"FRAME\n")?;
+        for plane in &self.planes {
+            self.writer.write_all(plane)?;
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Flushes anything still buffered, giving back the underlying writer
+    #[allow(clippy::missing_errors_doc)]
+    pub fn finish(mut self) -> Result<W, Box<dyn Error>> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    // Converts an image to Y, Cb and Cr planes, averaging the colour of each block of
+    // pixels that shares a sample
+    fn fill_planes(&mut self, image: &Image) {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let block = self.chroma.block_size();
+        let pixels = image.as_raw();
+        let [luma, blue, red] = &mut self.planes;
+        luma.clear();
+        luma.extend(pixels.chunks_exact(4).map(|pixel| ycbcr(pixel)[0]));
+        blue.clear();
+        red.clear();
+        for y in (0..height).step_by(block) {
+            for x in (0..width).step_by(block) {
+                let (mut sums, mut count) = ([0_u32; 2], 0);
+                for py in y..(y + block).min(height) {
+                    for px in x..(x + block).min(width) {
+                        let idx = (py * width + px) * 4;
+                        let [_, cb, cr] = ycbcr(&pixels[idx..idx + 4]);
+                        sums[0] += u32::from(cb);
+                        sums[1] += u32::from(cr);
+                        count += 1;
+                    }
+                }
+                blue.push(average(sums[0], count));
+                red.push(average(sums[1], count));
+            }
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn average(sum: u32, count: u32) -> u8 {
+    ((sum + count / 2) / count.max(1)) as u8
+}
+
+/// Converts an RGB(A) pixel to full range Y, Cb and Cr, as used by JPEG
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn ycbcr(pixel: &[u8]) -> [u8; 3] {
+    let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(f32::from);
+    let to_byte = |value: f32| value.round().clamp(0., 255.) as u8;
+    [
+        to_byte(0.299 * r + 0.587 * g + 0.114 * b),
+        to_byte(128. - 0.168_736 * r - 0.331_264 * g + 0.5 * b),
+        to_byte(128. + 0.5 * r - 0.418_688 * g - 0.081_312 * b),
+    ]
+}
+
+/// Streams every generation of a headless run as a frame of video
+pub struct Video<W: Write> {
+    renderer: SoftwareRenderer,
+    writer: Option<Y4mWriter<W>>,
+}
+
+impl<W: Write> Video<W> {
+    /// Draws frames with the given renderer, which should make images the size the
+    /// writer expects
+    pub const fn new(renderer: SoftwareRenderer, writer: Y4mWriter<W>) -> Self {
+        Self {
+            renderer,
+            writer: Some(writer),
+        }
+    }
+}
+
+impl<W: Write> Observer for Video<W> {
+    fn observe(&mut self, grid: &Grid, ages: &Ages) -> Result<(), Box<dyn Error>> {
+        if let Some(writer) = &mut self.writer {
+            writer.write(&self.renderer.render(&Frame::new(grid, ages)))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(writer) = self.writer.take() {
+            writer.finish()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::SharedBuffer;
+    use crate::simulation::headless::Headless;
+
+    #[test]
+    fn test_ycbcr() {
+        assert_eq!(ycbcr(&[0, 0, 0, 255]), [0, 128, 128]);
+        assert_eq!(ycbcr(&[255, 255, 255, 255]), [255, 128, 128]);
+        assert_eq!(ycbcr(&[255, 0, 0, 255]), [76, 85, 255]);
+    }
+
+    #[test]
+    fn test_write_420() {
+        // 3x2, so the right hand colour samples only cover one column
+        let mut pixels = Vec::new();
+        for colour in [
+            [0, 0, 0],
+            [0, 0, 0],
+            [255, 0, 0],
+            [0, 0, 0],
+            [0, 0, 0],
+            [255, 0, 0],
+        ] {
+            pixels.extend_from_slice(&colour);
+            pixels.push(255);
+        }
+        let image = Image::from_raw(3, 2, pixels).unwrap();
+        let mut writer = Y4mWriter::new(Vec::new(), 3, 2, 30, Chroma::C420);
+        writer.write(&image).unwrap();
+        writer.write(&image).unwrap();
+        assert_eq!(writer.frames(), 2);
+        let written = writer.finish().unwrap();
+        let header = b"YUV4MPEG2 W3 H2 F30:1 Ip A1:1 C420jpeg XCOLORRANGE=FULL\n";
+        assert!(written.starts_with(header));
+        let frame = [
+            &b"FRAME\n"[..],
+            &[0, 0, 76, 0, 0, 76],
+            &[128, 85],
+            &[128, 255],
+        ]
+        .concat();
+        assert_eq!(&written[header.len()..], [&frame[..], &frame[..]].concat());
+    }
+
+    #[test]
+    fn test_write_rejects_other_sizes() {
+        let mut writer = Y4mWriter::new(Vec::new(), 4, 4, 30, Chroma::C444);
+        assert!(writer.write(&Image::new(4, 3, [0, 0, 0, 255])).is_err());
+        assert_eq!(writer.frames(), 0);
+    }
+
+    #[test]
+    fn test_video() {
+        let output = SharedBuffer::default();
+        let writer = Y4mWriter::new(output.clone(), 8, 4, 10, Chroma::C444);
+        let video = Video::new(SoftwareRenderer::new(4, 2, 8, 4), writer);
+        Headless::new(Grid::new(4, 2), 2)
+            .with_observer(Box::new(video))
+            .run()
+            .unwrap();
+        let written = output.written();
+        let header = b"YUV4MPEG2 W8 H4 F10:1 Ip A1:1 C444 XCOLORRANGE=FULL\n".len();
+        // Generations 0 to 2, each with three full size planes
+        assert_eq!(written.len(), header + 3 * (6 + 3 * 8 * 4));
+    }
+
+    #[test]
+    fn test_parse_chroma() {
+        assert_eq!("420".parse(), Ok(Chroma::C420));
+        assert_eq!(" 444".parse(), Ok(Chroma::C444));
+        assert!("422".parse::<Chroma>().is_err());
+        assert_eq!(Chroma::C444.to_string(), "444");
+    }
+}
@@ -1,10 +1,19 @@
 extern crate rand;
 #[macro_use]
 extern crate gfx;
+extern crate color_quant;
 extern crate gfx_device_gl;
 extern crate gfx_window_glutin;
+extern crate gif;
 extern crate glutin;
+extern crate png;
 extern crate rayon;
 
+pub mod census;
+pub mod config;
 pub mod data;
+pub mod export;
+pub mod library;
 pub mod rendering;
+pub mod sat;
+pub mod simulation;
@@ -0,0 +1,219 @@
+use crate::census::pattern::Pattern;
+use crate::census::rle;
+use std::error::Error;
+use std::fmt;
+
+/// What sort of pattern an entry in the library is
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Category {
+    /// Never changes
+    StillLife,
+    /// Comes back to how it started, in the same place
+    Oscillator,
+    /// Comes back to how it started, somewhere else
+    Spaceship,
+    /// Keeps firing spaceships
+    Gun,
+    /// Starts small but takes a long time to settle down
+    Methuselah,
+}
+
+impl Category {
+    pub const ALL: [Self; 5] = [
+        Self::StillLife,
+        Self::Oscillator,
+        Self::Spaceship,
+        Self::Gun,
+        Self::Methuselah,
+    ];
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::StillLife => "still lifes",
+            Self::Oscillator => "oscillators",
+            Self::Spaceship => "spaceships",
+            Self::Gun => "guns",
+            Self::Methuselah => "methuselahs",
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A well known pattern for Conway's Game of Life that comes with the crate
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Entry {
+    /// What to ask for it by, like `gosper-gun`
+    pub name: &'static str,
+    pub category: Category,
+    rle: &'static str,
+}
+
+impl Entry {
+    /// Its full name, from the `#N` line of its RLE
+    pub fn title(&self) -> &'static str {
+        self.rle
+            .lines()
+            .find_map(|line| line.strip_prefix("#N "))
+            .unwrap_or(self.name)
+    }
+
+    /// The pattern the way up it's usually shown
+    pub fn pattern(&self) -> Pattern {
+        // Every entry is checked to decode by the tests
+        rle::decode(self.rle).unwrap_or_default()
+    }
+
+    /// Its RLE, with comments saying what it is
+    pub const fn rle(&self) -> &'static str {
+        self.rle
+    }
+}
+
+const fn entry(name: &'static str, category: Category, rle: &'static str) -> Entry {
+    Entry {
+        name,
+        category,
+        rle,
+    }
+}
+
+/// Everything in the library, grouped by category in the order of `Category::ALL`
+pub const PATTERNS: [Entry; 22] = [
+    entry("block", Category::StillLife, include_str!("block.rle")),
+    entry("beehive", Category::StillLife, include_str!("beehive.rle")),
+    entry("loaf", Category::StillLife, include_str!("loaf.rle")),
+    entry("boat", Category::StillLife, include_str!("boat.rle")),
+    entry("tub", Category::StillLife, include_str!("tub.rle")),
+    entry("pond", Category::StillLife, include_str!("pond.rle")),
+    entry("eater-1", Category::StillLife, include_str!("eater-1.rle")),
+    entry("blinker", Category::Oscillator, include_str!("blinker.rle")),
+    entry("toad", Category::Oscillator, include_str!("toad.rle")),
+    entry("beacon", Category::Oscillator, include_str!("beacon.rle")),
+    entry("pulsar", Category::Oscillator, include_str!("pulsar.rle")),
+    entry(
+        "pentadecathlon",
+        Category::Oscillator,
+        include_str!("pentadecathlon.rle"),
+    ),
+    entry("glider", Category::Spaceship, include_str!("glider.rle")),
+    entry("lwss", Category::Spaceship, include_str!("lwss.rle")),
+    entry("mwss", Category::Spaceship, include_str!("mwss.rle")),
+    entry("hwss", Category::Spaceship, include_str!("hwss.rle")),
+    entry("gosper-gun", Category::Gun, include_str!("gosper-gun.rle")),
+    entry("simkin-gun", Category::Gun, include_str!("simkin-gun.rle")),
+    entry(
+        "r-pentomino",
+        Category::Methuselah,
+        include_str!("r-pentomino.rle"),
+    ),
+    entry("diehard", Category::Methuselah, include_str!("diehard.rle")),
+    entry("acorn", Category::Methuselah, include_str!("acorn.rle")),
+    entry(
+        "pi-heptomino",
+        Category::Methuselah,
+        include_str!("pi-heptomino.rle"),
+    ),
+];
+
+/// Returned when there's nothing in the library by a name
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct UnknownPattern(String);
+
+impl fmt::Display for UnknownPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = PATTERNS.iter().map(|entry| entry.name).collect();
+        write!(
+            f,
+            "Unknown pattern [{}], expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for UnknownPattern {}
+
+/// Looks up a pattern in the library by name, ignoring case
+#[allow(clippy::missing_errors_doc)]
+pub fn find(name: &str) -> Result<&'static Entry, UnknownPattern> {
+    PATTERNS
+        .iter()
+        .find(|entry| entry.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| UnknownPattern(name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::census::{classify, lifespan, names};
+    use crate::data::Rule;
+
+    #[test]
+    fn test_entries() {
+        let mut categories: Vec<Category> = PATTERNS.iter().map(|entry| entry.category).collect();
+        categories.dedup();
+        assert_eq!(categories, Category::ALL);
+        for entry in &PATTERNS {
+            assert!(rle::decode(entry.rle()).is_ok(), "{}", entry.name);
+            assert_ne!(entry.title(), entry.name, "{} has no title", entry.name);
+            assert_eq!(find(&entry.name.to_uppercase()), Ok(entry));
+        }
+        assert!(find("gosper gun").is_err());
+    }
+
+    #[test]
+    fn test_objects_are_what_they_say() {
+        let objects = PATTERNS.iter().filter(|entry| {
+            [
+                Category::StillLife,
+                Category::Oscillator,
+                Category::Spaceship,
+            ]
+            .contains(&entry.category)
+        });
+        for entry in objects {
+            let classification = classify(&entry.pattern(), Rule::CONWAY).unwrap();
+            let title = entry.title().to_lowercase();
+            assert_eq!(
+                names::name(&classification.key, Rule::CONWAY),
+                Some(&*title)
+            );
+            let moves = classification.displacement != (0, 0);
+            let still = classification.period == 1;
+            let expected = match (still, moves) {
+                (true, _) => Category::StillLife,
+                (false, false) => Category::Oscillator,
+                (false, true) => Category::Spaceship,
+            };
+            assert_eq!(entry.category, expected, "{}", entry.name);
+        }
+    }
+
+    #[test]
+    fn test_guns_fire_gliders() {
+        for (name, period) in [("gosper-gun", 30), ("simkin-gun", 120)] {
+            let gun = find(name).unwrap().pattern();
+            // Back to the same phase, with a glider for every period gone by
+            let lifespan = lifespan::measure(&gun, Rule::CONWAY, period * 3);
+            assert_eq!(lifespan.settled, None);
+            assert_eq!(
+                lifespan.final_population,
+                gun.population() + 5 * 3,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_methuselahs_last() {
+        let diehard = lifespan::measure(&find("diehard").unwrap().pattern(), Rule::CONWAY, 200);
+        assert_eq!((diehard.settled, diehard.final_population), (Some(130), 0));
+        let pi = lifespan::measure(&find("pi-heptomino").unwrap().pattern(), Rule::CONWAY, 200);
+        assert_eq!(pi.settled, Some(173));
+    }
+}
@@ -1,27 +1,653 @@
 extern crate clap;
 extern crate gol;
 
-use clap::{App, Arg, ArgMatches};
-use gol::data::Grid;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gol::census::collision::Sweep;
+use gol::census::pattern::Pattern;
+use gol::census::search::{Search, Summary};
+use gol::census::Census;
+use gol::census::{lifespan, rle};
+use gol::config::Config;
+use gol::data::{Combine, Coord, Grid, Rule, Status, Topology, Transform};
+use gol::export::animation::{
+    Animation, AnimationOptions, Palette, Recording, DEFAULT_RECORDING_PATH,
+};
+use gol::export::png::Screenshots;
+use gol::export::runlog::{RunLog, RunLogWriter};
+use gol::export::stats::{StatsFormat, StatsWriter};
+use gol::export::svg::Svg;
+use gol::export::y4m::{Chroma, Video, Y4mWriter};
+use gol::export::{output_path, ImageSize, DEFAULT_SCREENSHOT_PATH};
+use gol::library::{self, Category};
 use gol::rendering;
+use gol::rendering::display::DisplayMode;
+use gol::rendering::layout::{Spacing, DEFAULT_CELL_GAP};
+use gol::rendering::software::SoftwareRenderer;
+use gol::rendering::theme::{parse_colour, Theme, COLOUR_NAMES, THEME_NAMES};
+use gol::sat::periodic::{self, Periodic};
+use gol::sat::predecessor::{Predecessor, Predecessors};
+use gol::simulation::headless::Headless;
 use std::error::Error;
 use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 use std::process::exit;
 use std::str::FromStr;
+use std::time::Duration;
 
 fn main() {
     exit(match inner_main() {
         Ok(()) => 0,
         Err(err) => {
-            println!("{err}");
+            eprintln!("{err}");
             1
         }
     })
 }
 
 fn inner_main() -> Result<(), Box<dyn Error>> {
-    let matches = App::new("Game of Life")
-        .version(version().as_ref())
+    let version = version();
+    let matches = cli(&version).get_matches();
+
+    let grid_width = get_number("grid-width", Some(0), &matches);
+    let grid_height = get_number("grid-height", Some(0), &matches);
+    let window_width = get_number("window-width", Some(0), &matches);
+    let window_height = get_number("window-height", Some(0), &matches);
+    let updates_per_second = get_number("update-rate", Some(0), &matches);
+    let rewind_memory: usize = get_number("rewind-memory", None, &matches);
+    let exports = Exports::new(updates_per_second, &matches)?;
+
+    let rule: Rule = matches.value_of("rule").unwrap_or_default().parse()?;
+    let topology: Topology = matches.value_of("topology").unwrap_or_default().parse()?;
+    let display_mode: DisplayMode = matches.value_of("display").unwrap_or_default().parse()?;
+    let config = match matches.value_of("config") {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let mut known_keys = vec!["theme", "cell-gap", "gridlines"];
+    known_keys.extend(COLOUR_NAMES);
+    config.check_keys(&known_keys)?;
+    let theme = theme(&matches, &config)?;
+    let spacing = spacing(&matches, &config)?;
+
+    let grid = starting_grid(grid_width, grid_height, rule, topology, &matches)?;
+    if let Some(matches) = matches.subcommand_matches("census") {
+        return census(grid, matches);
+    }
+    if let Some(matches) = matches.subcommand_matches("search") {
+        return search(rule, matches);
+    }
+    if let Some(matches) = matches.subcommand_matches("lifespan") {
+        return lifespans(rule, matches);
+    }
+    if let Some(matches) = matches.subcommand_matches("collide") {
+        return collide(rule, matches);
+    }
+    if let Some(matches) = matches.subcommand_matches("predecessor") {
+        return predecessor(rule, matches);
+    }
+    if let Some(matches) = matches.subcommand_matches("periodic") {
+        return periodic(rule, matches);
+    }
+    if let Some(matches) = matches.subcommand_matches("transform") {
+        return transform(topology, matches);
+    }
+    if let Some(matches) = matches.subcommand_matches("patterns") {
+        return patterns(matches);
+    }
+    let log = match matches.subcommand_matches("replay") {
+        Some(replay) => {
+            let log = RunLog::open(replay.value_of("log").unwrap_or_default())?;
+            if replay.is_present("generation") {
+                let style = (theme, spacing, display_mode);
+                return replay_generation(&log, replay, &exports, style);
+            }
+            Some(log)
+        }
+        None => None,
+    };
+    if matches.is_present("headless") {
+        let renderer = |size: ImageSize| -> Result<SoftwareRenderer, Box<dyn Error>> {
+            let (width, height) = size.dimensions(grid_width, grid_height)?;
+            Ok(
+                SoftwareRenderer::new(grid_width, grid_height, width, height)
+                    .with_spacing(spacing)
+                    .with_theme(theme.clone())
+                    .with_display_mode(display_mode),
+            )
+        };
+        let svg = Svg::new()
+            .with_spacing(spacing)
+            .with_theme(theme.clone())
+            .with_ruler(exports.svg_ruler);
+        return headless(grid, &matches, &exports, &renderer, svg);
+    }
+    let app = match log {
+        Some(log) => rendering::App::replay(log, window_width, window_height, updates_per_second),
+        None => rendering::App::new(grid, window_width, window_height, updates_per_second),
+    };
+    let mut app = app?
+        .with_display_mode(display_mode)
+        .with_theme(theme)
+        .with_spacing(spacing)
+        .with_screenshots(exports.screenshot_path, exports.screenshot_scale)
+        .with_svg_ruler(exports.svg_ruler)
+        .with_rewind_budget(rewind_memory.saturating_mul(1024 * 1024))
+        .with_recording(
+            matches.value_of("record").unwrap_or(DEFAULT_RECORDING_PATH),
+            exports.recording_options,
+            exports.recording_scale,
+        );
+    if let Some(path) = matches.value_of("log") {
+        let file = File::create(path).map_err(|e| format!("Could not create {path}: {e}"))?;
+        app = app.with_log(Box::new(BufWriter::new(file)));
+    }
+    app.run()
+}
+
+// Export settings shared by the window and headless mode
+struct Exports<'a> {
+    screenshot_path: &'a str,
+    screenshot_scale: Option<u32>,
+    svg_ruler: usize,
+    recording_options: AnimationOptions,
+    recording_scale: Option<u32>,
+}
+
+impl<'a> Exports<'a> {
+    fn new(updates_per_second: u16, matches: &'a ArgMatches<'_>) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            screenshot_path: matches.value_of("screenshot-path").unwrap_or_default(),
+            screenshot_scale: matches
+                .is_present("screenshot-scale")
+                .then(|| get_number("screenshot-scale", Some(0), matches)),
+            svg_ruler: get_number("svg-ruler", None, matches),
+            recording_options: animation_options(updates_per_second, matches)?,
+            recording_scale: matches
+                .is_present("record-scale")
+                .then(|| get_number("record-scale", Some(0), matches)),
+        })
+    }
+}
+
+// Runs the board without a window, exporting whatever was asked for along the way
+fn headless(
+    grid: Grid,
+    matches: &ArgMatches<'_>,
+    exports: &Exports<'_>,
+    renderer: &dyn Fn(ImageSize) -> Result<SoftwareRenderer, Box<dyn Error>>,
+    svg: Svg,
+) -> Result<(), Box<dyn Error>> {
+    let generations = get_number("generations", Some(0), matches);
+    let screenshot_at = generation_list("screenshot-at", generations, matches)?;
+    let screenshots = Screenshots::new(
+        renderer(ImageSize::PerCell(exports.screenshot_scale.unwrap_or(1)))?,
+        exports.screenshot_path,
+        screenshot_at,
+    )
+    .with_svg(svg);
+    let (columns, rows) = (grid.width(), grid.height());
+    let history = get_number("period-history", None, matches);
+    let mut headless = if matches.is_present("stop-when-periodic") {
+        // With no history to look back through, repeats could never be spotted
+        if history == 0 {
+            return Err("stop-when-periodic needs a period-history of at least 1".into());
+        }
+        Headless::new(grid, generations).with_stop_when_periodic(history)
+    } else {
+        Headless::new(grid.with_history(history), generations)
+    };
+    headless = headless.with_observer(Box::new(screenshots));
+    if let Some(path) = matches.value_of("record") {
+        let from = get_number("record-from", None, matches);
+        let to = if matches.is_present("record-to") {
+            get_number("record-to", None, matches)
+        } else {
+            generations
+        };
+        if from > to || to > generations {
+            return Err(format!(
+                "Can't record generations {from} to {to} of a run that's {generations} long"
+            )
+            .into());
+        }
+        let animation = Animation::create(output_path(path, from), exports.recording_options)?;
+        let scale = exports.recording_scale.unwrap_or(1);
+        let recording = Recording::new(renderer(ImageSize::PerCell(scale))?, animation, from, to);
+        headless = headless.with_observer(Box::new(recording));
+    }
+    if let Some(path) = matches.value_of("y4m") {
+        let size: ImageSize = matches.value_of("y4m-size").unwrap_or_default().parse()?;
+        let chroma: Chroma = matches.value_of("y4m-chroma").unwrap_or_default().parse()?;
+        let (width, height) = size.dimensions(columns, rows)?;
+        let frames_per_second = get_number("update-rate", Some(0), matches);
+        let writer = Y4mWriter::new(output(path)?, width, height, frames_per_second, chroma);
+        headless = headless.with_observer(Box::new(Video::new(renderer(size)?, writer)));
+    }
+    if let Some(path) = matches.value_of("stats") {
+        if path == "-" && matches.value_of("y4m") == Some("-") {
+            return Err("Can't stream both stats and video to stdout".into());
+        }
+        let format: StatsFormat = matches
+            .value_of("stats-format")
+            .unwrap_or_default()
+            .parse()?;
+        headless = headless.with_observer(Box::new(StatsWriter::new(output(path)?, format)));
+    }
+    if let Some(path) = matches.value_of("log") {
+        headless = headless.with_observer(Box::new(RunLogWriter::new(output(path)?)));
+    }
+    let grid = headless.run()?;
+    // Stdout might be taken by a video stream
+    if let Some(periodicity) = grid.periodicity() {
+        eprintln!("Generation {}: {periodicity}", grid.generation());
+    }
+    Ok(())
+}
+
+// Pulls a generation out of a run log, saving it as a screenshot or printing it as rows of
+// . and O
+fn replay_generation(
+    log: &RunLog,
+    matches: &ArgMatches<'_>,
+    exports: &Exports<'_>,
+    (theme, spacing, display_mode): (Theme, Spacing, DisplayMode),
+) -> Result<(), Box<dyn Error>> {
+    let generation = get_number("generation", None, matches);
+    let frame = log
+        .find(generation)
+        .filter(|&frame| log.generation(frame) == generation)
+        .ok_or_else(|| {
+            let (first, last) = log.generations();
+            format!("Generation {generation} isn't in the log, which goes from {first} to {last}")
+        })?;
+    let grid = log.grid(frame);
+    let Some(path) = matches.value_of("output") else {
+        for row in grid.cells() {
+            let row: String = row
+                .iter()
+                .map(|cell| if cell.0 == Status::Alive { 'O' } else { '.' })
+                .collect();
+            println!("{row}");
+        }
+        return Ok(());
+    };
+    let size = ImageSize::PerCell(exports.screenshot_scale.unwrap_or(1));
+    let (width, height) = size.dimensions(log.width(), log.height())?;
+    let renderer = SoftwareRenderer::new(log.width(), log.height(), width, height)
+        .with_spacing(spacing)
+        .with_theme(theme.clone())
+        .with_display_mode(display_mode);
+    let svg = Svg::new()
+        .with_spacing(spacing)
+        .with_theme(theme)
+        .with_ruler(exports.svg_ruler);
+    let screenshots = Screenshots::new(renderer, path, [generation]).with_svg(svg);
+    Headless::new(grid, 0)
+        .with_observer(Box::new(screenshots))
+        .run()?;
+    eprintln!(
+        "Saved generation {generation} to {}",
+        output_path(path, generation).display()
+    );
+    Ok(())
+}
+
+// A random board, or an empty one with a library pattern in the middle
+fn starting_grid(
+    width: usize,
+    height: usize,
+    rule: Rule,
+    topology: Topology,
+    matches: &ArgMatches<'_>,
+) -> Result<Grid, Box<dyn Error>> {
+    let Some(name) = matches.value_of("pattern-name") else {
+        return Ok(Grid::new(width, height)
+            .with_rule(rule)
+            .with_topology(topology));
+    };
+    let mut grid = Grid::empty(width, height)
+        .with_rule(rule)
+        .with_topology(topology);
+    place_in_middle(&library::find(name)?.pattern(), &mut grid);
+    Ok(grid)
+}
+
+// Stamps a pattern in the middle of a grid, flipped over so that it's the usual way up
+// once row 0 is drawn at the bottom
+fn place_in_middle(pattern: &Pattern, grid: &mut Grid) {
+    let pattern = pattern.transform(Transform::FlipRows);
+    let at = Coord {
+        i: grid.height().saturating_sub(pattern.height()) / 2,
+        j: grid.width().saturating_sub(pattern.width()) / 2,
+    };
+    pattern.stamp(grid, &at);
+}
+
+// Lists the patterns in the library, or prints one of them as RLE
+fn patterns(matches: &ArgMatches<'_>) -> Result<(), Box<dyn Error>> {
+    if let Some(name) = matches.value_of("name") {
+        print!("{}", library::find(name)?.rle());
+        return Ok(());
+    }
+    for (n, category) in Category::ALL.into_iter().enumerate() {
+        if n > 0 {
+            println!();
+        }
+        let mut title = category.to_string();
+        title[..1].make_ascii_uppercase();
+        println!("{title}");
+        for entry in library::PATTERNS
+            .iter()
+            .filter(|entry| entry.category == category)
+        {
+            println!("  {:<16}{}", entry.name, entry.title());
+        }
+    }
+    Ok(())
+}
+
+// Runs a random board until it settles, then lists the objects left on it
+fn census(grid: Grid, matches: &ArgMatches<'_>) -> Result<(), Box<dyn Error>> {
+    let generations = get_number("generations", Some(0), matches);
+    let history = get_number("period-history", Some(0), matches);
+    let grid = Headless::new(grid, generations)
+        .with_stop_when_periodic(history)
+        .run()?;
+    match grid.periodicity() {
+        Some(periodicity) => eprintln!("Generation {}: {periodicity}", grid.generation()),
+        None => eprintln!("Generation {}: not settled yet", grid.generation()),
+    }
+    for (label, count) in Census::of(&grid).counts() {
+        println!("{count:>6}  {label}");
+    }
+    Ok(())
+}
+
+// Searches soups a batch at a time, saving the summary after each batch so that an
+// interrupted search can carry on where it left off
+fn search(rule: Rule, matches: &ArgMatches<'_>) -> Result<(), Box<dyn Error>> {
+    let seeds = matches.value_of("seeds").unwrap_or_default();
+    let seeds = seeds
+        .split_once("..")
+        .and_then(|(start, end)| Some(start.parse::<u64>().ok()?..end.parse::<u64>().ok()?))
+        .ok_or_else(|| format!("seeds should be a range like 0..1000, got [{seeds}]"))?;
+    let path = matches.value_of("summary").unwrap_or_default();
+    let batch = get_number("batch", Some(0), matches);
+    let search = Search::new()
+        .with_rule(rule)
+        .with_soup_size(get_number("soup-size", Some(0), matches))
+        .with_board_size(get_number("board-size", Some(0), matches))
+        .with_generations(get_number("generations", Some(0), matches))
+        .with_history(get_number("period-history", Some(0), matches))
+        .with_rare_period(get_number("rare-period", None, matches))
+        .with_methuselah(get_number("methuselah", None, matches));
+    let mut summary = if matches.is_present("resume") && Path::new(path).exists() {
+        let summary: Summary = fs::read_to_string(path)?.parse()?;
+        if !summary.matches(&search) {
+            return Err(format!("{path} is from a different search, so can't be resumed").into());
+        }
+        if !(summary.seeds.start..=summary.seeds.end).contains(&seeds.start) {
+            return Err(format!(
+                "{path} has seeds {}..{}, which don't lead on to {}..{}",
+                summary.seeds.start, summary.seeds.end, seeds.start, seeds.end
+            )
+            .into());
+        }
+        summary
+    } else {
+        search.summary(seeds.start..seeds.start)
+    };
+    while summary.seeds.end < seeds.end {
+        let start = summary.seeds.end;
+        let end = seeds.end.min(start.saturating_add(batch));
+        let found = search.run(start..end);
+        for find in &found.finds {
+            eprintln!("Seed {}: {}", find.seed, find.reason);
+        }
+        summary = summary.merge(found);
+        fs::write(path, summary.to_string()).map_err(|e| format!("Could not write {path}: {e}"))?;
+        eprintln!("Searched seeds {}..{end}", summary.seeds.start);
+    }
+    println!("{summary}");
+    Ok(())
+}
+
+// Measures how long each pattern file takes to settle
+fn lifespans(rule: Rule, matches: &ArgMatches<'_>) -> Result<(), Box<dyn Error>> {
+    let max_generations = get_number("generations", Some(0), matches);
+    for (n, path) in matches
+        .values_of("patterns")
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        let pattern: Pattern = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {path}: {e}"))?
+            .parse()?;
+        if n > 0 {
+            println!();
+        }
+        println!("{path}");
+        println!("{}", lifespan::measure(&pattern, rule, max_generations));
+    }
+    Ok(())
+}
+
+// Crashes one pattern into another every way it can, then tables the different outcomes
+// and writes out the first start that led to each one
+fn collide(rule: Rule, matches: &ArgMatches<'_>) -> Result<(), Box<dyn Error>> {
+    let read = |name: &str| -> Result<Pattern, Box<dyn Error>> {
+        let path = matches.value_of(name).unwrap_or_default();
+        let pattern = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read {path}: {e}"))?
+            .parse()?;
+        Ok(pattern)
+    };
+    let offsets = matches.value_of("offsets").unwrap_or_default();
+    let offsets = offsets
+        .split_once("..")
+        .and_then(|(start, end)| Some(start.parse().ok()?..=end.parse().ok()?))
+        .ok_or_else(|| format!("offsets should be a range like -8..8, got [{offsets}]"))?;
+    let outcomes = Sweep::new(read("target")?, read("bullet")?)
+        .with_rule(rule)
+        .with_offsets(offsets)
+        .with_max_generations(get_number("generations", Some(0), matches))
+        .run();
+    if outcomes.is_empty() {
+        eprintln!("Every collision missed");
+        return Ok(());
+    }
+    println!(
+        "{:>4}  {:<24}  {:>6}  {:>8}  Outcome",
+        "#", "First", "Count", "Settled"
+    );
+    for (n, outcome) in outcomes.iter().enumerate() {
+        let settled = outcome
+            .lifespan
+            .settled
+            .map_or_else(|| "-".to_string(), |settled| settled.to_string());
+        println!(
+            "{:>4}  {:<24}  {:>6}  {settled:>8}  {}",
+            n + 1,
+            outcome.first.to_string(),
+            outcome.count,
+            outcome.description
+        );
+    }
+    for (n, outcome) in outcomes.iter().enumerate() {
+        println!();
+        println!("#C {}: {}, {}", n + 1, outcome.first, outcome.description);
+        print!("{}", rle::encode(&outcome.start, rule));
+    }
+    Ok(())
+}
+
+// Asks a SAT solver for a pattern that turns into the given one, or whether the given one
+// is a Garden of Eden
+fn predecessor(rule: Rule, matches: &ArgMatches<'_>) -> Result<(), Box<dyn Error>> {
+    let path = matches.value_of("pattern").unwrap_or_default();
+    let target: Pattern = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {path}: {e}"))?
+        .parse()?;
+    let generations = get_number("generations", Some(0), matches);
+    let margin = get_number("margin", None, matches);
+    let max_conflicts = get_number("max-conflicts", Some(0), matches);
+    let search = Predecessors::new(target)
+        .with_rule(rule)
+        .with_generations(generations)
+        .with_margin(margin)
+        .with_max_conflicts(max_conflicts);
+    let gave_up = || format!("Gave up after {max_conflicts} conflicts");
+    if matches.is_present("garden-of-eden") {
+        match search.is_garden_of_eden() {
+            Some(true) => println!("{path} is a Garden of Eden"),
+            Some(false) => println!("{path} can appear in the middle of a bigger pattern"),
+            None => return Err(gave_up().into()),
+        }
+        return Ok(());
+    }
+    match search.find() {
+        Predecessor::Found(predecessor) => {
+            if !search.check(&predecessor) {
+                return Err(format!(
+                    "Found {predecessor}, but it doesn't turn into {path} when it's run"
+                )
+                .into());
+            }
+            println!("!Turns into {path} after {generations} generation(s), checked");
+            for row in predecessor.to_string().split('/') {
+                println!("{}", row.replace('o', "O"));
+            }
+        }
+        Predecessor::Impossible => println!(
+            "Nothing within {margin} cells of {path} turns into it in {generations} generation(s)"
+        ),
+        Predecessor::GaveUp => return Err(gave_up().into()),
+    }
+    Ok(())
+}
+
+// Asks a SAT solver for an oscillator or spaceship, checking anything it finds by running it
+fn periodic(rule: Rule, matches: &ArgMatches<'_>) -> Result<(), Box<dyn Error>> {
+    let period = get_number("period", Some(0), matches);
+    let displacement = matches.value_of("displacement").unwrap_or_default();
+    let displacement = displacement
+        .split_once(',')
+        .and_then(|(rows, columns)| Some((rows.trim().parse().ok()?, columns.trim().parse().ok()?)))
+        .ok_or_else(|| {
+            format!("displacement should be rows and columns like 1,-1, got [{displacement}]")
+        })?;
+    let symmetry = match matches.value_of("symmetry") {
+        Some(symmetry) => symmetry.parse()?,
+        None => Transform::Identity,
+    };
+    let max_conflicts = get_number("max-conflicts", Some(0), matches);
+    let search = Periodic::new(period)
+        .with_rule(rule)
+        .with_displacement(displacement)
+        .with_size(
+            get_number("height", Some(0), matches),
+            get_number("width", Some(0), matches),
+        )
+        .with_symmetry(symmetry)
+        .with_max_conflicts(max_conflicts);
+    match search.find() {
+        periodic::Outcome::Found(pattern) => {
+            if !search.check(&pattern) {
+                return Err(format!("Found {pattern}, but it doesn't repeat when it's run").into());
+            }
+            let (rows, columns) = displacement;
+            println!("!Period {period}, moving {rows} rows and {columns} columns, checked");
+            for row in pattern.to_string().split('/') {
+                println!("{}", row.replace('o', "O"));
+            }
+        }
+        periodic::Outcome::Impossible => println!("Nothing like that fits"),
+        periodic::Outcome::GaveUp => {
+            return Err(format!("Gave up after {max_conflicts} conflicts").into())
+        }
+    }
+    Ok(())
+}
+
+// Rotates, reflects, shifts, combines and crops a pattern file, printing the result
+fn transform(topology: Topology, matches: &ArgMatches<'_>) -> Result<(), Box<dyn Error>> {
+    let mut pattern = read_pattern(matches.value_of("pattern").unwrap_or_default())?;
+    // Turned before the board is sized, so that rotating a pattern that isn't square
+    // doesn't push it past the edges
+    for name in matches.values_of("transform").into_iter().flatten() {
+        pattern = pattern.transform(name.parse()?);
+    }
+    let other = matches.value_of("with").map(read_pattern).transpose()?;
+    let (i, j) = parse_pair(matches.value_of("at").unwrap_or_default(), "at")?;
+    let at = Coord { i, j };
+    // Big enough for both patterns unless it's been given a size
+    let (mut height, mut width) = (pattern.height(), pattern.width());
+    if let Some(other) = &other {
+        height = height.max(at.i + other.height());
+        width = width.max(at.j + other.width());
+    }
+    let height = matches.value_of("height").map_or(Ok(height), str::parse)?;
+    let width = matches.value_of("width").map_or(Ok(width), str::parse)?;
+    let mut grid = Grid::empty(width, height).with_topology(topology);
+    pattern.stamp(&mut grid, &Coord { i: 0, j: 0 });
+    if let Some(shift) = matches.value_of("shift") {
+        let shift = parse_pair(shift, "shift")?;
+        if let Some(region) = grid.bounding_box() {
+            grid.translate(&region, shift);
+        }
+    }
+    if let Some(other) = other {
+        let combine: Combine = matches.value_of("combine").unwrap_or_default().parse()?;
+        let other = other.to_grid(0, Rule::default());
+        grid.combine(&other, &at, combine);
+    }
+    if matches.is_present("crop") {
+        // Nothing's left to print once the empty edges are trimmed off
+        let Some(region) = grid.bounding_box() else {
+            return Ok(());
+        };
+        grid = grid.crop(&region);
+    }
+    for row in grid.cells() {
+        let row: String = row
+            .iter()
+            .map(|cell| if cell.0 == Status::Alive { 'O' } else { '.' })
+            .collect();
+        println!("{row}");
+    }
+    Ok(())
+}
+
+fn read_pattern(path: &str) -> Result<Pattern, Box<dyn Error>> {
+    let pattern = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read {path}: {e}"))?
+        .parse()?;
+    Ok(pattern)
+}
+
+// Parses a pair of numbers like 1,-2
+fn parse_pair<A: FromStr>(pair: &str, name: &str) -> Result<(A, A), String> {
+    pair.split_once(',')
+        .and_then(|(a, b)| Some((a.trim().parse().ok()?, b.trim().parse().ok()?)))
+        .ok_or_else(|| format!("{name} should be rows and columns like 1,-2, got [{pair}]"))
+}
+
+// Opens a file to stream output to, or stdout for -
+fn output(path: &str) -> Result<Box<dyn Write>, Box<dyn Error>> {
+    if path == "-" {
+        Ok(Box::new(BufWriter::new(io::stdout())))
+    } else {
+        let file = File::create(path).map_err(|e| format!("Could not create {path}: {e}"))?;
+        Ok(Box::new(BufWriter::new(file)))
+    }
+}
+
+fn cli(version: &str) -> App<'_, '_> {
+    App::new("Game of Life")
+        .version(version)
         .about("Conway's Game of Life in OpenGL!")
         .arg(
             Arg::with_name("grid-width")
@@ -37,18 +663,7 @@ fn inner_main() -> Result<(), Box<dyn Error>> {
                 .default_value("80")
                 .help("Height of the grid"),
         )
-        .arg(
-            Arg::with_name("window-width")
-                .long("window-width")
-                .default_value("1024")
-                .help("Width of the window"),
-        )
-        .arg(
-            Arg::with_name("window-height")
-                .long("window-height")
-                .default_value("768")
-                .help("Height of the window"),
-        )
+        .args(&window_args())
         .arg(
             Arg::with_name("update-rate")
                 .short("u")
@@ -56,17 +671,510 @@ fn inner_main() -> Result<(), Box<dyn Error>> {
                 .default_value("30")
                 .help("Number of updates to the game board per second"),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("rule")
+                .long("rule")
+                .default_value("B3/S23")
+                .help("Rule to run the board with, in B/S notation"),
+        )
+        .arg(
+            Arg::with_name("pattern-name")
+                .long("pattern-name")
+                .takes_value(true)
+                .help("Start with a library pattern on an empty board, see `gol patterns`"),
+        )
+        .arg(
+            Arg::with_name("topology")
+                .long("topology")
+                .default_value("torus")
+                .possible_values(&["torus", "plane"])
+                .help("Whether the board's edges wrap around, or are dead past them"),
+        )
+        .arg(
+            Arg::with_name("display")
+                .long("display")
+                .default_value("classic")
+                .possible_values(&["classic", "age", "trails"])
+                .help("How cells are coloured; can be cycled with M while running"),
+        )
+        .arg(
+            Arg::with_name("theme")
+                .long("theme")
+                .takes_value(true)
+                .possible_values(&THEME_NAMES)
+                .help("Colour theme [default: classic]; can be cycled with T while running"),
+        )
+        .arg(
+            Arg::with_name("cell-gap")
+                .long("cell-gap")
+                .takes_value(true)
+                .help("Share of each cell left as a gap, from 0 up to 1 [default: 0.1]"),
+        )
+        .arg(
+            Arg::with_name("gridlines")
+                .long("gridlines")
+                .takes_value(true)
+                .help(
+                    "Draw gridlines instead of gaps, with a major line every given number of cells",
+                ),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .takes_value(true)
+                .help("Config file with a theme, custom colours and/or cell spacing"),
+        )
+        .args(&export_args())
+        .subcommands(vec![
+            census_command(),
+            search_command(),
+            lifespan_command(),
+            collide_command(),
+            predecessor_command(),
+            periodic_command(),
+            transform_command(),
+            patterns_command(),
+            replay_command(),
+        ])
+}
 
-    let grid_width = get_number("grid-width", Some(0), &matches);
-    let grid_height = get_number("grid-height", Some(0), &matches);
-    let window_width = get_number("window-width", Some(0), &matches);
-    let window_height = get_number("window-height", Some(0), &matches);
-    let updates_per_second = get_number("update-rate", None, &matches);
+fn census_command() -> App<'static, 'static> {
+    SubCommand::with_name("census")
+        .about("Run a random board until it settles, then count the objects on it")
+        .args(&[
+            Arg::with_name("generations")
+                .long("generations")
+                .default_value("10000")
+                .help("Most generations to wait for the board to settle"),
+            Arg::with_name("period-history")
+                .long("period-history")
+                .default_value("1024")
+                .help("Generations to look back through for repeats"),
+        ])
+}
+
+fn lifespan_command() -> App<'static, 'static> {
+    SubCommand::with_name("lifespan")
+        .about("Measure how long patterns take to settle on an unbounded board")
+        .args(&[
+            Arg::with_name("patterns")
+                .required(true)
+                .multiple(true)
+                .help("Plaintext pattern files, with rows of . and O"),
+            Arg::with_name("generations")
+                .long("generations")
+                .default_value("100000")
+                .help("Most generations to wait for each pattern to settle"),
+        ])
+}
+
+fn collide_command() -> App<'static, 'static> {
+    SubCommand::with_name("collide")
+        .about("Crash one pattern into another from every offset and phase, listing outcomes")
+        .args(&[
+            Arg::with_name("target")
+                .required(true)
+                .help("Plaintext pattern file for the pattern being hit"),
+            Arg::with_name("bullet")
+                .required(true)
+                .help("Plaintext pattern file for the pattern hitting it, e.g. a glider"),
+            Arg::with_name("offsets")
+                .long("offsets")
+                .allow_hyphen_values(true)
+                .default_value("-8..8")
+                .help("Range of rows and columns to try the bullet at, ends included"),
+            Arg::with_name("generations")
+                .long("generations")
+                .default_value("10000")
+                .help("Most generations to wait for each collision to settle"),
+        ])
+}
+
+fn predecessor_command() -> App<'static, 'static> {
+    SubCommand::with_name("predecessor")
+        .about("Use a SAT solver to find a pattern that turns into another one")
+        .args(&[
+            Arg::with_name("pattern")
+                .required(true)
+                .help("Plaintext pattern file to find a predecessor of"),
+            Arg::with_name("generations")
+                .long("generations")
+                .default_value("1")
+                .help("Generations from the predecessor to the pattern"),
+            Arg::with_name("margin")
+                .long("margin")
+                .default_value("2")
+                .help("Cells the predecessor can stick out past the pattern on each side"),
+            Arg::with_name("max-conflicts")
+                .long("max-conflicts")
+                .default_value("1000000")
+                .help("Conflicts the solver can run into before giving up"),
+            Arg::with_name("garden-of-eden")
+                .long("garden-of-eden")
+                .help("Check whether the pattern could come from anything at all instead"),
+        ])
+}
+
+fn periodic_command() -> App<'static, 'static> {
+    SubCommand::with_name("periodic")
+        .about("Use a SAT solver to find an oscillator or spaceship that fits in a box")
+        .args(&[
+            Arg::with_name("period")
+                .long("period")
+                .default_value("2")
+                .help("Generations before it comes back to the same shape"),
+            Arg::with_name("displacement")
+                .long("displacement")
+                .allow_hyphen_values(true)
+                .default_value("0,0")
+                .help("Rows and columns it moves each period, e.g. 1,1 for a glider"),
+            Arg::with_name("height")
+                .long("height")
+                .default_value("8")
+                .help("Rows in the box the first generation has to fit in"),
+            Arg::with_name("width")
+                .long("width")
+                .default_value("8")
+                .help("Columns in the box the first generation has to fit in"),
+            Arg::with_name("symmetry")
+                .long("symmetry")
+                .takes_value(true)
+                .help("A transform it has to look the same after, e.g. flip-columns or rotate90"),
+            Arg::with_name("max-conflicts")
+                .long("max-conflicts")
+                .default_value("1000000")
+                .help("Conflicts the solver can run into before giving up"),
+        ])
+}
+
+fn patterns_command() -> App<'static, 'static> {
+    SubCommand::with_name("patterns")
+        .about("List the patterns in the built in library, or print one of them as RLE")
+        .arg(Arg::with_name("name").help("Pattern to print, like gosper-gun"))
+}
+
+fn replay_command() -> App<'static, 'static> {
+    SubCommand::with_name("replay")
+        .about("Scrub through a run log saved with --log, or pull a generation out of it")
+        .args(&[
+            Arg::with_name("log")
+                .required(true)
+                .help("Run log to replay"),
+            Arg::with_name("generation")
+                .long("generation")
+                .takes_value(true)
+                .help("Print this generation as rows of . and O instead of opening a window"),
+            Arg::with_name("output")
+                .long("output")
+                .takes_value(true)
+                .requires("generation")
+                .help("Save the generation as a PNG or SVG instead, with {gen} replaced by it"),
+        ])
+}
+
+fn transform_command() -> App<'static, 'static> {
+    SubCommand::with_name("transform")
+        .about("Rotate, reflect, shift, combine and crop a pattern file, following --topology")
+        .args(&[
+            Arg::with_name("pattern")
+                .required(true)
+                .help("Plaintext pattern file to start from"),
+            Arg::with_name("transform")
+                .long("transform")
+                .multiple(true)
+                .number_of_values(1)
+                .takes_value(true)
+                .possible_values(&[
+                    "identity",
+                    "rotate90",
+                    "rotate180",
+                    "rotate270",
+                    "flip-rows",
+                    "flip-columns",
+                    "transpose",
+                    "anti-transpose",
+                ])
+                .help("Rotations or reflections to apply, in order"),
+            Arg::with_name("shift")
+                .long("shift")
+                .allow_hyphen_values(true)
+                .takes_value(true)
+                .help("Rows and columns to move the pattern by afterwards, e.g. 1,-2"),
+            Arg::with_name("combine")
+                .long("combine")
+                .takes_value(true)
+                .possible_values(&["or", "and", "xor", "difference"])
+                .requires("with")
+                .help("How to combine the result with the pattern from --with"),
+            Arg::with_name("with")
+                .long("with")
+                .takes_value(true)
+                .requires("combine")
+                .help("Plaintext pattern file to combine the result with"),
+            Arg::with_name("at")
+                .long("at")
+                .default_value("0,0")
+                .help("Row and column to put the top left of the --with pattern at"),
+            Arg::with_name("height")
+                .long("height")
+                .takes_value(true)
+                .help("Rows in the board, which is just big enough for the patterns otherwise"),
+            Arg::with_name("width")
+                .long("width")
+                .takes_value(true)
+                .help("Columns in the board, which is just big enough for the patterns otherwise"),
+            Arg::with_name("crop")
+                .long("crop")
+                .help("Trim empty rows and columns off the edges at the end"),
+        ])
+}
+
+fn search_command() -> App<'static, 'static> {
+    SubCommand::with_name("search")
+        .about("Run seeded random soups on every core, counting what they leave behind")
+        .args(&[
+            Arg::with_name("seeds")
+                .long("seeds")
+                .default_value("0..1000")
+                .help("Range of seeds to search, with the end left out"),
+            Arg::with_name("summary")
+                .long("summary")
+                .default_value("search.txt")
+                .help("File to save counts of each object and any rare finds to"),
+            Arg::with_name("resume")
+                .long("resume")
+                .help("Carry on from the last seed in an existing --summary"),
+            Arg::with_name("batch")
+                .long("batch")
+                .default_value("1000")
+                .help("Soups to search between saves of the summary"),
+            Arg::with_name("soup-size")
+                .long("soup-size")
+                .default_value("16")
+                .help("Width and height of the random square each soup starts from"),
+            Arg::with_name("board-size")
+                .long("board-size")
+                .default_value("64")
+                .help("Width and height of the board soups are run on, which wraps around"),
+            Arg::with_name("generations")
+                .long("generations")
+                .default_value("20000")
+                .help("Most generations to wait for each soup to settle"),
+            Arg::with_name("period-history")
+                .long("period-history")
+                .default_value("4096")
+                .help("Generations to look back through for repeats"),
+            Arg::with_name("rare-period")
+                .long("rare-period")
+                .default_value("4")
+                .help("Flag oscillators with at least this period"),
+            Arg::with_name("methuselah")
+                .long("methuselah")
+                .default_value("5000")
+                .help("Flag soups that take at least this many generations to settle"),
+        ])
+}
+
+// Options for the window, when there is one
+fn window_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("window-width")
+            .long("window-width")
+            .default_value("1024")
+            .help("Width of the window"),
+        Arg::with_name("window-height")
+            .long("window-height")
+            .default_value("768")
+            .help("Height of the window"),
+        Arg::with_name("rewind-memory")
+            .long("rewind-memory")
+            .default_value("64")
+            .help(
+                "Megabytes of earlier generations and edits to keep for undoing and stepping back",
+            ),
+    ]
+}
+
+// Options for running without a window and exporting the board
+fn export_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("headless")
+            .long("headless")
+            .help("Run as fast as possible without a window, e.g. to take screenshots"),
+        Arg::with_name("generations")
+            .long("generations")
+            .default_value("100")
+            .help("Number of generations to run for in headless mode"),
+        Arg::with_name("stop-when-periodic")
+            .long("stop-when-periodic")
+            .requires("headless")
+            .help("Stop once the board starts repeating itself, rather than at --generations"),
+        Arg::with_name("period-history")
+            .long("period-history")
+            .default_value("1024")
+            .help("Generations to look back through for repeats in headless mode, or 0 for none"),
+        Arg::with_name("screenshot-at")
+            .long("screenshot-at")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(true)
+            .help("Generations to save screenshots of in headless mode, e.g. 0,10,100"),
+        Arg::with_name("screenshot-path")
+            .long("screenshot-path")
+            .default_value(DEFAULT_SCREENSHOT_PATH)
+            .help(
+                "Where screenshots are saved, with {gen} replaced by the generation, as PNG or SVG",
+            ),
+        Arg::with_name("screenshot-scale")
+            .long("screenshot-scale")
+            .takes_value(true)
+            .help("Pixels per cell in screenshots [default: 1 headless, else window size]"),
+        Arg::with_name("svg-ruler")
+            .long("svg-ruler")
+            .default_value("0")
+            .help("Label every this many rows and columns in SVG screenshots, or 0 for none"),
+        Arg::with_name("stats")
+            .long("stats")
+            .takes_value(true)
+            .requires("headless")
+            .help("Write population, births, deaths and bounds every generation to a file, or -"),
+        Arg::with_name("log")
+            .long("log")
+            .takes_value(true)
+            .help("Record every generation to a run log, to scrub through with `gol replay`"),
+        Arg::with_name("stats-format")
+            .long("stats-format")
+            .default_value("csv")
+            .possible_values(&["csv", "json"])
+            .help("Write stats as CSV, or as a JSON object per line"),
+        Arg::with_name("y4m")
+            .long("y4m")
+            .takes_value(true)
+            .requires("headless")
+            .help("Stream every generation as YUV4MPEG2 video to a file, or - for stdout"),
+        Arg::with_name("y4m-size")
+            .long("y4m-size")
+            .default_value("1")
+            .help("Pixels per cell in the video, or its size in pixels like 1920x1080"),
+        Arg::with_name("y4m-chroma")
+            .long("y4m-chroma")
+            .default_value("420")
+            .possible_values(&["420", "444"])
+            .help("Chroma subsampling of the video; 444 keeps colours sharp, 420 plays anywhere"),
+        Arg::with_name("record")
+            .long("record")
+            .takes_value(true)
+            .help(
+                "Animated .gif or .png to record to, with R or headless [default: gol-{gen}.gif]",
+            ),
+        Arg::with_name("record-from")
+            .long("record-from")
+            .default_value("0")
+            .help("First generation to record in headless mode"),
+        Arg::with_name("record-to")
+            .long("record-to")
+            .takes_value(true)
+            .help("Last generation to record in headless mode [default: --generations]"),
+        Arg::with_name("record-skip")
+            .long("record-skip")
+            .default_value("1")
+            .help("Only record every this many generations"),
+        Arg::with_name("record-scale")
+            .long("record-scale")
+            .takes_value(true)
+            .help("Pixels per cell in recordings [default: 1 headless, else window size]"),
+        Arg::with_name("record-palette")
+            .long("record-palette")
+            .default_value("auto")
+            .help("Colours to record with: auto, greyscale, or a number of colours from 2 to 256"),
+    ]
+}
 
-    let grid = Grid::new(grid_width, grid_height);
-    let app = rendering::App::new(grid, window_width, window_height, updates_per_second);
-    app?.run()
+// Picks the theme from the command line or the config file, then applies any custom
+// colours from the config file on top of it.
+fn theme(matches: &ArgMatches<'_>, config: &Config) -> Result<Theme, Box<dyn Error>> {
+    let mut theme: Theme = matches
+        .value_of("theme")
+        .or_else(|| config.get("theme"))
+        .unwrap_or(THEME_NAMES[0])
+        .parse()?;
+    for name in COLOUR_NAMES {
+        if let Some(value) = config.get(name) {
+            let colour = parse_colour(value).map_err(|e| format!("Invalid {name} colour: {e}"))?;
+            theme.set_colour(name, colour);
+            theme.name = "custom".to_string();
+        }
+    }
+    Ok(theme)
+}
+
+// Gridlines win over gaps, and either given on the command line wins over the config file.
+fn spacing(matches: &ArgMatches<'_>, config: &Config) -> Result<Spacing, Box<dyn Error>> {
+    let from_cli = matches.is_present("cell-gap") || matches.is_present("gridlines");
+    let setting = |name| {
+        if from_cli {
+            matches.value_of(name)
+        } else {
+            config.get(name)
+        }
+    };
+    if let Some(major_every) = setting("gridlines") {
+        let major_every = major_every
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| format!("gridlines should be a positive number, got [{major_every}]"))?;
+        return Ok(Spacing::Gridlines(major_every));
+    }
+    let ratio = match setting("cell-gap") {
+        Some(ratio) => ratio
+            .trim()
+            .parse::<f32>()
+            .map_err(|_| format!("cell-gap should be a number, got [{ratio}]"))?,
+        None => DEFAULT_CELL_GAP,
+    };
+    Ok(Spacing::gaps(ratio)?)
+}
+
+// Recordings play back at the update rate, so skipped generations make frames longer
+fn animation_options(
+    updates_per_second: u16,
+    matches: &ArgMatches<'_>,
+) -> Result<AnimationOptions, Box<dyn Error>> {
+    let skip: u64 = get_number("record-skip", Some(0), matches);
+    let palette: Palette = matches
+        .value_of("record-palette")
+        .unwrap_or_default()
+        .parse()?;
+    Ok(AnimationOptions {
+        palette,
+        skip,
+        frame_delay: Duration::from_secs_f64(skip as f64 / f64::from(updates_per_second)),
+    })
+}
+
+// Parses a list of generations, none of which can be after the last one that gets run
+fn generation_list(
+    name: &str,
+    generations: u64,
+    matches: &ArgMatches<'_>,
+) -> Result<Vec<u64>, Box<dyn Error>> {
+    let mut list = Vec::new();
+    for value in matches.values_of(name).into_iter().flatten() {
+        let generation: u64 = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("{name} should be a list of generations, got [{value}]"))?;
+        if generation > generations {
+            return Err(format!(
+                "{name} includes generation {generation}, after the last one ({generations})"
+            )
+            .into());
+        }
+        list.push(generation);
+    }
+    Ok(list)
 }
 
 fn version() -> String {
@@ -99,13 +1207,11 @@ where
             }
             _ => Some(u),
         })
-        .expect(
-            &{
-                if let Some(min) = maybe_min {
-                    format!("{name} should be a positive number greater than {min}.")
-                } else {
-                    format!("{name} should be a positive number.")
-                }
-            }[..],
-        )
+        .unwrap_or_else(|| {
+            if let Some(min) = maybe_min {
+                panic!("{name} should be a positive number greater than {min}.")
+            } else {
+                panic!("{name} should be a positive number.")
+            }
+        })
 }
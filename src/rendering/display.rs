@@ -0,0 +1,173 @@
+use super::theme::Theme;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// Number of generations it takes a live cell to shift all the way to the aged colour
+const AGE_RAMP_GENERATIONS: f32 = 100.;
+
+/// Number of generations it takes a dead cell to fade out completely in `Trails` mode
+pub const TRAIL_GENERATIONS: u16 = 8;
+
+/// How cells get coloured
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DisplayMode {
+    /// Live and dead cells each get a single colour
+    Classic,
+    /// Live cells shift colour the longer they survive
+    Age,
+    /// Like `Age`, but recently dead cells also fade out over a few generations
+    Trails,
+}
+
+impl DisplayMode {
+    pub const ALL: [Self; 3] = [Self::Classic, Self::Age, Self::Trails];
+
+    /// The mode after this one, wrapping around at the end
+    #[must_use]
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Classic => Self::Age,
+            Self::Age => Self::Trails,
+            Self::Trails => Self::Classic,
+        }
+    }
+
+    /// Colour for a cell with the given status and age, see `data::Ages`
+    pub fn colour(self, theme: &Theme, alive: bool, age: u16) -> [f32; 4] {
+        match (self, alive) {
+            (Self::Classic, true) => theme.alive,
+            (Self::Age | Self::Trails, true) => {
+                // Logarithmic, so the change is visible in the first few generations
+                // rather than only for very old cells
+                let t = f32::from(age).ln_1p() / AGE_RAMP_GENERATIONS.ln_1p();
+                lerp(theme.alive, theme.aged, t)
+            }
+            (Self::Trails, false) if age <= TRAIL_GENERATIONS => lerp(
+                theme.alive,
+                theme.dead,
+                f32::from(age) / f32::from(TRAIL_GENERATIONS + 1),
+            ),
+            (_, false) => theme.dead,
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Age => "age",
+            Self::Trails => "trails",
+        }
+    }
+}
+
+/// Linearly interpolates between two colours, with `t` clamped to [0, 1]
+pub fn lerp(from: [f32; 4], to: [f32; 4], t: f32) -> [f32; 4] {
+    let t = t.clamp(0., 1.);
+    let mut colour = from;
+    for (c, to) in colour.iter_mut().zip(to.iter()) {
+        *c = *c * (1. - t) + to * t;
+    }
+    colour
+}
+
+impl fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returned when a display mode name isn't recognised
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseDisplayModeError(String);
+
+impl fmt::Display for ParseDisplayModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = DisplayMode::ALL.iter().map(|mode| mode.name()).collect();
+        write!(
+            f,
+            "Unknown display mode [{}], expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl Error for ParseDisplayModeError {}
+
+impl FromStr for DisplayMode {
+    type Err = ParseDisplayModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|mode| mode.name().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| ParseDisplayModeError(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::NEVER_ALIVE;
+
+    const WHITE: [f32; 4] = [1., 1., 1., 1.];
+
+    #[test]
+    fn test_next() {
+        let mut mode = DisplayMode::Classic;
+        for expected in [DisplayMode::Age, DisplayMode::Trails, DisplayMode::Classic] {
+            mode = mode.next();
+            assert_eq!(mode, expected);
+        }
+    }
+
+    #[test]
+    fn test_classic_colour() {
+        let theme = Theme::default();
+        assert_eq!(DisplayMode::Classic.colour(&theme, true, 500), theme.alive);
+        assert_eq!(DisplayMode::Classic.colour(&theme, false, 1), theme.dead);
+    }
+
+    #[test]
+    fn test_age_colour() {
+        let theme = Theme::default();
+        let colour = |alive, age| DisplayMode::Age.colour(&theme, alive, age);
+        assert_eq!(colour(true, 0), theme.alive);
+        assert_eq!(colour(true, 100), theme.aged);
+        assert_eq!(colour(true, u16::MAX), theme.aged);
+        assert_ne!(colour(true, 5), theme.alive);
+        assert_ne!(colour(true, 5), colour(true, 10));
+        assert_eq!(colour(false, 1), theme.dead);
+    }
+
+    #[test]
+    fn test_trails_colour() {
+        let theme = Theme::default();
+        let colour = |alive, age| DisplayMode::Trails.colour(&theme, alive, age);
+        assert_eq!(colour(true, 100), theme.aged);
+        let just_died = colour(false, 1);
+        assert_ne!(just_died, theme.dead);
+        assert_ne!(just_died, colour(false, 2));
+        assert_eq!(colour(false, TRAIL_GENERATIONS + 1), theme.dead);
+        assert_eq!(colour(false, NEVER_ALIVE), theme.dead);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let black = [0., 0., 0., 1.];
+        assert_eq!(lerp(black, WHITE, 0.5), [0.5, 0.5, 0.5, 1.]);
+        assert_eq!(lerp(black, WHITE, 2.), WHITE);
+        assert_eq!(lerp(black, WHITE, -1.), black);
+    }
+
+    #[test]
+    fn test_from_str() {
+        for mode in DisplayMode::ALL {
+            assert_eq!(mode.to_string().parse(), Ok(mode));
+        }
+        assert_eq!("AGE".parse(), Ok(DisplayMode::Age));
+        assert!("sepia".parse::<DisplayMode>().is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::census::pattern::{signed, Pattern};
+use crate::data::{BoundingBox, Coord, Transform};
+use crate::simulation::Edit;
+
+/// Keeps track of editing the board with the mouse: dragging out a selection and working on
+/// it, or moving a pattern around until it gets pasted.
+///
+/// Row 0 of the grid is at the bottom of the screen, so patterns coming in and out of here
+/// are flipped to be the way up they look, like they would be in any other program.
+#[derive(Debug, Default)]
+pub struct Editor {
+    cursor: Option<Coord>,
+    // Where the selection started, while it's being dragged out
+    anchor: Option<Coord>,
+    selection: Option<BoundingBox>,
+    // Pattern that follows the cursor around until it gets pasted
+    pasting: Option<Pattern>,
+}
+
+impl Editor {
+    /// Moves the cursor to a cell, or off the board, stretching the selection along with it
+    /// while one is being dragged out
+    pub fn move_cursor(&mut self, cursor: Option<Coord>) {
+        if let (Some(anchor), Some(cursor)) = (&self.anchor, &cursor) {
+            self.selection = Some(spanning(anchor, cursor));
+        }
+        self.cursor = cursor;
+    }
+
+    /// Pastes the pattern that's waiting to be pasted at the cursor, or otherwise starts
+    /// dragging out a new selection from it
+    pub fn press(&mut self) -> Option<Edit> {
+        let cursor = self.cursor.clone()?;
+        if let Some(pattern) = self.pasting.take() {
+            let (pattern, at) = placed(&pattern, &cursor);
+            return Some(Edit::Paste(pattern, at));
+        }
+        self.selection = Some(spanning(&cursor, &cursor));
+        self.anchor = Some(cursor);
+        None
+    }
+
+    /// Stops dragging out the selection
+    pub fn release(&mut self) {
+        self.anchor = None;
+    }
+
+    pub const fn selection(&self) -> Option<&BoundingBox> {
+        self.selection.as_ref()
+    }
+
+    /// The live cells in the selection, given which cells of the grid are alive
+    pub fn copy<F>(&self, alive: F) -> Option<Pattern>
+    where
+        F: Fn(&Coord) -> bool,
+    {
+        let region = self.selection?;
+        let cells = (region.min_i..=region.max_i)
+            .flat_map(|i| (region.min_j..=region.max_j).map(move |j| Coord { i, j }));
+        // Flipped over, since row 0 is at the bottom
+        let cells = cells.filter(|coord| alive(coord)).map(|Coord { i, j }| {
+            let (i, j) = signed((i, j));
+            (-i, j)
+        });
+        Some(Pattern::new(cells))
+    }
+
+    /// Kills every cell in the selection
+    pub fn clear(&self) -> Option<Edit> {
+        self.selection.map(Edit::Clear)
+    }
+
+    /// Fills the selection with random cells
+    pub fn randomise(&self) -> Option<Edit> {
+        self.selection.map(Edit::Randomise)
+    }
+
+    /// Rotates or reflects the pattern waiting to be pasted, or failing that the selection,
+    /// going by how it looks on screen
+    pub fn transform(&mut self, transform: Transform) -> Option<Edit> {
+        if let Some(pattern) = &mut self.pasting {
+            *pattern = pattern.transform(transform);
+            return None;
+        }
+        let region = self.selection?;
+        if transform.swaps_axes() {
+            self.selection = Some(BoundingBox {
+                max_i: region.min_i + region.width() - 1,
+                max_j: region.min_j + region.height() - 1,
+                ..region
+            });
+        }
+        Some(Edit::Transform(region, upside_down(transform)))
+    }
+
+    /// Has a pattern follow the cursor around until it's pasted, instead of selecting
+    pub fn start_pasting(&mut self, pattern: Pattern) {
+        self.anchor = None;
+        self.selection = None;
+        self.pasting = Some(pattern);
+    }
+
+    /// Drops the pattern waiting to be pasted, or failing that the selection, returning
+    /// false if there was neither
+    pub fn cancel(&mut self) -> bool {
+        self.anchor = None;
+        self.pasting.take().is_some() || self.selection.take().is_some()
+    }
+
+    /// Cells that would come alive if the pattern waiting to be pasted was pasted now, which
+    /// can be past the grid's edges
+    pub fn ghost(&self) -> Vec<(isize, isize)> {
+        let (Some(pattern), Some(cursor)) = (&self.pasting, &self.cursor) else {
+            return Vec::new();
+        };
+        let (pattern, (top, left)) = placed(pattern, cursor);
+        pattern
+            .cells()
+            .iter()
+            .map(|&cell| {
+                let (i, j) = signed(cell);
+                (top + i, left + j)
+            })
+            .collect()
+    }
+}
+
+// The smallest box with both cells in its corners
+fn spanning(a: &Coord, b: &Coord) -> BoundingBox {
+    BoundingBox {
+        min_i: a.i.min(b.i),
+        min_j: a.j.min(b.j),
+        max_i: a.i.max(b.i),
+        max_j: a.j.max(b.j),
+    }
+}
+
+// Turns a pattern the way up it looks on screen into one the way up the grid is, along
+// with where its top left corner goes for the on screen top left to be at a cell
+fn placed(pattern: &Pattern, at: &Coord) -> (Pattern, (isize, isize)) {
+    let (i, j) = signed((at.i, at.j));
+    let (height, _) = signed((pattern.height(), pattern.width()));
+    (pattern.transform(Transform::FlipRows), (i + 1 - height, j))
+}
+
+// The transform that does to the grid what the given one looks like it does on screen,
+// where it's upside down
+const fn upside_down(transform: Transform) -> Transform {
+    match transform {
+        Transform::Rotate90 => Transform::Rotate270,
+        Transform::Rotate270 => Transform::Rotate90,
+        Transform::Transpose => Transform::AntiTranspose,
+        Transform::AntiTranspose => Transform::Transpose,
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Grid;
+
+    fn editor_with_selection(from: Coord, to: Coord) -> Editor {
+        let mut editor = Editor::default();
+        editor.move_cursor(Some(from));
+        assert_eq!(editor.press(), None);
+        editor.move_cursor(Some(to));
+        editor.release();
+        editor
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut editor = editor_with_selection(Coord { i: 3, j: 1 }, Coord { i: 1, j: 4 });
+        let region = BoundingBox {
+            min_i: 1,
+            min_j: 1,
+            max_i: 3,
+            max_j: 4,
+        };
+        assert_eq!(editor.selection(), Some(&region));
+        // Moving after letting go leaves the selection alone
+        editor.move_cursor(Some(Coord { i: 0, j: 0 }));
+        assert_eq!(editor.selection(), Some(&region));
+        assert_eq!(editor.clear(), Some(Edit::Clear(region)));
+        assert!(editor.cancel());
+        assert!(!editor.cancel());
+        assert_eq!(editor.clear(), None);
+    }
+
+    #[test]
+    fn test_copy_and_paste_are_the_right_way_up() {
+        // A glider heading down and to the right on screen, where row 0 is at the bottom
+        let screen: Pattern = ".o./..o/ooo".parse().unwrap();
+        let mut grid = Grid::empty(6, 6);
+        let mut editor = Editor::default();
+        editor.start_pasting(screen.clone());
+        editor.move_cursor(Some(Coord { i: 4, j: 1 }));
+        assert_eq!(
+            editor.ghost(),
+            [(2, 1), (2, 2), (2, 3), (3, 3), (4, 2)].to_vec()
+        );
+        let edit = editor.press().unwrap();
+        edit.apply(&mut grid);
+        assert_eq!(editor.ghost(), Vec::new());
+
+        let editor = editor_with_selection(Coord { i: 0, j: 0 }, Coord { i: 5, j: 5 });
+        let live: Vec<_> = grid.live_cells().collect();
+        let copied = editor.copy(|coord| live.contains(&(coord.i, coord.j)));
+        assert_eq!(copied, Some(screen));
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut editor = editor_with_selection(Coord { i: 0, j: 0 }, Coord { i: 1, j: 2 });
+        assert_eq!(
+            editor.transform(Transform::Rotate90),
+            Some(Edit::Transform(
+                BoundingBox {
+                    min_i: 0,
+                    min_j: 0,
+                    max_i: 1,
+                    max_j: 2,
+                },
+                Transform::Rotate270
+            ))
+        );
+        let rotated = editor.selection().copied().unwrap();
+        assert_eq!((rotated.height(), rotated.width()), (3, 2));
+        // Patterns waiting to be pasted get turned instead
+        editor.start_pasting("oo".parse().unwrap());
+        assert_eq!(editor.transform(Transform::Rotate90), None);
+        editor.move_cursor(Some(Coord { i: 5, j: 5 }));
+        assert_eq!(editor.ghost(), [(4, 5), (5, 5)].to_vec());
+    }
+}
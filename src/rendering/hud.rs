@@ -0,0 +1,181 @@
+use super::font;
+use super::Instance;
+use crate::data::Rule;
+use std::time::{Duration, Instant};
+
+// How often the measured rates get refreshed
+const RATE_WINDOW: Duration = Duration::from_millis(500);
+
+/// Heads-up display showing what the simulation is up to
+#[derive(Debug)]
+pub struct Hud {
+    visible: bool,
+    rule: Rule,
+    updates_per_second: u16,
+    fps: RateMeter,
+    sim_rate: RateMeter,
+    last_generation: u64,
+}
+
+impl Hud {
+    pub fn new(rule: Rule, updates_per_second: u16, now: Instant) -> Self {
+        Self {
+            visible: true,
+            rule,
+            updates_per_second,
+            fps: RateMeter::new(now),
+            sim_rate: RateMeter::new(now),
+            last_generation: 0,
+        }
+    }
+
+    pub const fn visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Records that a frame showing the given generation was rendered at `now`
+    pub fn record_frame(&mut self, generation: u64, now: Instant) {
+        self.fps.record(1, now);
+        self.sim_rate
+            .record(generation.saturating_sub(self.last_generation), now);
+        self.last_generation = generation;
+    }
+
+    /// Lines of text to display for a board with the given population
+    pub fn lines(&self, population: usize) -> Vec<String> {
+        vec![
+            format!("GEN  {}", self.last_generation),
+            format!("POP  {population}"),
+            format!("RULE {}", self.rule),
+            format!("FPS  {:.1}", self.fps.rate()),
+            format!(
+                "SIM  {:.1}/{} UPS",
+                self.sim_rate.rate(),
+                self.updates_per_second
+            ),
+        ]
+    }
+}
+
+// Counts events and turns them into a per-second rate every `RATE_WINDOW`
+#[derive(Debug)]
+struct RateMeter {
+    window_start: Instant,
+    count: u64,
+    rate: f64,
+}
+
+impl RateMeter {
+    const fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            count: 0,
+            rate: 0.,
+        }
+    }
+
+    fn record(&mut self, count: u64, now: Instant) {
+        self.count += count;
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed >= RATE_WINDOW {
+            self.rate = self.count as f64 / elapsed.as_secs_f64();
+            self.count = 0;
+            self.window_start = now;
+        }
+    }
+
+    const fn rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+/// Lays out lines of text as instances, starting at the given top left corner in
+/// normalised device coordinates. `pixel` is the size of a single font pixel.
+///
+/// Every lit pixel gets a shadow drawn first, offset down and to the right, so that
+/// the text stays readable on top of the board.
+pub fn text_instances<S: AsRef<str>>(
+    lines: &[S],
+    top_left: [f32; 2],
+    pixel: [f32; 2],
+    colour: [f32; 4],
+    shadow: [f32; 4],
+) -> Vec<Instance> {
+    let mut shadows = vec![];
+    let mut text = vec![];
+    font::for_each_pixel(lines, |column, row| {
+        let translate = [
+            top_left[0] + (column as f32 + 0.5) * pixel[0],
+            top_left[1] - (row as f32 + 0.5) * pixel[1],
+        ];
+        shadows.push(Instance {
+            translate: [translate[0] + pixel[0], translate[1] - pixel[1]],
+            colour: shadow,
+        });
+        text.push(Instance { translate, colour });
+    });
+    shadows.append(&mut text);
+    shadows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let now = Instant::now();
+        let mut hud = Hud::new(Rule::CONWAY, 30, now);
+        hud.record_frame(5, now + Duration::from_millis(100));
+        hud.record_frame(15, now + Duration::from_secs(1));
+        assert_eq!(
+            hud.lines(42),
+            vec![
+                "GEN  15".to_string(),
+                "POP  42".to_string(),
+                "RULE B3/S23".to_string(),
+                "FPS  2.0".to_string(),
+                "SIM  15.0/30 UPS".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut hud = Hud::new(Rule::CONWAY, 30, Instant::now());
+        assert!(hud.visible());
+        hud.toggle();
+        assert!(!hud.visible());
+    }
+
+    #[test]
+    fn test_rate_meter() {
+        let now = Instant::now();
+        let mut meter = RateMeter::new(now);
+        meter.record(10, now + Duration::from_millis(100));
+        assert!(meter.rate().abs() < f64::EPSILON);
+        meter.record(10, now + Duration::from_millis(500));
+        assert!((meter.rate() - 40.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_text_instances() {
+        let white = [1.; 4];
+        let black = [0., 0., 0., 1.];
+        let instances = text_instances(&["-"], [-1., 1.], [0.1, 0.2], white, black);
+        assert_eq!(instances.len(), 2 * font::GLYPH_WIDTH);
+        let (shadows, text) = instances.split_at(font::GLYPH_WIDTH);
+        assert!(shadows.iter().all(|inst| inst.colour == black));
+        assert!(text.iter().all(|inst| inst.colour == white));
+        let first = text[0].translate;
+        assert!((first[0] - -0.95).abs() < 1e-6);
+        assert!((first[1] - 0.3).abs() < 1e-6);
+        let first_shadow = shadows[0].translate;
+        assert!((first_shadow[0] - -0.85).abs() < 1e-6);
+        assert!((first_shadow[1] - 0.1).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,251 @@
+use std::error::Error;
+use std::fmt;
+
+/// Fraction of each cell's space that is left as a gap by default
+pub const DEFAULT_CELL_GAP: f32 = 0.1;
+
+/// Below this many pixels across, cells are drawn without gaps or gridlines, since
+/// they would only cause moiré patterns at that size
+pub const MIN_CELL_PIXELS: f32 = 4.;
+
+/// How cells are separated from each other
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Spacing {
+    /// Cells are separated by gaps, taking up the given fraction of each cell's space
+    Gaps(f32),
+    /// Cells touch each other and are separated by one pixel wide gridlines, with a
+    /// major gridline every given number of cells
+    Gridlines(usize),
+}
+
+impl Default for Spacing {
+    fn default() -> Self {
+        Self::Gaps(DEFAULT_CELL_GAP)
+    }
+}
+
+/// Returned when a cell gap is out of range
+#[derive(PartialEq, Debug, Clone)]
+pub struct InvalidCellGap(pub f32);
+
+impl fmt::Display for InvalidCellGap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid cell gap [{}], expected a number from 0 up to (but not including) 1",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidCellGap {}
+
+impl Spacing {
+    /// Gaps taking up the given fraction of each cell's space
+    #[allow(clippy::missing_errors_doc)]
+    pub fn gaps(ratio: f32) -> Result<Self, InvalidCellGap> {
+        if (0. ..1.).contains(&ratio) {
+            Ok(Self::Gaps(ratio))
+        } else {
+            Err(InvalidCellGap(ratio))
+        }
+    }
+}
+
+/// Where cells and gridlines go on screen, in normalised device coordinates.
+///
+/// Row 0 is at the bottom and column 0 on the left.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Layout {
+    columns: usize,
+    rows: usize,
+    cell: [f32; 2],
+    pitch: [f32; 2],
+    gap: [f32; 2],
+    // Size of a single screen pixel
+    pixel: [f32; 2],
+    major_every: Option<usize>,
+}
+
+/// A gridline, running either horizontally or vertically across the whole board
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Gridline {
+    /// X coordinate for vertical lines, Y coordinate for horizontal ones
+    pub position: f32,
+    pub major: bool,
+}
+
+impl Layout {
+    /// Lays out a board with the given number of columns and rows in a window of the
+    /// given size in pixels
+    pub fn new(columns: usize, rows: usize, window_pixels: [f32; 2], spacing: Spacing) -> Self {
+        let (ratio, major_every) = match spacing {
+            Spacing::Gaps(ratio) => (ratio, None),
+            Spacing::Gridlines(major_every) => (0., Some(major_every.max(1))),
+        };
+        let pixel = [2. / window_pixels[0].max(1.), 2. / window_pixels[1].max(1.)];
+        let pitch_for = |count: usize, ratio: f32| 2. / (count.max(1) as f32 + ratio);
+        let too_small = (0..2).any(|axis| {
+            let count = [columns, rows][axis];
+            pitch_for(count, 0.) / pixel[axis] < MIN_CELL_PIXELS
+        });
+        let (ratio, major_every) = if too_small {
+            (0., None)
+        } else {
+            (ratio, major_every)
+        };
+        let pitch = [pitch_for(columns, ratio), pitch_for(rows, ratio)];
+        let gap = [pitch[0] * ratio, pitch[1] * ratio];
+        Self {
+            columns,
+            rows,
+            cell: [pitch[0] - gap[0], pitch[1] - gap[1]],
+            pitch,
+            gap,
+            pixel,
+            major_every,
+        }
+    }
+
+    pub const fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub const fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Width and height of a single cell
+    pub const fn cell_size(&self) -> [f32; 2] {
+        self.cell
+    }
+
+    /// Scale to apply to the unit quad so that it is the size of a cell
+    pub const fn scale(&self) -> [[f32; 2]; 2] {
+        [[self.cell[0], 0.], [0., self.cell[1]]]
+    }
+
+    /// Size of a single screen pixel
+    pub const fn pixel_size(&self) -> [f32; 2] {
+        self.pixel
+    }
+
+    /// Centre of the cell in row `i` and column `j`
+    pub fn centre(&self, i: usize, j: usize) -> [f32; 2] {
+        [
+            -1. + self.gap[0] + self.cell[0] / 2. + j as f32 * self.pitch[0],
+            -1. + self.gap[1] + self.cell[1] / 2. + i as f32 * self.pitch[1],
+        ]
+    }
+
+    /// Row and column of the cell under a point, counting half the gap on each side of a
+    /// cell as part of it, or None if the point is off the board
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn cell_at(&self, point: [f32; 2]) -> Option<(usize, usize)> {
+        let index = |axis: usize, count: usize| {
+            let index = ((point[axis] + 1. - self.gap[axis] / 2.) / self.pitch[axis]).floor();
+            (index >= 0. && index < count as f32).then_some(index as usize)
+        };
+        Some((index(1, self.rows)?, index(0, self.columns)?))
+    }
+
+    /// Vertical and horizontal gridlines on the boundaries between cells, if gridlines
+    /// are being drawn.
+    ///
+    /// Each line is centred on the pixel just after its boundary rather than on the
+    /// boundary itself, so that it covers exactly one pixel instead of straddling two.
+    pub fn gridlines(&self) -> Option<(Vec<Gridline>, Vec<Gridline>)> {
+        let major_every = self.major_every?;
+        let lines = |count: usize, pitch: f32, pixel: f32| -> Vec<Gridline> {
+            (0..=count)
+                .map(|k| Gridline {
+                    position: (-1. + k as f32 * pitch + pixel / 2.).min(1. - pixel / 2.),
+                    major: k % major_every == 0,
+                })
+                .collect()
+        };
+        Some((
+            lines(self.columns, self.pitch[0], self.pixel[0]),
+            lines(self.rows, self.pitch[1], self.pixel[1]),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_spacing_gaps() {
+        assert_eq!(Spacing::gaps(0.), Ok(Spacing::Gaps(0.)));
+        assert_eq!(Spacing::gaps(0.5), Ok(Spacing::Gaps(0.5)));
+        assert!(Spacing::gaps(1.).is_err());
+        assert!(Spacing::gaps(-0.1).is_err());
+        assert!(Spacing::gaps(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_layout_with_gaps() {
+        let layout = Layout::new(4, 2, [800., 400.], Spacing::Gaps(0.2));
+        // 4 cells and 5 gaps fill the width: 4 * 0.8p + 5 * 0.2p = 2
+        let pitch = 2. / 4.2;
+        assert_close(layout.cell_size()[0], 0.8 * pitch);
+        let first = layout.centre(0, 0);
+        assert_close(first[0], -1. + 0.2 * pitch + 0.4 * pitch);
+        let last = layout.centre(1, 3);
+        assert_close(last[0] + layout.cell_size()[0] / 2., 1. - 0.2 * pitch);
+        assert_close(last[1] + layout.cell_size()[1] / 2., 1. - 0.2 * 2. / 2.2);
+        assert_eq!(layout.gridlines(), None);
+        assert_eq!(
+            layout.scale(),
+            [[layout.cell_size()[0], 0.], [0., layout.cell_size()[1]]]
+        );
+    }
+
+    #[test]
+    fn test_layout_with_gridlines() {
+        let layout = Layout::new(4, 2, [800., 400.], Spacing::Gridlines(2));
+        assert_close(layout.cell_size()[0], 0.5);
+        assert_close(layout.centre(0, 0)[0], -0.75);
+        let (vertical, horizontal) = layout.gridlines().unwrap();
+        assert_eq!(vertical.len(), 5);
+        assert_eq!(horizontal.len(), 3);
+        let majors: Vec<bool> = vertical.iter().map(|line| line.major).collect();
+        assert_eq!(majors, vec![true, false, true, false, true]);
+        // 800 pixels across, so half a pixel is 1/800
+        assert_close(vertical[0].position, -1. + 1. / 800.);
+        assert_close(vertical[1].position, -0.5 + 1. / 800.);
+        assert_close(vertical[4].position, 1. - 1. / 800.);
+        assert_close(horizontal[2].position, 1. - 1. / 400.);
+    }
+
+    #[test]
+    fn test_cell_at() {
+        let layout = Layout::new(4, 2, [800., 400.], Spacing::Gaps(0.2));
+        for (i, j) in [(0, 0), (1, 3), (0, 2)] {
+            assert_eq!(layout.cell_at(layout.centre(i, j)), Some((i, j)));
+        }
+        // Halfway between two cells' centres, and just inside the first cell's gap
+        let between = (layout.centre(1, 1)[0] + layout.centre(1, 2)[0]) / 2.;
+        assert_eq!(layout.cell_at([between - 0.001, 0.5]), Some((1, 1)));
+        assert_eq!(layout.cell_at([between + 0.001, 0.5]), Some((1, 2)));
+        assert_eq!(layout.cell_at([-0.999, -0.999]), None);
+        assert_eq!(layout.cell_at([0., 1.5]), None);
+    }
+
+    #[test]
+    fn test_small_cells_have_no_gaps_or_gridlines() {
+        // 3 pixels per cell across
+        let layout = Layout::new(300, 10, [900., 900.], Spacing::Gaps(0.2));
+        assert_close(layout.cell_size()[0], 2. / 300.);
+        assert_close(layout.centre(0, 0)[0], -1. + 1. / 300.);
+        let layout = Layout::new(300, 10, [900., 900.], Spacing::Gridlines(10));
+        assert_eq!(layout.gridlines(), None);
+        let layout = Layout::new(30, 10, [900., 900.], Spacing::Gridlines(10));
+        assert!(layout.gridlines().is_some());
+    }
+}
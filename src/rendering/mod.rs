@@ -1,4 +1,29 @@
-use super::data::{Grid, GridIdx};
+pub mod display;
+pub mod editor;
+pub mod font;
+pub mod hud;
+pub mod layout;
+pub mod palette;
+pub mod scrubber;
+pub mod software;
+pub mod theme;
+
+use self::display::DisplayMode;
+use self::editor::Editor;
+use self::hud::Hud;
+use self::layout::{Gridline, Layout, Spacing};
+use self::palette::Palette;
+use self::scrubber::Scrubber;
+use self::software::SoftwareRenderer;
+use self::theme::Theme;
+use super::census::rle;
+use super::data::{Coord, Grid, GridIdx, Rule, Topology, Transform};
+use super::export::animation::{Animation, AnimationOptions, DEFAULT_RECORDING_PATH};
+use super::export::runlog::RunLog;
+use super::export::svg::{self, Svg};
+use super::export::{self, ImageSize, DEFAULT_SCREENSHOT_PATH};
+use super::simulation::{Command, Edit, FrameReceiver, Replay, Simulation, SimulationHandle};
+use copypasta::{ClipboardContext, ClipboardProvider};
 use gfx;
 use gfx::traits::FactoryExt;
 use gfx::Device;
@@ -6,12 +31,12 @@ use gfx::Factory;
 use gfx_device_gl::{CommandBuffer, Device as GlDevice, Resources};
 use gfx_window_glutin;
 use glutin;
-use glutin::dpi::LogicalSize;
+use glutin::dpi::{LogicalPosition, LogicalSize};
 use rayon::prelude::*;
 use std::error::Error;
-use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::{Duration, Instant};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Instant;
 
 const WINDOW_TITLE: &str = "Simple Life";
 
@@ -32,13 +57,19 @@ const QUAD_VERTICES: [Vertex; 4] = [
 
 const QUAD_INDICES: [u16; 6] = [0, 1, 2, 2, 3, 0];
 
-const CLEARING_COLOR: [f32; 4] = [0.1, 0.2, 0.3, 1.0];
+// Screen pixels per font pixel, before accounting for HiDPI
+const HUD_FONT_SCALE: f64 = 2.;
+// Gap between the HUD and the edges of the window, in font pixels
+const HUD_MARGIN: f32 = 3.;
+const HUD_MAX_INSTANCES: usize = 16384;
 
-const WHITE: [f32; 4] = [1., 1., 1., 1.];
-const COLOURED: [f32; 4] = [0.2, 0.4, 0.5, 1.];
+// How far major gridlines are blended from the background towards the text colour
+const MAJOR_GRIDLINE_BLEND: f32 = 0.5;
 
-const SCALE_TOTAL: f32 = 2.0;
-const INSTANCE_PORTION: f32 = 1.8;
+// How far selected cells, and cells a pattern is about to be pasted over, are blended
+// towards the text colour
+const SELECTION_BLEND: f32 = 0.25;
+const GHOST_BLEND: f32 = 0.6;
 
 pub type ColorFormat = gfx::format::Rgba8;
 pub type DepthFormat = gfx::format::DepthStencil;
@@ -68,41 +99,123 @@ gfx_defines! {
 
 // Fills the provided instance buffer, but also returns a vector of instances for later
 // manipulation, when we want to update the instances and update the buffer again.
-fn fill_instances(instances: &mut [Instance], grid: &Grid, size: [[f32; 2]; 2]) -> Vec<Instance> {
-    let width = grid.width();
-    let height = grid.height();
-    let cells = grid.cells();
-
-    let size_x = size[0][0];
-    let size_y = size[1][1];
-    let scale_remaining = SCALE_TOTAL - INSTANCE_PORTION;
-    let gap_x = scale_remaining / (width + 1) as f32;
-    let gap_y = scale_remaining / (height + 1) as f32;
-    let begin_x = -1. + gap_x + (size_x / 2.);
-    let begin_y = -1. + gap_y + (size_y / 2.);
-
-    let mut translate = [begin_x, begin_y];
-
+fn fill_instances(
+    instances: &mut [Instance],
+    grid: &Grid,
+    layout: &Layout,
+    theme: &Theme,
+) -> Vec<Instance> {
     let mut v = Vec::with_capacity(grid.area());
     let mut index = 0;
-    for row in cells {
-        for cell in row {
-            let colour = if cell.alive() { COLOURED } else { WHITE };
-            let inst = Instance { translate, colour };
+    for (i, row) in grid.cells().into_iter().enumerate() {
+        for (j, cell) in row.into_iter().enumerate() {
+            let colour = if cell.alive() {
+                theme.alive
+            } else {
+                theme.dead
+            };
+            let inst = Instance {
+                translate: layout.centre(i, j),
+                colour,
+            };
             v.push(inst);
             instances[index] = inst;
-            translate[0] += size_x + gap_x;
             index += 1;
         }
-        translate[1] += size_y + gap_y;
-        translate[0] = begin_x;
     }
     v
 }
 
+// Instances for one direction of gridlines, each a single pixel thick quad that gets
+// stretched across the whole window
+fn gridline_instances(lines: &[Gridline], vertical: bool, theme: &Theme) -> Vec<Instance> {
+    let major = major_gridline_colour(theme);
+    lines
+        .iter()
+        .map(|line| Instance {
+            translate: if vertical {
+                [line.position, 0.]
+            } else {
+                [0., line.position]
+            },
+            colour: if line.major { major } else { theme.background },
+        })
+        .collect()
+}
+
+/// Colour of every major gridline, the minor ones being the background colour
+pub(crate) fn major_gridline_colour(theme: &Theme) -> [f32; 4] {
+    display::lerp(theme.background, theme.text, MAJOR_GRIDLINE_BLEND)
+}
+
+// Where screenshots and recordings go, and how they're drawn
+struct Exports {
+    screenshot_path: String,
+    // Pixels per cell in screenshots, which otherwise match the window size
+    screenshot_scale: Option<u32>,
+    // How often SVG screenshots are labelled with coordinates, if at all
+    svg_ruler: usize,
+    // Animation being recorded, along with the last generation that was added to it
+    recording: Option<(Animation, u64)>,
+    recording_path: String,
+    recording_options: AnimationOptions,
+    recording_scale: Option<u32>,
+}
+
+impl Default for Exports {
+    fn default() -> Self {
+        Self {
+            screenshot_path: DEFAULT_SCREENSHOT_PATH.to_string(),
+            screenshot_scale: None,
+            svg_ruler: 0,
+            recording: None,
+            recording_path: DEFAULT_RECORDING_PATH.to_string(),
+            recording_options: AnimationOptions::default(),
+            recording_scale: None,
+        }
+    }
+}
+
+// What's being shown: a board being simulated, or a replay of a recorded run
+enum Source {
+    Simulation(Simulation),
+    Replay(Replay),
+}
+
+impl Source {
+    const fn grid(&self) -> &Grid {
+        match self {
+            Self::Simulation(simulation) => simulation.grid(),
+            Self::Replay(replay) => replay.grid(),
+        }
+    }
+
+    fn spawn(self) -> Result<SimulationHandle, Box<dyn Error>> {
+        match self {
+            Self::Simulation(simulation) => simulation.spawn(),
+            Self::Replay(replay) => replay.spawn(),
+        }
+    }
+}
+
 pub struct App {
-    grid: Arc<Mutex<Grid>>,
-    updates_per_second: u16,
+    // Handed over to its own thread once we start running
+    source: Option<Source>,
+    frames: FrameReceiver,
+    area_requires_par: bool,
+    rule: Rule,
+    topology: Topology,
+    editor: Editor,
+    palette: Palette,
+    // Only there when replaying
+    scrubber: Option<Scrubber>,
+    // Where the mouse is, in normalised device coordinates
+    cursor: Option<[f32; 2]>,
+    // Opened the first time something gets copied or pasted
+    clipboard: Option<ClipboardContext>,
+    display_mode: DisplayMode,
+    // Themes that can be cycled through, starting with the one currently in use
+    themes: Vec<Theme>,
     window: glutin::WindowedContext,
     device: GlDevice,
     // main_depth: DepthStencilView<Resources, DepthFormat>,
@@ -114,6 +227,18 @@ pub struct App {
     upload: gfx::handle::Buffer<Resources, Instance>,
     instances: Vec<Instance>,
     uploading: bool,
+    window_pixels: [f32; 2],
+    spacing: Spacing,
+    layout: Layout,
+    exports: Exports,
+    // Vertical then horizontal gridlines, drawn with the same pipeline as cells
+    gridline_data: [pipe::Data<Resources>; 2],
+    gridline_slices: [gfx::Slice<Resources>; 2],
+    hud: Hud,
+    hud_data: pipe::Data<Resources>,
+    hud_slice: gfx::Slice<Resources>,
+    // Size of a single font pixel in normalised device coordinates
+    hud_pixel: [f32; 2],
 }
 
 impl App {
@@ -124,6 +249,47 @@ impl App {
         window_height: u32,
         updates_per_second: u16,
     ) -> Result<Self, Box<dyn Error>> {
+        let (simulation, frames) = Simulation::new(grid, updates_per_second);
+        let source = Source::Simulation(simulation);
+        Self::open(
+            source,
+            frames,
+            window_width,
+            window_height,
+            updates_per_second,
+        )
+    }
+
+    /// Plays back a recorded run rather than simulating one, with a bar along the bottom
+    /// of the window for scrubbing through it
+    #[allow(clippy::missing_errors_doc)]
+    pub fn replay(
+        log: RunLog,
+        window_width: u32,
+        window_height: u32,
+        updates_per_second: u16,
+    ) -> Result<Self, Box<dyn Error>> {
+        let (first, last) = log.generations();
+        let (replay, frames) = Replay::new(log, updates_per_second);
+        let mut app = Self::open(
+            Source::Replay(replay),
+            frames,
+            window_width,
+            window_height,
+            updates_per_second,
+        )?;
+        app.scrubber = Some(Scrubber::new(first, last));
+        Ok(app)
+    }
+
+    fn open(
+        source: Source,
+        frames: FrameReceiver,
+        window_width: u32,
+        window_height: u32,
+        updates_per_second: u16,
+    ) -> Result<Self, Box<dyn Error>> {
+        let grid = source.grid();
         let events_loop = glutin::EventsLoop::new();
         let window_size = LogicalSize::new(window_width.into(), window_height.into());
         let builder = glutin::WindowBuilder::new()
@@ -134,19 +300,21 @@ impl App {
             gfx_window_glutin::init::<ColorFormat, DepthFormat>(builder, context, &events_loop)?;
         let encoder = factory.create_command_buffer().into();
 
-        let width: u32 = u32::try_from(grid.width())?;
-        let height: u32 = u32::try_from(grid.height())?;
         let area = u32::try_from(grid.area())?;
 
-        let size = [
-            [INSTANCE_PORTION / width as f32, 0.],
-            [0., INSTANCE_PORTION / height as f32],
-        ];
+        let window_pixels = window_pixel_size(&window, window_width, window_height);
+        let layout = Layout::new(
+            grid.width(),
+            grid.height(),
+            window_pixels,
+            Spacing::default(),
+        );
+        let size = layout.scale();
 
         let upload = factory.create_upload_buffer(area as usize)?;
         let insts = {
             let mut writer = factory.write_mapping(&upload)?;
-            fill_instances(&mut writer, &grid, size)
+            fill_instances(&mut writer, grid, &layout, &Theme::default())
         };
 
         let instances = factory.create_buffer(
@@ -160,10 +328,49 @@ impl App {
             factory.create_vertex_buffer_with_slice(&QUAD_VERTICES, &QUAD_INDICES[..]);
         slice.instances = Some((area, 0));
         let locals = Locals { scale: size };
+        let data = pipe::Data {
+            vertex: quad_vertices,
+            instance: instances,
+            scale: size,
+            locals: factory.create_buffer_immutable(
+                &[locals],
+                gfx::buffer::Role::Constant,
+                gfx::memory::Bind::empty(),
+            )?,
+            out: main_color,
+        };
+
+        let pixel = layout.pixel_size();
+        let gridline_data = gridline_data(
+            &mut factory,
+            &data,
+            pixel,
+            grid.width().max(grid.height()) + 1,
+        )?;
+
+        let hud_pixel = hud_pixel_size(&window, pixel);
+        let hud_data = hud_data(&mut factory, &data, hud_pixel)?;
+        let mut hud_slice = slice.clone();
+        hud_slice.instances = Some((0, 0));
+        let gridline_slices = [hud_slice.clone(), hud_slice.clone()];
+
+        let area_requires_par = grid.area_requires_bool();
+        let (rule, topology) = (*grid.rule(), grid.topology());
+        let hud = Hud::new(rule, updates_per_second, Instant::now());
 
         Ok(Self {
-            grid: Arc::new(Mutex::new(grid)),
-            updates_per_second,
+            source: Some(source),
+            frames,
+            area_requires_par,
+            rule,
+            topology,
+            editor: Editor::default(),
+            palette: Palette::default(),
+            scrubber: None,
+            cursor: None,
+            clipboard: None,
+            display_mode: DisplayMode::Classic,
+            themes: Theme::all(),
             window,
             device,
             events_loop,
@@ -174,55 +381,299 @@ impl App {
                 pipe::new(),
             )?,
             encoder,
-            data: pipe::Data {
-                vertex: quad_vertices,
-                instance: instances,
-                scale: size,
-                locals: factory.create_buffer_immutable(
-                    &[locals],
-                    gfx::buffer::Role::Constant,
-                    gfx::memory::Bind::empty(),
-                )?,
-                out: main_color,
-            },
+            data,
             instances: insts,
             slice,
             upload,
             uploading: true,
+            window_pixels,
+            spacing: Spacing::default(),
+            layout,
+            exports: Exports::default(),
+            gridline_data,
+            gridline_slices,
+            hud,
+            hud_data,
+            hud_slice,
+            hud_pixel,
         })
     }
 
+    /// Starts off colouring cells with the given mode, rather than the classic one
+    #[must_use]
+    pub const fn with_display_mode(mut self, display_mode: DisplayMode) -> Self {
+        self.display_mode = display_mode;
+        self
+    }
+
+    /// Uses the given theme rather than the default one. The built-in themes can still be
+    /// cycled through afterwards.
+    #[must_use]
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.themes.retain(|t| t.name != theme.name);
+        self.themes.insert(0, theme);
+        self
+    }
+
+    /// Separates cells with the given spacing, rather than the default gaps
+    #[must_use]
+    pub fn with_spacing(mut self, spacing: Spacing) -> Self {
+        let columns = self.layout.columns();
+        self.layout = Layout::new(columns, self.layout.rows(), self.window_pixels, spacing);
+        for (idx, inst) in self.instances.iter_mut().enumerate() {
+            inst.translate = self.layout.centre(idx / columns, idx % columns);
+        }
+        self.data.scale = self.layout.scale();
+        self.spacing = spacing;
+        self
+    }
+
+    /// Saves screenshots to the given path, with `{gen}` replaced by the generation. With
+    /// a scale, cells are that many pixels across rather than the size they are on screen.
+    #[must_use]
+    pub fn with_screenshots(mut self, path: &str, scale: Option<u32>) -> Self {
+        self.exports.screenshot_path = path.to_string();
+        self.exports.screenshot_scale = scale;
+        self
+    }
+
+    /// Bytes of memory to keep earlier states in, for undoing and stepping back
+    #[must_use]
+    pub fn with_rewind_budget(mut self, budget: usize) -> Self {
+        if let Some(Source::Simulation(simulation)) = self.source.take() {
+            let simulation = simulation.with_rewind_budget(budget);
+            self.source = Some(Source::Simulation(simulation));
+        }
+        self
+    }
+
+    /// Records every generation to a run log, see `RunLogWriter`. Replays aren't recorded.
+    #[must_use]
+    pub fn with_log(mut self, writer: Box<dyn Write + Send>) -> Self {
+        if let Some(Source::Simulation(simulation)) = self.source.take() {
+            self.source = Some(Source::Simulation(simulation.with_log(writer)));
+        }
+        self
+    }
+
+    /// Labels every this many rows and columns in screenshots saved as SVGs, see
+    /// `Svg::with_ruler`
+    #[must_use]
+    pub const fn with_svg_ruler(mut self, every: usize) -> Self {
+        self.exports.svg_ruler = every;
+        self
+    }
+
+    /// Records animations to the given path, with `{gen}` replaced by the generation
+    /// recording started at. Scale works the same way as for screenshots.
+    #[must_use]
+    pub fn with_recording(
+        mut self,
+        path: &str,
+        options: AnimationOptions,
+        scale: Option<u32>,
+    ) -> Self {
+        self.exports.recording_path = path.to_string();
+        self.exports.recording_options = options;
+        self.exports.recording_scale = scale;
+        self
+    }
+
     #[inline]
     fn render(&mut self) -> Result<(), Box<dyn Error>> {
         if self.uploading {
             self.encoder
                 .copy_buffer(&self.upload, &self.data.instance, 0, 0, self.upload.len())?;
             self.uploading = false;
-        } else {
-            self.update_instances()?;
-            self.encoder
-                .update_buffer(&self.data.instance, &self.instances, 0)?;
         }
-        self.encoder.clear(&self.data.out, CLEARING_COLOR);
+        self.update_instances()?;
+        self.encoder
+            .update_buffer(&self.data.instance, &self.instances, 0)?;
+        self.encoder
+            .clear(&self.data.out, self.themes[0].background);
         self.encoder.draw(&self.slice, &self.pso, &self.data);
+        if let Some((vertical, horizontal)) = self.layout.gridlines() {
+            self.render_gridlines(&vertical, &horizontal)?;
+        }
+
+        let frame = self.frames.latest();
+        let (population, generation) = (frame.population(), frame.generation());
+        self.hud.record_frame(generation, Instant::now());
+        self.render_text(population, generation)?;
+
         self.encoder.flush(&mut self.device);
         self.window.swap_buffers()?;
         self.device.cleanup();
         Ok(())
     }
 
-    #[allow(clippy::significant_drop_tightening)]
+    fn render_gridlines(
+        &mut self,
+        vertical: &[Gridline],
+        horizontal: &[Gridline],
+    ) -> Result<(), Box<dyn Error>> {
+        let theme = &self.themes[0];
+        for (idx, lines) in [vertical, horizontal].into_iter().enumerate() {
+            let instances = gridline_instances(lines, idx == 0, theme);
+            let (data, slice) = (&self.gridline_data[idx], &mut self.gridline_slices[idx]);
+            self.encoder.update_buffer(&data.instance, &instances, 0)?;
+            slice.instances = Some((u32::try_from(instances.len())?, 0));
+            self.encoder.draw(slice, &self.pso, data);
+        }
+        Ok(())
+    }
+
+    // Draws the HUD in the top left, the palette in the top right and the scrubber along
+    // the bottom, whichever are showing, in one go
+    #[allow(clippy::cast_precision_loss)]
+    fn render_text(&mut self, population: usize, generation: u64) -> Result<(), Box<dyn Error>> {
+        let (pixel, theme) = (self.hud_pixel, &self.themes[0]);
+        let top = 1. - HUD_MARGIN * pixel[1];
+        let mut instances = Vec::new();
+        if self.hud.visible() {
+            instances = hud::text_instances(
+                &self.hud.lines(population),
+                [-1. + HUD_MARGIN * pixel[0], top],
+                pixel,
+                theme.text,
+                theme.shadow,
+            );
+        }
+        if self.palette.is_open() {
+            let lines = self.palette.lines();
+            let longest = lines.iter().map(|line| line.chars().count()).max();
+            let width = (longest.unwrap_or(0) * font::ADVANCE) as f32 + HUD_MARGIN;
+            instances.extend(hud::text_instances(
+                &lines,
+                [1. - width * pixel[0], top],
+                pixel,
+                theme.text,
+                theme.shadow,
+            ));
+        }
+        if let Some(scrubber) = &self.scrubber {
+            let unplayed = major_gridline_colour(theme);
+            instances.extend(scrubber.instances(generation, pixel, theme.text, unplayed));
+        }
+        instances.truncate(HUD_MAX_INSTANCES);
+        if !instances.is_empty() {
+            self.encoder
+                .update_buffer(&self.hud_data.instance, &instances, 0)?;
+            self.hud_slice.instances = Some((u32::try_from(instances.len())?, 0));
+            self.encoder
+                .draw(&self.hud_slice, &self.pso, &self.hud_data);
+        }
+        Ok(())
+    }
+
+    // Draws boards in software, the same way they appear on screen minus the HUD. With
+    // a scale, cells are that many pixels across, otherwise images match the window size.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn software_renderer(&self, scale: Option<u32>) -> Result<SoftwareRenderer, Box<dyn Error>> {
+        let size = scale.map_or_else(
+            || ImageSize::Pixels(self.window_pixels[0] as u32, self.window_pixels[1] as u32),
+            ImageSize::PerCell,
+        );
+        let (columns, rows) = (self.layout.columns(), self.layout.rows());
+        let (width, height) = size.dimensions(columns, rows)?;
+        Ok(SoftwareRenderer::new(columns, rows, width, height)
+            .with_spacing(self.spacing)
+            .with_theme(self.themes[0].clone())
+            .with_display_mode(self.display_mode))
+    }
+
+    fn save_screenshot(&mut self) -> Result<PathBuf, Box<dyn Error>> {
+        let generation = self.frames.latest().generation();
+        let path = export::output_path(&self.exports.screenshot_path, generation);
+        if svg::is_svg(&path) {
+            let svg = Svg::new()
+                .with_spacing(self.spacing)
+                .with_theme(self.themes[0].clone())
+                .with_ruler(self.exports.svg_ruler);
+            let (columns, rows) = (self.layout.columns(), self.layout.rows());
+            svg::save(
+                &svg.render_frame(self.frames.latest(), columns, rows),
+                &path,
+            )?;
+        } else {
+            let renderer = self.software_renderer(self.exports.screenshot_scale)?;
+            export::png::save(&renderer.render(self.frames.latest()), &path)?;
+        }
+        Ok(path)
+    }
+
+    fn toggle_recording(&mut self) {
+        if let Some((animation, _)) = self.exports.recording.take() {
+            let path = animation.path().to_path_buf();
+            match animation.finish() {
+                Ok(frames) => println!("Saved {frames} frames to {}", path.display()),
+                Err(err) => eprintln!("Could not save recording: {err}"),
+            }
+        } else {
+            let generation = self.frames.latest().generation();
+            let path = export::output_path(&self.exports.recording_path, generation);
+            match Animation::create(&path, self.exports.recording_options) {
+                Ok(animation) => {
+                    println!("Recording to {}, press R again to stop", path.display());
+                    // Makes sure the generation we started at is the first frame
+                    let before_start = generation.wrapping_sub(self.exports.recording_options.skip);
+                    self.exports.recording = Some((animation, before_start));
+                }
+                Err(err) => eprintln!("Could not start recording: {err}"),
+            }
+        }
+    }
+
+    // Adds the latest frame to the recording, if there is one and enough generations
+    // have gone by since the last frame that was added
+    fn record_frame(&mut self) {
+        let Some((_, last)) = self.exports.recording else {
+            return;
+        };
+        let generation = self.frames.latest().generation();
+        if generation.wrapping_sub(last) < self.exports.recording_options.skip.max(1) {
+            return;
+        }
+        let recorded = self
+            .software_renderer(self.exports.recording_scale)
+            .and_then(|renderer| {
+                let image = renderer.render(self.frames.latest());
+                let (animation, last) = self.exports.recording.as_mut().ok_or("Not recording")?;
+                *last = generation;
+                animation.add(&image)
+            });
+        if let Err(err) = recorded {
+            eprintln!("Could not record frame, stopping: {err}");
+            self.toggle_recording();
+        }
+    }
+
     #[doc(hidden)]
     #[inline]
     pub fn update_instances(&mut self) -> Result<(), Box<dyn Error>> {
-        let grid = self.grid.lock().map_err(|e| format!("{e}"))?;
+        let selection = self.editor.selection().copied();
+        let ghost = self.ghost_indices();
+        let columns = self.layout.columns();
+        let frame = self.frames.latest();
+        let display_mode = self.display_mode;
+        let theme = &self.themes[0];
         let op = |(idx, inst): (usize, &mut Instance)| {
-            if let Some(cell) = grid.get_idx(&GridIdx(idx)) {
-                let colour = if cell.alive() { COLOURED } else { WHITE };
-                inst.colour = colour;
+            let grid_idx = GridIdx(idx);
+            let mut colour =
+                display_mode.colour(theme, frame.alive(&grid_idx), frame.age(&grid_idx));
+            let coord = Coord {
+                i: idx / columns,
+                j: idx % columns,
+            };
+            if selection.is_some_and(|region| region.contains(&coord)) {
+                colour = display::lerp(colour, theme.text, SELECTION_BLEND);
+            }
+            if ghost.binary_search(&idx).is_ok() {
+                colour = display::lerp(colour, theme.text, GHOST_BLEND);
             }
+            inst.colour = colour;
         };
-        if grid.area_requires_bool() {
+        if self.area_requires_par {
             self.instances.par_iter_mut().enumerate().for_each(op);
         } else {
             for (idx, inst) in self.instances.iter_mut().enumerate() {
@@ -232,50 +683,356 @@ impl App {
         Ok(())
     }
 
+    // Flattened indices of the cells the pattern waiting to be pasted would cover, in order
+    fn ghost_indices(&self) -> Vec<usize> {
+        let (rows, columns) = (self.layout.rows(), self.layout.columns());
+        let mut ghost: Vec<usize> = self
+            .editor
+            .ghost()
+            .into_iter()
+            .filter_map(|cell| self.topology.wrap(cell, rows, columns))
+            .map(|Coord { i, j }| i * columns + j)
+            .collect();
+        ghost.sort_unstable();
+        ghost
+    }
+
+    // A point in the window in normalised device coordinates
+    #[allow(clippy::cast_possible_truncation)]
+    fn point_at(&self, position: LogicalPosition) -> [f32; 2] {
+        let position = position.to_physical(self.window.window().get_hidpi_factor());
+        [
+            (position.x * 2. / f64::from(self.window_pixels[0]) - 1.) as f32,
+            (1. - position.y * 2. / f64::from(self.window_pixels[1])) as f32,
+        ]
+    }
+
+    // Puts the live cells in the selection on the clipboard as RLE, so they can be pasted
+    // into other programs, returning how many there were
+    fn copy(&mut self) -> Result<usize, Box<dyn Error>> {
+        let columns = self.layout.columns();
+        let frame = self.frames.latest();
+        let pattern = self
+            .editor
+            .copy(|&Coord { i, j }| frame.alive(&GridIdx(i * columns + j)))
+            .ok_or("Nothing is selected")?;
+        let rle = rle::encode(&pattern, self.rule);
+        self.clipboard()?
+            .set_contents(rle)
+            .map_err(|err| err.to_string())?;
+        Ok(pattern.population())
+    }
+
+    // Starts pasting the RLE pattern on the clipboard
+    fn paste(&mut self) -> Result<(), Box<dyn Error>> {
+        let text = self
+            .clipboard()?
+            .get_contents()
+            .map_err(|err| err.to_string())?;
+        let pattern = rle::decode(&text)?;
+        self.editor.start_pasting(pattern);
+        Ok(())
+    }
+
+    fn clipboard(&mut self) -> Result<&mut ClipboardContext, Box<dyn Error>> {
+        let clipboard = match self.clipboard.take() {
+            Some(clipboard) => clipboard,
+            None => ClipboardContext::new().map_err(|err| err.to_string())?,
+        };
+        Ok(self.clipboard.insert(clipboard))
+    }
+
+    /// Runs the simulation on its own thread and renders it until the window is closed.
+    ///
+    /// The simulation is stopped before this returns, and any error it ran into is
+    /// returned from here.
     #[allow(clippy::missing_errors_doc)]
     pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        // Do updates to the grid in another thread.
-        {
-            let grid = self.grid.clone();
-            let updates_per_second = self.updates_per_second;
-            thread::spawn(move || async_update_loop(&grid, updates_per_second));
+        let source = self.source.take().ok_or("App is already running")?;
+        let handle = source.spawn()?;
+        let rendered = self.render_loop(&handle);
+        if self.exports.recording.is_some() {
+            self.toggle_recording();
         }
+        let stopped = handle.stop();
+        rendered.and(stopped)
+    }
 
+    fn render_loop(&mut self, simulation: &SimulationHandle) -> Result<(), Box<dyn Error>> {
         let mut running = true;
         while running {
-            // fetch events
-            let currently_uploading = self.uploading;
+            let mut actions = Vec::new();
             self.events_loop.poll_events(|polled_event| {
                 if let glutin::Event::WindowEvent { event, .. } = polled_event {
-                    match event {
-                        glutin::WindowEvent::KeyboardInput {
-                            input:
-                                glutin::KeyboardInput {
-                                    virtual_keycode: Some(glutin::VirtualKeyCode::Escape),
-                                    ..
-                                },
-                            ..
-                        }
-                        | glutin::WindowEvent::CloseRequested => running = false,
-                        glutin::WindowEvent::Resized(_) => running = currently_uploading,
-                        _ => {}
-                    }
+                    actions.extend(Action::for_event(&event));
                 }
             });
+            for action in actions {
+                running &= self.handle(action, simulation);
+            }
             self.render()?;
+            self.record_frame();
+            // No point rendering a board that has stopped updating; stopping the
+            // simulation will tell us what went wrong.
+            if simulation.is_finished() {
+                running = false;
+            }
         }
         Ok(())
     }
+
+    // Carries out an action, returning false if it means we should stop running
+    fn handle(&mut self, action: Action, simulation: &SimulationHandle) -> bool {
+        let edit = |edit: Option<Edit>| {
+            if let Some(edit) = edit {
+                simulation.edit(edit);
+            }
+        };
+        match action {
+            Action::ToggleHud => self.hud.toggle(),
+            Action::NextDisplayMode => self.display_mode = self.display_mode.next(),
+            Action::NextTheme => self.themes.rotate_left(1),
+            // Failing to export isn't worth stopping the simulation over
+            Action::Screenshot => match self.save_screenshot() {
+                Ok(path) => println!("Saved screenshot to {}", path.display()),
+                Err(err) => eprintln!("Could not save screenshot: {err}"),
+            },
+            Action::ToggleRecording => self.toggle_recording(),
+            Action::MoveCursor(position) => {
+                self.cursor = position.map(|position| self.point_at(position));
+                let (cursor, pixel) = (self.cursor, self.hud_pixel);
+                let scrubbed = self.scrubber.as_ref().zip(cursor);
+                if let Some(generation) =
+                    scrubbed.and_then(|(scrubber, cursor)| scrubber.drag(cursor, pixel))
+                {
+                    simulation.send(Command::Seek(generation));
+                } else {
+                    let cell = cursor.and_then(|cursor| self.layout.cell_at(cursor));
+                    self.editor.move_cursor(cell.map(|(i, j)| Coord { i, j }));
+                }
+            }
+            Action::Press => {
+                let (cursor, pixel) = (self.cursor, self.hud_pixel);
+                let scrubbed = self.scrubber.as_mut().zip(cursor);
+                match scrubbed.and_then(|(scrubber, cursor)| scrubber.press(cursor, pixel)) {
+                    Some(generation) => simulation.send(Command::Seek(generation)),
+                    None => edit(self.editor.press()),
+                }
+            }
+            Action::Release => {
+                if !self.scrubber.as_mut().is_some_and(Scrubber::release) {
+                    self.editor.release();
+                }
+            }
+            Action::Copy => match self.copy() {
+                Ok(cells) => println!("Copied {cells} live cells"),
+                Err(err) => eprintln!("Could not copy: {err}"),
+            },
+            Action::Cut => match self.copy() {
+                Ok(cells) => {
+                    println!("Cut {cells} live cells");
+                    edit(self.editor.clear());
+                }
+                Err(err) => eprintln!("Could not cut: {err}"),
+            },
+            Action::Paste => {
+                if let Err(err) = self.paste() {
+                    eprintln!("Could not paste: {err}");
+                }
+            }
+            Action::Clear => edit(self.editor.clear()),
+            Action::Randomise => edit(self.editor.randomise()),
+            Action::Transform(transform) => edit(self.editor.transform(transform)),
+            Action::Undo => simulation.send(Command::Undo),
+            Action::Redo => simulation.send(Command::Redo),
+            Action::StepBack => simulation.send(Command::StepBack),
+            Action::StepForward => simulation.send(Command::StepForward),
+            Action::TogglePause => simulation.send(Command::TogglePause),
+            Action::TogglePalette => self.palette.toggle(),
+            Action::Step(by) if self.palette.is_open() => self.palette.step(by),
+            Action::StepCategory(by) if self.palette.is_open() => self.palette.step_category(by),
+            Action::Pick if self.palette.is_open() => {
+                self.editor.start_pasting(self.palette.selected().pattern());
+                self.palette.close();
+            }
+            Action::Step(_) | Action::StepCategory(_) | Action::Pick => {}
+            Action::Cancel if self.palette.is_open() => self.palette.close(),
+            // Escape only quits once there's nothing left to cancel
+            Action::Cancel => return self.editor.cancel(),
+            Action::Resize => return self.uploading,
+            Action::Quit => return false,
+        }
+        true
+    }
+}
+
+// Vertical then horizontal gridline buffers. Gridlines are quads a single pixel thick,
+// stretched across the whole window.
+fn gridline_data(
+    factory: &mut gfx_device_gl::Factory,
+    data: &pipe::Data<Resources>,
+    pixel: [f32; 2],
+    capacity: usize,
+) -> Result<[pipe::Data<Resources>; 2], Box<dyn Error>> {
+    let mut create = |scale| -> Result<_, Box<dyn Error>> {
+        Ok(pipe::Data {
+            instance: factory.create_buffer(
+                capacity,
+                gfx::buffer::Role::Vertex,
+                gfx::memory::Usage::Dynamic,
+                gfx::memory::Bind::TRANSFER_DST,
+            )?,
+            scale,
+            ..data.clone()
+        })
+    };
+    Ok([
+        create([[pixel[0], 0.], [0., 2.]])?,
+        create([[2., 0.], [0., pixel[1]]])?,
+    ])
+}
+
+// The HUD is drawn with the same pipeline, just with its own instances, and quads sized
+// to a single font pixel.
+fn hud_data(
+    factory: &mut gfx_device_gl::Factory,
+    data: &pipe::Data<Resources>,
+    hud_pixel: [f32; 2],
+) -> Result<pipe::Data<Resources>, Box<dyn Error>> {
+    Ok(pipe::Data {
+        instance: factory.create_buffer(
+            HUD_MAX_INSTANCES,
+            gfx::buffer::Role::Vertex,
+            gfx::memory::Usage::Dynamic,
+            gfx::memory::Bind::TRANSFER_DST,
+        )?,
+        scale: [[hud_pixel[0], 0.], [0., hud_pixel[1]]],
+        ..data.clone()
+    })
 }
 
-// Only used so we can use the ? macro...
-fn async_update_loop(grid: &Arc<Mutex<Grid>>, updates_per_second: u16) -> Result<(), String> {
-    let wait_duration = Duration::from_millis(1000 / u64::from(updates_per_second));
-    let mut last_updated = Instant::now();
-    loop {
-        if last_updated.elapsed() > wait_duration {
-            grid.lock().map_err(|e| format!("{e}"))?.advance();
-            last_updated = Instant::now();
+// Things the user can ask for while the app is running
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum Action {
+    ToggleHud,
+    NextDisplayMode,
+    NextTheme,
+    Screenshot,
+    ToggleRecording,
+    // The cursor moved to somewhere in the window, or left it
+    MoveCursor(Option<LogicalPosition>),
+    // The left mouse button went down or up
+    Press,
+    Release,
+    Copy,
+    Cut,
+    Paste,
+    Clear,
+    Randomise,
+    // Rotates or reflects whatever's being edited, going by how it looks on screen
+    Transform(Transform),
+    Undo,
+    Redo,
+    StepBack,
+    StepForward,
+    TogglePause,
+    TogglePalette,
+    // Moves through the palette's patterns, or its categories
+    Step(isize),
+    StepCategory(isize),
+    // Picks the pattern highlighted in the palette to paste
+    Pick,
+    Cancel,
+    Resize,
+    Quit,
+}
+
+impl Action {
+    fn for_event(event: &glutin::WindowEvent) -> Option<Self> {
+        use glutin::VirtualKeyCode as Key;
+        match event {
+            glutin::WindowEvent::KeyboardInput {
+                input:
+                    glutin::KeyboardInput {
+                        state: glutin::ElementState::Pressed,
+                        virtual_keycode: Some(key),
+                        modifiers,
+                        ..
+                    },
+                ..
+            } => {
+                // Command on a Mac
+                let shortcut = modifiers.ctrl || modifiers.logo;
+                match key {
+                    Key::C if shortcut => Some(Self::Copy),
+                    Key::X if shortcut => Some(Self::Cut),
+                    Key::V if shortcut => Some(Self::Paste),
+                    Key::Z if shortcut && modifiers.shift => Some(Self::Redo),
+                    Key::Z if shortcut => Some(Self::Undo),
+                    Key::Y if shortcut => Some(Self::Redo),
+                    Key::H => Some(Self::ToggleHud),
+                    Key::M => Some(Self::NextDisplayMode),
+                    Key::T => Some(Self::NextTheme),
+                    Key::P => Some(Self::Screenshot),
+                    Key::R => Some(Self::ToggleRecording),
+                    Key::Delete | Key::Back => Some(Self::Clear),
+                    Key::N => Some(Self::Randomise),
+                    Key::Period => Some(Self::Transform(Transform::Rotate90)),
+                    Key::Comma => Some(Self::Transform(Transform::Rotate270)),
+                    Key::X => Some(Self::Transform(Transform::FlipColumns)),
+                    Key::Y => Some(Self::Transform(Transform::FlipRows)),
+                    Key::LBracket => Some(Self::StepBack),
+                    Key::RBracket => Some(Self::StepForward),
+                    Key::Space => Some(Self::TogglePause),
+                    Key::L => Some(Self::TogglePalette),
+                    Key::Up => Some(Self::Step(-1)),
+                    Key::Down => Some(Self::Step(1)),
+                    Key::Left => Some(Self::StepCategory(-1)),
+                    Key::Right => Some(Self::StepCategory(1)),
+                    Key::Return => Some(Self::Pick),
+                    Key::Escape => Some(Self::Cancel),
+                    _ => None,
+                }
+            }
+            glutin::WindowEvent::CursorMoved { position, .. } => {
+                Some(Self::MoveCursor(Some(*position)))
+            }
+            glutin::WindowEvent::CursorLeft { .. } => Some(Self::MoveCursor(None)),
+            glutin::WindowEvent::MouseInput {
+                state,
+                button: glutin::MouseButton::Left,
+                ..
+            } => Some(if *state == glutin::ElementState::Pressed {
+                Self::Press
+            } else {
+                Self::Release
+            }),
+            glutin::WindowEvent::CloseRequested => Some(Self::Quit),
+            glutin::WindowEvent::Resized(_) => Some(Self::Resize),
+            _ => None,
         }
     }
 }
+
+// Size of the window's drawable area in physical pixels
+#[allow(clippy::cast_possible_truncation)]
+fn window_pixel_size(
+    window: &glutin::WindowedContext,
+    window_width: u32,
+    window_height: u32,
+) -> [f32; 2] {
+    let window = window.window();
+    let size = window
+        .get_inner_size()
+        .unwrap_or_else(|| LogicalSize::new(window_width.into(), window_height.into()))
+        .to_physical(window.get_hidpi_factor());
+    [size.width.max(1.) as f32, size.height.max(1.) as f32]
+}
+
+// Works out how big a font pixel should be in normalised device coordinates, so that
+// text stays the same size on screen regardless of the window or board dimensions.
+#[allow(clippy::cast_possible_truncation)]
+fn hud_pixel_size(window: &glutin::WindowedContext, screen_pixel: [f32; 2]) -> [f32; 2] {
+    let font_pixel = (HUD_FONT_SCALE * window.window().get_hidpi_factor()) as f32;
+    [screen_pixel[0] * font_pixel, screen_pixel[1] * font_pixel]
+}
@@ -0,0 +1,121 @@
+use crate::library::{Category, Entry, PATTERNS};
+
+/// A menu of the patterns in the library, a category at a time, for picking one to paste
+#[derive(Debug, Default)]
+pub struct Palette {
+    open: bool,
+    // Index into the library of the highlighted pattern
+    selected: usize,
+}
+
+impl Palette {
+    pub const fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    /// Highlights the pattern before or after the current one, staying in the same category
+    /// and wrapping around at its ends
+    pub fn step(&mut self, by: isize) {
+        let category = self.selected().category;
+        let entries: Vec<usize> = (0..PATTERNS.len())
+            .filter(|&n| PATTERNS[n].category == category)
+            .collect();
+        let position = entries
+            .iter()
+            .position(|&n| n == self.selected)
+            .unwrap_or(0);
+        self.selected = entries[wrapping(position, by, entries.len())];
+    }
+
+    /// Moves on to the first pattern of the category before or after the current one
+    pub fn step_category(&mut self, by: isize) {
+        let category = self.selected().category;
+        let position = Category::ALL
+            .iter()
+            .position(|&c| c == category)
+            .unwrap_or(0);
+        let category = Category::ALL[wrapping(position, by, Category::ALL.len())];
+        self.selected = PATTERNS
+            .iter()
+            .position(|entry| entry.category == category)
+            .unwrap_or(0);
+    }
+
+    pub fn selected(&self) -> &'static Entry {
+        &PATTERNS[self.selected]
+    }
+
+    /// Lines of text to display: the category, then its patterns with the highlighted one
+    /// marked
+    pub fn lines(&self) -> Vec<String> {
+        let category = self.selected().category;
+        let mut lines = vec![format!("< {category} >"), String::new()];
+        lines.extend(
+            PATTERNS
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| entry.category == category)
+                .map(|(n, entry)| {
+                    let marker = if n == self.selected { ">" } else { " " };
+                    format!("{marker} {}", entry.title())
+                }),
+        );
+        lines
+    }
+}
+
+// Moves an index into a list of the given length, wrapping around at either end
+fn wrapping(index: usize, by: isize, len: usize) -> usize {
+    let len = isize::try_from(len).unwrap_or(isize::MAX);
+    let index = isize::try_from(index).unwrap_or(0);
+    usize::try_from((index + by).rem_euclid(len)).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step() {
+        let mut palette = Palette::default();
+        assert!(!palette.is_open());
+        palette.toggle();
+        assert!(palette.is_open());
+        assert_eq!(palette.selected().name, "block");
+        palette.step(-1);
+        assert_eq!(palette.selected().name, "eater-1");
+        palette.step(2);
+        assert_eq!(palette.selected().name, "beehive");
+        palette.step_category(-1);
+        assert_eq!(palette.selected().name, "r-pentomino");
+        palette.step_category(1);
+        palette.step_category(1);
+        assert_eq!(palette.selected().name, "blinker");
+        palette.close();
+        assert!(!palette.is_open());
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut palette = Palette::default();
+        palette.step_category(3);
+        palette.step(1);
+        assert_eq!(
+            palette.lines(),
+            vec![
+                "< guns >".to_string(),
+                String::new(),
+                "  Gosper glider gun".to_string(),
+                "> Simkin glider gun".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use super::Instance;
+
+// Gap between the bar and the edges of the window, in font pixels
+const MARGIN: f32 = 3.;
+// Width and height of the handle, in font pixels
+const HANDLE: [usize; 2] = [3, 7];
+// How far above or below the bar it can still be grabbed, in font pixels
+const REACH: f32 = 6.;
+
+/// A bar along the bottom of the window for scrubbing through the generations of a
+/// replay, with a handle at the one being shown. Sizes are in font pixels, like the HUD.
+#[derive(Debug)]
+pub struct Scrubber {
+    first: u64,
+    last: u64,
+    dragging: bool,
+}
+
+impl Scrubber {
+    /// Covers the generations from `first` to `last`
+    pub const fn new(first: u64, last: u64) -> Self {
+        Self {
+            first,
+            last,
+            dragging: false,
+        }
+    }
+
+    /// Starts dragging the handle if a point is on the bar, returning the generation
+    /// under it. `pixel` is the size of a font pixel.
+    pub fn press(&mut self, point: [f32; 2], pixel: [f32; 2]) -> Option<u64> {
+        let (left, right, y) = bar(pixel);
+        if (point[1] - y).abs() > REACH * pixel[1] || point[0] < left || point[0] > right {
+            return None;
+        }
+        self.dragging = true;
+        self.drag(point, pixel)
+    }
+
+    /// The generation under a point while the handle is being dragged, which carries on
+    /// past the ends of the bar
+    pub fn drag(&self, point: [f32; 2], pixel: [f32; 2]) -> Option<u64> {
+        let (left, right, _) = bar(pixel);
+        self.dragging
+            .then(|| self.generation_at((point[0] - left) / (right - left)))
+    }
+
+    /// Lets go of the handle, returning whether it was being dragged
+    pub fn release(&mut self) -> bool {
+        std::mem::take(&mut self.dragging)
+    }
+
+    /// Instances drawing the bar, in `played` up to the handle at the given generation and
+    /// `unplayed` after it
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub fn instances(
+        &self,
+        generation: u64,
+        pixel: [f32; 2],
+        played: [f32; 4],
+        unplayed: [f32; 4],
+    ) -> Vec<Instance> {
+        let (left, right, y) = bar(pixel);
+        let handle = left + self.fraction(generation) * (right - left);
+        let length = ((right - left) / pixel[0]).round() as usize;
+        let mut instances: Vec<Instance> = (0..length)
+            .map(|n| left + (n as f32 + 0.5) * pixel[0])
+            .map(|x| Instance {
+                translate: [x, y],
+                colour: if x <= handle { played } else { unplayed },
+            })
+            .collect();
+        for column in 0..HANDLE[0] {
+            for row in 0..HANDLE[1] {
+                let offset = |n: usize, size: usize| n as f32 - (size - 1) as f32 / 2.;
+                instances.push(Instance {
+                    translate: [
+                        handle + offset(column, HANDLE[0]) * pixel[0],
+                        y + offset(row, HANDLE[1]) * pixel[1],
+                    ],
+                    colour: played,
+                });
+            }
+        }
+        instances
+    }
+
+    // Where a generation is along the bar, from 0 at the left to 1 at the right
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn fraction(&self, generation: u64) -> f32 {
+        if self.last <= self.first {
+            return 1.;
+        }
+        let along = generation.clamp(self.first, self.last) - self.first;
+        (along as f64 / (self.last - self.first) as f64) as f32
+    }
+
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn generation_at(&self, fraction: f32) -> u64 {
+        let span = (self.last - self.first) as f64;
+        self.first + (f64::from(fraction.clamp(0., 1.)) * span).round() as u64
+    }
+}
+
+// Left and right ends of the bar, and how far up it is, in normalised device coordinates
+#[allow(clippy::cast_precision_loss)]
+fn bar(pixel: [f32; 2]) -> (f32, f32, f32) {
+    let half_handle = HANDLE[1] as f32 / 2.;
+    (
+        -1. + MARGIN * pixel[0],
+        1. - MARGIN * pixel[0],
+        -1. + (MARGIN + half_handle) * pixel[1],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIXEL: [f32; 2] = [0.01, 0.02];
+
+    #[test]
+    fn test_dragging() {
+        let mut scrubber = Scrubber::new(100, 200);
+        let (left, right, y) = bar(PIXEL);
+        let middle = (left + right) / 2.;
+        assert_eq!(scrubber.drag([middle, y], PIXEL), None);
+        // Too far above the bar to grab it
+        assert_eq!(scrubber.press([middle, 0.], PIXEL), None);
+        assert!(!scrubber.release());
+        assert_eq!(scrubber.press([middle, y + 0.1], PIXEL), Some(150));
+        assert_eq!(scrubber.drag([left, 0.5], PIXEL), Some(100));
+        assert_eq!(scrubber.drag([1., 0.5], PIXEL), Some(200));
+        assert!(scrubber.release());
+        assert_eq!(scrubber.drag([left, y], PIXEL), None);
+    }
+
+    #[test]
+    fn test_instances() {
+        let scrubber = Scrubber::new(0, 10);
+        let (played, unplayed) = ([1.; 4], [0.; 4]);
+        let instances = scrubber.instances(5, PIXEL, played, unplayed);
+        let track = instances.len() - HANDLE[0] * HANDLE[1];
+        assert_eq!(track, 194);
+        let played_track = instances[..track]
+            .iter()
+            .filter(|inst| inst.colour == played)
+            .count();
+        assert_eq!(played_track, track / 2);
+        assert!(instances[track..].iter().all(|inst| inst.colour == played));
+        // A log with a single generation in it is all played
+        let single = Scrubber::new(3, 3).instances(3, PIXEL, played, unplayed);
+        assert!(single.iter().all(|inst| inst.colour == played));
+    }
+}
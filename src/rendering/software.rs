@@ -0,0 +1,365 @@
+use super::display::DisplayMode;
+use super::layout::{Layout, Spacing};
+use super::theme::Theme;
+use super::{gridline_instances, Instance};
+use crate::data::{Ages, Grid, GridIdx};
+use crate::simulation::Frame;
+use std::ops::Range;
+
+/// An RGBA image with 8 bits per channel, stored row by row from the top left
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates an image filled with a single colour
+    pub fn new(width: u32, height: u32, colour: [u8; 4]) -> Self {
+        let area = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            pixels: colour.repeat(area),
+        }
+    }
+
+    /// Wraps raw RGBA bytes laid out like `as_raw`, returning `None` if there aren't
+    /// exactly enough of them for the given size
+    pub fn from_raw(width: u32, height: u32, pixels: Vec<u8>) -> Option<Self> {
+        let expected = (width as usize * height as usize).checked_mul(4)?;
+        (pixels.len() == expected).then_some(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Colour of the pixel in column `x` and row `y`, counting from the top left
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let start = (y as usize * self.width as usize + x as usize) * 4;
+        let mut colour = [0; 4];
+        colour.copy_from_slice(&self.pixels[start..start + 4]);
+        Some(colour)
+    }
+
+    /// Raw RGBA bytes, row by row from the top left
+    pub fn as_raw(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn as_raw_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    pub fn into_raw(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    // Fills the pixels whose centres fall inside a rectangle given in normalised device
+    // coordinates, the same way OpenGL decides which pixels a quad covers
+    fn fill(&mut self, centre: [f32; 2], size: [f32; 2], colour: [u8; 4]) {
+        let to_x = |x: f32| f32::midpoint(x, 1.) * self.width as f32;
+        let to_y = |y: f32| (1. - y) / 2. * self.height as f32;
+        let columns = covered(
+            to_x(centre[0] - size[0] / 2.),
+            to_x(centre[0] + size[0] / 2.),
+            self.width,
+        );
+        let rows = covered(
+            to_y(centre[1] + size[1] / 2.),
+            to_y(centre[1] - size[1] / 2.),
+            self.height,
+        );
+        let stride = self.width as usize * 4;
+        for row in rows {
+            let line = &mut self.pixels[row * stride..(row + 1) * stride];
+            for pixel in line[columns.start * 4..columns.end * 4].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&colour);
+            }
+        }
+    }
+
+    // Draws quads the way the instancing pipeline does, each one the unit quad scaled by
+    // `scale` and moved to its instance's position
+    fn draw(&mut self, instances: &[Instance], scale: [[f32; 2]; 2]) {
+        let size = [scale[0][0], scale[1][1]];
+        for instance in instances {
+            self.fill(instance.translate, size, to_rgba8(instance.colour));
+        }
+    }
+}
+
+// Pixels whose centres lie within [start, end), clamped to the image
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn covered(start: f32, end: f32, limit: u32) -> Range<usize> {
+    let limit = limit as f32;
+    let first = (start - 0.5).ceil().clamp(0., limit) as usize;
+    let last = (end - 0.5).ceil().clamp(0., limit) as usize;
+    first..last.max(first)
+}
+
+/// Converts a colour with components between 0 and 1 to 8 bits per channel
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn to_rgba8(colour: [f32; 4]) -> [u8; 4] {
+    colour.map(|c| (c.clamp(0., 1.) * 255.).round() as u8)
+}
+
+/// Draws boards into images on the CPU, without needing a window or an OpenGL context.
+///
+/// Cells, gaps and gridlines are laid out and coloured exactly as they are on screen, so
+/// the images match what the windowed renderer would show at the same size.
+#[derive(Debug, Clone)]
+pub struct SoftwareRenderer {
+    width: u32,
+    height: u32,
+    layout: Layout,
+    display_mode: DisplayMode,
+    theme: Theme,
+}
+
+impl SoftwareRenderer {
+    /// Renders boards with the given number of columns and rows into images of the given
+    /// size in pixels
+    pub fn new(columns: usize, rows: usize, width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            layout: Layout::new(columns, rows, pixels(width, height), Spacing::default()),
+            display_mode: DisplayMode::Classic,
+            theme: Theme::default(),
+        }
+    }
+
+    /// Separates cells with the given spacing, rather than the default gaps
+    #[must_use]
+    pub fn with_spacing(mut self, spacing: Spacing) -> Self {
+        let (columns, rows) = (self.layout.columns(), self.layout.rows());
+        self.layout = Layout::new(columns, rows, pixels(self.width, self.height), spacing);
+        self
+    }
+
+    /// Colours cells with the given mode, rather than the classic one
+    #[must_use]
+    pub const fn with_display_mode(mut self, display_mode: DisplayMode) -> Self {
+        self.display_mode = display_mode;
+        self
+    }
+
+    /// Uses the given theme rather than the default one
+    #[must_use]
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub const fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Draws a frame captured from the simulation
+    pub fn render(&self, frame: &Frame) -> Image {
+        let mut image = Image::new(self.width, self.height, to_rgba8(self.theme.background));
+        let columns = self.layout.columns();
+        let cells: Vec<Instance> = (0..columns * self.layout.rows())
+            .map(|idx| {
+                let grid_idx = GridIdx(idx);
+                Instance {
+                    translate: self.layout.centre(idx / columns, idx % columns),
+                    colour: self.display_mode.colour(
+                        &self.theme,
+                        frame.alive(&grid_idx),
+                        frame.age(&grid_idx),
+                    ),
+                }
+            })
+            .collect();
+        image.draw(&cells, self.layout.scale());
+        if let Some((vertical, horizontal)) = self.layout.gridlines() {
+            let pixel = self.layout.pixel_size();
+            image.draw(
+                &gridline_instances(&vertical, true, &self.theme),
+                [[pixel[0], 0.], [0., 2.]],
+            );
+            image.draw(
+                &gridline_instances(&horizontal, false, &self.theme),
+                [[2., 0.], [0., pixel[1]]],
+            );
+        }
+        image
+    }
+
+    /// Draws a grid, with every live cell coloured as if it had just been born
+    pub fn render_grid(&self, grid: &Grid) -> Image {
+        self.render(&Frame::new(grid, &Ages::new(grid)))
+    }
+}
+
+fn pixels(width: u32, height: u32) -> [f32; 2] {
+    [width as f32, height as f32]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Coord, Status};
+    use crate::rendering::{display, MAJOR_GRIDLINE_BLEND};
+
+    // Draws an image as text, one character per pixel, so that renders can be compared
+    // against snapshots that are readable in the source
+    fn snapshot(image: &Image, theme: &Theme) -> String {
+        let key = [
+            ('.', theme.background),
+            ('#', theme.alive),
+            ('o', theme.dead),
+            (
+                '+',
+                display::lerp(theme.background, theme.text, MAJOR_GRIDLINE_BLEND),
+            ),
+        ];
+        let mut rows = Vec::new();
+        for y in 0..image.height() {
+            let row: String = (0..image.width())
+                .map(|x| {
+                    let pixel = image.pixel(x, y).unwrap();
+                    key.iter()
+                        .find(|(_, colour)| to_rgba8(*colour) == pixel)
+                        .map_or('?', |(c, _)| *c)
+                })
+                .collect();
+            rows.push(row);
+        }
+        rows.join("\n")
+    }
+
+    // A 3 x 2 grid with the bottom left and top right cells alive
+    fn corners() -> Grid {
+        Grid::from_fn(3, 2, |&Coord { i, j }| {
+            if (i, j) == (0, 0) || (i, j) == (1, 2) {
+                Status::Alive
+            } else {
+                Status::Dead
+            }
+        })
+    }
+
+    #[test]
+    fn test_image() {
+        let mut image = Image::new(3, 2, [1, 2, 3, 4]);
+        assert_eq!(image.as_raw().len(), 24);
+        assert_eq!(
+            Image::from_raw(3, 2, image.as_raw().to_vec()),
+            Some(image.clone())
+        );
+        assert_eq!(Image::from_raw(3, 3, image.as_raw().to_vec()), None);
+        assert_eq!(image.pixel(2, 1), Some([1, 2, 3, 4]));
+        assert_eq!(image.pixel(3, 0), None);
+        // The top left quarter of the image
+        image.fill([-0.5, 0.5], [1., 1.], [9; 4]);
+        assert_eq!(image.pixel(0, 0), Some([9; 4]));
+        assert_eq!(image.pixel(1, 0), Some([1, 2, 3, 4]));
+        assert_eq!(image.pixel(0, 1), Some([1, 2, 3, 4]));
+        // Off the edges
+        image.fill([2., 2.], [1., 1.], [9; 4]);
+        image.fill([0., 0.], [4., 4.], [7; 4]);
+        assert!(image.into_raw().iter().all(|&c| c == 7));
+    }
+
+    #[test]
+    fn test_to_rgba8() {
+        assert_eq!(to_rgba8([0., 0.5, 1., 2.]), [0, 128, 255, 255]);
+        assert_eq!(to_rgba8([-1., 0.2, 0.4, 1.]), [0, 51, 102, 255]);
+    }
+
+    #[test]
+    fn test_render_without_gaps() {
+        let theme = Theme::default();
+        let renderer = SoftwareRenderer::new(3, 2, 6, 4).with_spacing(Spacing::Gaps(0.));
+        let expected = "\
+oooo##
+oooo##
+##oooo
+##oooo";
+        assert_eq!(
+            snapshot(&renderer.render_grid(&corners()), &theme),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_render_with_gaps() {
+        let theme = Theme::default();
+        let renderer = SoftwareRenderer::new(3, 2, 18, 10).with_spacing(Spacing::Gaps(0.4));
+        let expected = "\
+..................
+..................
+..ooo..oooo..###..
+..ooo..oooo..###..
+..................
+..................
+..###..oooo..ooo..
+..###..oooo..ooo..
+..................
+..................";
+        assert_eq!(
+            snapshot(&renderer.render_grid(&corners()), &theme),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_render_with_gridlines() {
+        let theme = Theme::default();
+        let renderer = SoftwareRenderer::new(3, 2, 15, 10).with_spacing(Spacing::Gridlines(2));
+        // Major lines every 2 cells, minor ones in the background colour
+        let expected = "\
++++++++++++++++
++oooo.oooo+###.
++oooo.oooo+###.
++oooo.oooo+###.
+...............
++####.oooo+ooo.
++####.oooo+ooo.
++####.oooo+ooo.
++####.oooo+ooo.
++++++++++++++++";
+        assert_eq!(
+            snapshot(&renderer.render_grid(&corners()), &theme),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_render_display_mode_and_theme() {
+        let theme = Theme::named("dark").unwrap();
+        // A lone cell in the bottom left, which dies straight away
+        let mut grid = Grid::empty(4, 4);
+        grid.set(&Coord { i: 0, j: 0 }, Status::Alive);
+        let renderer = SoftwareRenderer::new(4, 4, 8, 8)
+            .with_spacing(Spacing::Gaps(0.))
+            .with_theme(theme.clone())
+            .with_display_mode(DisplayMode::Trails);
+        let mut ages = Ages::new(&grid);
+        grid.advance();
+        ages.update(&grid);
+        let image = renderer.render(&Frame::new(&grid, &ages));
+        let faded = to_rgba8(DisplayMode::Trails.colour(&theme, false, 1));
+        assert_ne!(faded, to_rgba8(theme.dead));
+        assert_eq!(image.pixel(0, 7), Some(faded));
+        assert_eq!(image.pixel(1, 6), Some(faded));
+        assert_eq!(image.pixel(2, 7), Some(to_rgba8(theme.dead)));
+        assert_eq!(image.pixel(7, 0), Some(to_rgba8(theme.dead)));
+    }
+}
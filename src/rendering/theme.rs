@@ -0,0 +1,292 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Colours used to draw the board
+#[derive(PartialEq, Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    /// Behind the cells, and in the gaps between them
+    pub background: [f32; 4],
+    pub dead: [f32; 4],
+    /// Live cells, and newly born ones when colouring by age
+    pub alive: [f32; 4],
+    /// What long-lived cells end up as when colouring by age
+    pub aged: [f32; 4],
+    /// HUD text
+    pub text: [f32; 4],
+    /// Drawn behind HUD text so it stands out from the board
+    pub shadow: [f32; 4],
+}
+
+/// Names of the built-in themes, in the order they get cycled through
+pub const THEME_NAMES: [&str; 6] = [
+    "classic",
+    "light",
+    "dark",
+    "high-contrast",
+    "okabe-ito",
+    "viridis",
+];
+
+/// Names of the colours in a theme that can be customised
+pub const COLOUR_NAMES: [&str; 6] = ["background", "dead", "alive", "aged", "text", "shadow"];
+
+const BLACK: [f32; 4] = [0., 0., 0., 1.];
+const WHITE: [f32; 4] = [1., 1., 1., 1.];
+
+impl Theme {
+    /// Looks up one of the built-in themes by name
+    pub fn named(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase();
+        let (background, dead, alive, aged, text, shadow) = match name.as_str() {
+            "classic" => (
+                [0.1, 0.2, 0.3, 1.],
+                WHITE,
+                [0.2, 0.4, 0.5, 1.],
+                [0.85, 0.45, 0.15, 1.],
+                WHITE,
+                BLACK,
+            ),
+            "light" => (
+                [0.8, 0.8, 0.82, 1.],
+                [0.98, 0.98, 0.98, 1.],
+                [0.15, 0.15, 0.2, 1.],
+                [0.6, 0.15, 0.15, 1.],
+                BLACK,
+                WHITE,
+            ),
+            "dark" => (
+                [0.02, 0.02, 0.03, 1.],
+                [0.12, 0.12, 0.14, 1.],
+                [0.85, 0.87, 0.9, 1.],
+                [0.95, 0.7, 0.3, 1.],
+                WHITE,
+                BLACK,
+            ),
+            "high-contrast" => (
+                [0.4, 0.4, 0.4, 1.],
+                BLACK,
+                WHITE,
+                [1., 1., 0., 1.],
+                WHITE,
+                BLACK,
+            ),
+            // Blue and orange from Okabe & Ito's palette, which stay
+            // distinguishable under the common forms of colour blindness
+            "okabe-ito" => (
+                rgb(0, 0, 0),
+                rgb(240, 240, 240),
+                rgb(0, 114, 178),
+                rgb(230, 159, 0),
+                WHITE,
+                BLACK,
+            ),
+            // Stops along the viridis colour map, which is perceptually uniform and
+            // increases in lightness, so the age ramp reads correctly in greyscale too
+            "viridis" => (
+                rgb(20, 20, 30),
+                rgb(68, 1, 84),
+                rgb(33, 145, 140),
+                rgb(253, 231, 37),
+                WHITE,
+                BLACK,
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            name,
+            background,
+            dead,
+            alive,
+            aged,
+            text,
+            shadow,
+        })
+    }
+
+    /// Sets one of the colours listed in `COLOUR_NAMES`, returning false if there is
+    /// no colour with that name
+    pub fn set_colour(&mut self, name: &str, colour: [f32; 4]) -> bool {
+        let target = match name {
+            "background" => &mut self.background,
+            "dead" => &mut self.dead,
+            "alive" => &mut self.alive,
+            "aged" => &mut self.aged,
+            "text" => &mut self.text,
+            "shadow" => &mut self.shadow,
+            _ => return false,
+        };
+        *target = colour;
+        true
+    }
+
+    /// All the built-in themes, in the order they get cycled through
+    pub fn all() -> Vec<Self> {
+        THEME_NAMES
+            .iter()
+            .filter_map(|name| Self::named(name))
+            .collect()
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::named(THEME_NAMES[0]).expect("classic is a built-in theme")
+    }
+}
+
+/// Returned when a theme name isn't recognised
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseThemeError(String);
+
+impl fmt::Display for ParseThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown theme [{}], expected one of {}",
+            self.0,
+            THEME_NAMES.join(", ")
+        )
+    }
+}
+
+impl Error for ParseThemeError {}
+
+impl FromStr for Theme {
+    type Err = ParseThemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::named(s).ok_or_else(|| ParseThemeError(s.to_string()))
+    }
+}
+
+#[allow(clippy::cast_lossless)]
+const fn rgb(r: u8, g: u8, b: u8) -> [f32; 4] {
+    [r as f32 / 255., g as f32 / 255., b as f32 / 255., 1.]
+}
+
+/// Returned when a colour can't be parsed
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ParseColourError(String);
+
+impl fmt::Display for ParseColourError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid colour [{}], expected #RRGGBB, #RRGGBBAA, or 3 to 4 comma separated numbers between 0 and 1",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseColourError {}
+
+/// Parses an RGBA colour, written either as hex (`#336699` or `#336699ff`) or as comma
+/// separated components between 0 and 1 (`0.2, 0.4, 0.6` or `0.2, 0.4, 0.6, 1`). Alpha
+/// defaults to fully opaque.
+#[allow(clippy::missing_errors_doc)]
+pub fn parse_colour(input: &str) -> Result<[f32; 4], ParseColourError> {
+    let err = || ParseColourError(input.to_string());
+    let trimmed = input.trim();
+    let components: Vec<f32> = if let Some(hex) = trimmed.strip_prefix('#') {
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+            return Err(err());
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                u8::from_str_radix(&hex[i..i + 2], 16)
+                    .map(|c| f32::from(c) / 255.)
+                    .map_err(|_| err())
+            })
+            .collect::<Result<_, _>>()?
+    } else {
+        trimmed
+            .split(',')
+            .map(|c| match c.trim().parse::<f32>() {
+                Ok(c) if (0. ..=1.).contains(&c) => Ok(c),
+                _ => Err(err()),
+            })
+            .collect::<Result<_, _>>()?
+    };
+    match components[..] {
+        [r, g, b] => Ok([r, g, b, 1.]),
+        [r, g, b, a] => Ok([r, g, b, a]),
+        _ => Err(err()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named() {
+        for name in THEME_NAMES {
+            let theme = Theme::named(name).unwrap();
+            assert_eq!(theme.name, name);
+            for colour in [
+                theme.background,
+                theme.dead,
+                theme.alive,
+                theme.aged,
+                theme.text,
+                theme.shadow,
+            ] {
+                assert!(colour.iter().all(|c| (0. ..=1.).contains(c)));
+            }
+            assert_ne!(theme.dead, theme.alive);
+        }
+        assert_eq!(Theme::named(" Dark ").unwrap().name, "dark");
+        assert_eq!(Theme::named("sepia"), None);
+        assert_eq!(Theme::all().len(), THEME_NAMES.len());
+        assert_eq!(Theme::default().name, "classic");
+    }
+
+    #[test]
+    fn test_set_colour() {
+        let mut theme = Theme::default();
+        for name in COLOUR_NAMES {
+            assert!(theme.set_colour(name, [0.5; 4]));
+        }
+        assert_eq!(theme.background, [0.5; 4]);
+        assert_eq!(theme.shadow, [0.5; 4]);
+        assert!(!theme.set_colour("border", [0.5; 4]));
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("viridis".parse::<Theme>().unwrap().name, "viridis");
+        assert!("sepia".parse::<Theme>().is_err());
+    }
+
+    #[test]
+    fn test_parse_colour() {
+        assert_eq!(parse_colour("#ff0000"), Ok([1., 0., 0., 1.]));
+        assert_eq!(parse_colour(" #00FF0000 "), Ok([0., 1., 0., 0.]));
+        assert_eq!(parse_colour("0, 0.5, 1"), Ok([0., 0.5, 1., 1.]));
+        assert_eq!(parse_colour("0,0.5,1,0.25"), Ok([0., 0.5, 1., 0.25]));
+    }
+
+    #[test]
+    fn test_parse_colour_invalid() {
+        for invalid in [
+            "",
+            "#ff00",
+            "#gg0000",
+            "#ff00000",
+            "#ffé000",
+            "1, 1",
+            "1, 1, 1, 1, 1",
+            "1.5, 0, 0",
+            "-0.1, 0, 0",
+            "red",
+        ] {
+            assert!(
+                parse_colour(invalid).is_err(),
+                "{invalid} should be invalid"
+            );
+        }
+    }
+}
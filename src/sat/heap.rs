@@ -0,0 +1,72 @@
+// Variables ordered by activity, most active first, for picking what to decide next
+#[derive(Debug, Clone, Default)]
+pub struct Heap {
+    vars: Vec<usize>,
+    // Where each variable is in vars, by variable
+    positions: Vec<Option<usize>>,
+}
+
+impl Heap {
+    pub fn insert(&mut self, var: usize, activity: &[f64]) {
+        if self.positions.len() <= var {
+            self.positions.resize(var + 1, None);
+        }
+        if self.positions[var].is_some() {
+            return;
+        }
+        self.positions[var] = Some(self.vars.len());
+        self.vars.push(var);
+        self.up(self.vars.len() - 1, activity);
+    }
+
+    pub fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        let last = self.vars.len().checked_sub(1)?;
+        self.swap(0, last);
+        let var = self.vars.pop()?;
+        self.positions[var] = None;
+        self.down(0, activity);
+        Some(var)
+    }
+
+    // Moves a variable forward after its activity has gone up
+    pub fn increased(&mut self, var: usize, activity: &[f64]) {
+        if let Some(Some(position)) = self.positions.get(var) {
+            self.up(*position, activity);
+        }
+    }
+
+    fn up(&mut self, mut position: usize, activity: &[f64]) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if activity[self.vars[parent]] >= activity[self.vars[position]] {
+                break;
+            }
+            self.swap(parent, position);
+            position = parent;
+        }
+    }
+
+    fn down(&mut self, mut position: usize, activity: &[f64]) {
+        loop {
+            let mut largest = position;
+            for child in [position * 2 + 1, position * 2 + 2] {
+                if child < self.vars.len()
+                    && activity[self.vars[child]] > activity[self.vars[largest]]
+                {
+                    largest = child;
+                }
+            }
+            if largest == position {
+                break;
+            }
+            self.swap(largest, position);
+            position = largest;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.vars.swap(a, b);
+        self.positions[self.vars[a]] = Some(a);
+        self.positions[self.vars[b]] = Some(b);
+    }
+}
@@ -0,0 +1,295 @@
+use super::{Lit, Solver};
+use crate::census::pattern::Pattern;
+use crate::data::{Rule, Transform};
+
+// One generation of a rectangle of cells, each a variable that's true if the cell's
+// alive. Cells outside it are dead.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub top: isize,
+    pub left: isize,
+    pub height: usize,
+    pub width: usize,
+    cells: Vec<Lit>,
+}
+
+impl Layer {
+    // A rectangle of cells that could each be alive or dead
+    pub fn free(
+        solver: &mut Solver,
+        (top, left): (isize, isize),
+        (height, width): (usize, usize),
+    ) -> Self {
+        let cells = (0..height * width).map(|_| solver.new_var()).collect();
+        Self {
+            top,
+            left,
+            height,
+            width,
+            cells,
+        }
+    }
+
+    pub fn get(&self, (i, j): (isize, isize)) -> Option<Lit> {
+        let row = usize::try_from(i - self.top)
+            .ok()
+            .filter(|&row| row < self.height)?;
+        let column = usize::try_from(j - self.left)
+            .ok()
+            .filter(|&column| column < self.width)?;
+        Some(self.cells[row * self.width + column])
+    }
+
+    pub fn coords(&self) -> impl Iterator<Item = (isize, isize)> + '_ {
+        let rows = self.top..self.top + signed(self.height);
+        rows.flat_map(move |i| (self.left..self.left + signed(self.width)).map(move |j| (i, j)))
+    }
+
+    // The next generation, which reaches one cell further out on every side
+    pub fn grow(&self, solver: &mut Solver, rule: Rule) -> Self {
+        self.next(solver, rule, 1)
+    }
+
+    // The next generation of the cells whose neighbours are all in this layer
+    pub fn shrink(&self, solver: &mut Solver, rule: Rule) -> Self {
+        self.next(solver, rule, -1)
+    }
+
+    // Requires the cells to be alive exactly where the pattern is, with the pattern's top
+    // left corner at (top, left)
+    pub fn require(&self, solver: &mut Solver, pattern: &Pattern, (top, left): (isize, isize)) {
+        for (i, j) in self.coords() {
+            let (row, column) = (i - top, j - left);
+            let alive = usize::try_from(row).is_ok_and(|row| {
+                usize::try_from(column)
+                    .is_ok_and(|column| pattern.cells().binary_search(&(row, column)).is_ok())
+            });
+            if let Some(cell) = self.get((i, j)) {
+                solver.add_clause(&[if alive { cell } else { !cell }]);
+            }
+        }
+    }
+
+    // Requires at least one cell to be alive
+    pub fn require_alive(&self, solver: &mut Solver) {
+        solver.add_clause(&self.cells);
+    }
+
+    // Requires the cells to look the same after a rotation or reflection. Transforms that
+    // swap rows and columns need the layer to be square.
+    pub fn require_symmetry(&self, solver: &mut Solver, transform: Transform) {
+        let size = (signed(self.height), signed(self.width));
+        for (i, j) in self.coords() {
+            let (row, column) = transform.apply_within((i - self.top, j - self.left), size);
+            let mirrored = (row + self.top, column + self.left);
+            if let (Some(cell), Some(other)) = (self.get((i, j)), self.get(mirrored)) {
+                solver.add_clause(&[!cell, other]);
+            }
+        }
+    }
+
+    // Requires the cells to be the same as another layer's, moved by `shift`
+    pub fn require_same(&self, solver: &mut Solver, other: &Self, shift: (isize, isize)) {
+        for pair in self.pairs(other, shift) {
+            match pair {
+                (Some(a), Some(b)) => {
+                    solver.add_clause(&[!a, b]);
+                    solver.add_clause(&[a, !b]);
+                }
+                (Some(cell), None) | (None, Some(cell)) => solver.add_clause(&[!cell]),
+                (None, None) => {}
+            }
+        }
+    }
+
+    // Requires at least one cell to be different from another layer's, moved by `shift`
+    pub fn require_different(&self, solver: &mut Solver, other: &Self, shift: (isize, isize)) {
+        let mut differences = Vec::new();
+        for pair in self.pairs(other, shift) {
+            match pair {
+                (Some(a), Some(b)) => {
+                    let different = solver.new_var();
+                    solver.add_clause(&[!different, a, b]);
+                    solver.add_clause(&[!different, !a, !b]);
+                    differences.push(different);
+                }
+                (Some(cell), None) | (None, Some(cell)) => differences.push(cell),
+                (None, None) => {}
+            }
+        }
+        solver.add_clause(&differences);
+    }
+
+    // Each of this layer's cells with the one from the other layer that would be in the
+    // same place after moving it by `shift`, along with any of the other layer's cells
+    // that wouldn't have a partner
+    fn pairs(&self, other: &Self, (di, dj): (isize, isize)) -> Vec<(Option<Lit>, Option<Lit>)> {
+        let mut pairs: Vec<_> = self
+            .coords()
+            .map(|(i, j)| (self.get((i, j)), other.get((i - di, j - dj))))
+            .collect();
+        pairs.extend(
+            other
+                .coords()
+                .filter(|&(i, j)| self.get((i + di, j + dj)).is_none())
+                .map(|coord| (None, other.get(coord))),
+        );
+        pairs
+    }
+
+    // The live cells in the solver's last solution
+    pub fn pattern(&self, solver: &Solver) -> Pattern {
+        Pattern::new(
+            self.coords()
+                .filter(|&coord| self.get(coord).is_some_and(|cell| solver.model_value(cell))),
+        )
+    }
+
+    fn next(&self, solver: &mut Solver, rule: Rule, grow_by: isize) -> Self {
+        let size = |length: usize| usize::try_from(signed(length) + grow_by * 2).unwrap_or(0);
+        let next = Self::free(
+            solver,
+            (self.top - grow_by, self.left - grow_by),
+            (size(self.height), size(self.width)),
+        );
+        let coords: Vec<(isize, isize)> = next.coords().collect();
+        for (n, (i, j)) in coords.into_iter().enumerate() {
+            let neighbours: Vec<Lit> = NEIGHBOURS
+                .iter()
+                .filter_map(|&(di, dj)| self.get((i + di, j + dj)))
+                .collect();
+            let cell = next.cells[n];
+            transition(solver, rule, self.get((i, j)), &neighbours, cell);
+        }
+        next
+    }
+}
+
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// Adds clauses making `next` alive exactly when the rule says a cell with these
+// neighbours should be. A cell that's None is dead, and so are any missing neighbours.
+pub fn transition(
+    solver: &mut Solver,
+    rule: Rule,
+    cell: Option<Lit>,
+    neighbours: &[Lit],
+    next: Lit,
+) {
+    let at_least = count(solver, neighbours);
+    for n in 0..=8 {
+        // Exactly n neighbours means at least n but not at least n + 1
+        let mut exactly = Vec::new();
+        if n > 0 {
+            match at_least.get(n - 1) {
+                Some(&lit) => exactly.push(!lit),
+                None => continue,
+            }
+        }
+        if let Some(&lit) = at_least.get(n) {
+            exactly.push(lit);
+        }
+        let mut add = |alive: bool, becomes: bool| {
+            let mut clause = exactly.clone();
+            match (cell, alive) {
+                (Some(cell), true) => clause.push(!cell),
+                (Some(cell), false) => clause.push(cell),
+                (None, true) => return,
+                (None, false) => {}
+            }
+            clause.push(if becomes { next } else { !next });
+            solver.add_clause(&clause);
+        };
+        add(true, rule.survives(n));
+        add(false, rule.births(n));
+    }
+}
+
+// Variables where the nth is true exactly when at least n + 1 of the inputs are, built by
+// merging counts of each half of the inputs
+fn count(solver: &mut Solver, inputs: &[Lit]) -> Vec<Lit> {
+    if inputs.len() <= 1 {
+        return inputs.to_vec();
+    }
+    let (first, second) = inputs.split_at(inputs.len() / 2);
+    let (first, second) = (count(solver, first), count(solver, second));
+    let sum: Vec<Lit> = (0..inputs.len()).map(|_| solver.new_var()).collect();
+    for a in 0..=first.len() {
+        for b in 0..=second.len() {
+            // a of the first and b of the second make at least a + b
+            if a + b > 0 {
+                let mut clause = vec![sum[a + b - 1]];
+                clause.extend(a.checked_sub(1).map(|a| !first[a]));
+                clause.extend(b.checked_sub(1).map(|b| !second[b]));
+                solver.add_clause(&clause);
+            }
+            // Fewer than a + 1 of the first and b + 1 of the second make fewer than
+            // a + b + 1
+            if a + b < inputs.len() {
+                let mut clause = vec![!sum[a + b]];
+                clause.extend(first.get(a).copied());
+                clause.extend(second.get(b).copied());
+                solver.add_clause(&clause);
+            }
+        }
+    }
+    sum
+}
+
+#[allow(clippy::cast_possible_wrap)]
+pub const fn signed(length: usize) -> isize {
+    length as isize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Solution;
+    use super::*;
+
+    #[test]
+    fn test_count() {
+        for inputs in 0..=8 {
+            for alive in 0..=inputs {
+                let mut solver = Solver::new();
+                let lits: Vec<Lit> = (0..inputs).map(|_| solver.new_var()).collect();
+                let at_least = count(&mut solver, &lits);
+                for (n, &lit) in lits.iter().enumerate() {
+                    solver.add_clause(&[if n < alive { lit } else { !lit }]);
+                }
+                assert_eq!(solver.solve(u64::MAX), Solution::Satisfiable);
+                for (n, &lit) in at_least.iter().enumerate() {
+                    assert_eq!(solver.model_value(lit), n < alive);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_layers_follow_the_rule() {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        let start: Pattern = "oo.o/.o../oooo/o..o".parse().unwrap();
+        let mut solver = Solver::new();
+        let first = Layer::free(&mut solver, (0, 0), (4, 4));
+        first.require(&mut solver, &start, (0, 0));
+        let second = first.grow(&mut solver, rule);
+        let third = second.grow(&mut solver, rule);
+        assert_eq!(solver.solve(u64::MAX), Solution::Satisfiable);
+
+        let mut grid = start.to_grid(4, rule);
+        grid.advance();
+        grid.advance();
+        assert_eq!(third.pattern(&solver), Pattern::from_grid(&grid));
+        assert_eq!((third.height, third.width), (8, 8));
+        let inside = third.shrink(&mut solver, rule);
+        assert_eq!((inside.top, inside.left, inside.height), (-1, -1, 6));
+    }
+}
@@ -0,0 +1,490 @@
+mod heap;
+pub(crate) mod life;
+pub mod periodic;
+pub mod predecessor;
+
+use self::heap::Heap;
+use std::ops::Not;
+
+pub const DEFAULT_MAX_CONFLICTS: u64 = 1_000_000;
+
+// Conflicts between restarts are this many times the next number in the Luby sequence
+const RESTART_INTERVAL: u64 = 100;
+const ACTIVITY_DECAY: f64 = 0.95;
+// Activities get scaled down once any of them gets this big, to keep them finite
+const ACTIVITY_LIMIT: f64 = 1e100;
+
+/// A variable or its negation
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub struct Lit(u32);
+
+impl Lit {
+    #[allow(clippy::cast_possible_truncation)]
+    const fn new(var: usize, negated: bool) -> Self {
+        Self(((var as u32) << 1) | negated as u32)
+    }
+
+    const fn var(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    const fn negated(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Not for Lit {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(self.0 ^ 1)
+    }
+}
+
+/// What a solver found out about its clauses
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Solution {
+    /// There's a way of satisfying them all, which `Solver::model_value` reads back
+    Satisfiable,
+    /// There's no way of satisfying them all
+    Unsatisfiable,
+    /// It ran into too many conflicts before finding out
+    Unknown,
+}
+
+/// A conflict driven clause learning SAT solver, which decides whether there's a way of
+/// making every one of a set of clauses true, where each clause is a list of literals at
+/// least one of which has to be true.
+///
+/// Fine for the few thousand variables that Life searches over small regions need, with
+/// watched literals, learnt clauses, activity based decisions, saved phases and restarts,
+/// but none of the clause database cleaning of bigger solvers.
+#[derive(Debug, Clone, Default)]
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    // Clauses with each literal as one of their first two, by literal
+    watches: Vec<Vec<usize>>,
+    // By variable
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    phases: Vec<bool>,
+    activity: Vec<f64>,
+    activity_increment: f64,
+    heap: Heap,
+    trail: Vec<Lit>,
+    // Where each decision level starts on the trail
+    trail_levels: Vec<usize>,
+    propagated: usize,
+    model: Vec<bool>,
+    // False once the clauses are known to be unsatisfiable
+    consistent: bool,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self {
+            activity_increment: 1.0,
+            consistent: true,
+            ..Self::default()
+        }
+    }
+
+    /// A new variable, which is false until something says otherwise
+    pub fn new_var(&mut self) -> Lit {
+        let var = self.values.len();
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.phases.push(false);
+        self.activity.push(0.0);
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.heap.insert(var, &self.activity);
+        Lit::new(var, false)
+    }
+
+    pub fn add_clause(&mut self, lits: &[Lit]) {
+        self.backtrack(0);
+        if !self.consistent {
+            return;
+        }
+        let mut lits = lits.to_vec();
+        lits.sort_unstable();
+        lits.dedup();
+        let always_true = lits.windows(2).any(|pair| pair[0].var() == pair[1].var())
+            || lits.iter().any(|&lit| self.value(lit) == Some(true));
+        if always_true {
+            return;
+        }
+        lits.retain(|&lit| self.value(lit).is_none());
+        match lits.len() {
+            0 => self.consistent = false,
+            1 => {
+                self.assign(lits[0], None);
+                self.consistent = self.propagate().is_none();
+            }
+            _ => {
+                self.add_watched(lits);
+            }
+        }
+    }
+
+    /// Looks for a way of satisfying every clause, giving up after `max_conflicts`
+    pub fn solve(&mut self, max_conflicts: u64) -> Solution {
+        self.backtrack(0);
+        if !self.consistent || self.propagate().is_some() {
+            self.consistent = false;
+            return Solution::Unsatisfiable;
+        }
+        let mut conflicts = 0;
+        let mut restarts = 0;
+        let mut next_restart = RESTART_INTERVAL * luby(restarts);
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.trail_levels.is_empty() {
+                    self.consistent = false;
+                    return Solution::Unsatisfiable;
+                }
+                conflicts += 1;
+                if conflicts > max_conflicts {
+                    self.backtrack(0);
+                    return Solution::Unknown;
+                }
+                let (learnt, level) = self.analyse(conflict);
+                self.backtrack(level);
+                if learnt.len() == 1 {
+                    self.assign(learnt[0], None);
+                } else {
+                    let first = learnt[0];
+                    let clause = self.add_watched(learnt);
+                    self.assign(first, Some(clause));
+                }
+                self.decay_activity();
+                next_restart = next_restart.saturating_sub(1);
+            } else if next_restart == 0 {
+                restarts += 1;
+                next_restart = RESTART_INTERVAL * luby(restarts);
+                self.backtrack(0);
+            } else if let Some(var) = self.next_decision() {
+                self.trail_levels.push(self.trail.len());
+                self.assign(Lit::new(var, !self.phases[var]), None);
+            } else {
+                self.model = self
+                    .values
+                    .iter()
+                    .map(|&value| value == Some(true))
+                    .collect();
+                self.backtrack(0);
+                return Solution::Satisfiable;
+            }
+        }
+    }
+
+    /// Whether a literal was true in the last satisfying assignment found
+    pub fn model_value(&self, lit: Lit) -> bool {
+        self.model.get(lit.var()).copied().unwrap_or(false) != lit.negated()
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        value(&self.values, lit)
+    }
+
+    fn add_watched(&mut self, lits: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[lits[0].index()].push(index);
+        self.watches[lits[1].index()].push(index);
+        self.clauses.push(lits);
+        index
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var();
+        self.values[var] = Some(!lit.negated());
+        self.levels[var] = self.trail_levels.len();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    // Assigns everything the clauses force, returning a clause that's become false if
+    // there is one
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let falsified = !self.trail[self.propagated];
+            self.propagated += 1;
+            let mut watchers = std::mem::take(&mut self.watches[falsified.index()]);
+            let mut kept = 0;
+            let mut conflict = None;
+            for n in 0..watchers.len() {
+                let index = watchers[n];
+                if conflict.is_some() {
+                    watchers[kept] = index;
+                    kept += 1;
+                    continue;
+                }
+                let clause = &mut self.clauses[index];
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let other = clause[0];
+                if value(&self.values, other) == Some(true) {
+                    watchers[kept] = index;
+                    kept += 1;
+                    continue;
+                }
+                let values = &self.values;
+                let replacement = clause[2..]
+                    .iter()
+                    .position(|&lit| value(values, lit) != Some(false));
+                if let Some(position) = replacement {
+                    clause.swap(1, position + 2);
+                    let watched = clause[1];
+                    self.watches[watched.index()].push(index);
+                    continue;
+                }
+                watchers[kept] = index;
+                kept += 1;
+                match self.value(other) {
+                    Some(false) => conflict = Some(index),
+                    _ => self.assign(other, Some(index)),
+                }
+            }
+            watchers.truncate(kept);
+            self.watches[falsified.index()] = watchers;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    // Works back from a conflict to the first literal at the current level that led to
+    // it on its own, returning a clause that rules the conflict out and the level to go
+    // back to for that clause to force something
+    fn analyse(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let level = self.trail_levels.len();
+        let mut seen = vec![false; self.values.len()];
+        let mut learnt = vec![Lit(0)];
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut position = self.trail.len();
+        let asserting = loop {
+            // The first literal of a reason is the one it forced, which is already seen
+            let skip = usize::from(clause != conflict);
+            for n in skip..self.clauses[clause].len() {
+                let lit = self.clauses[clause][n];
+                let var = lit.var();
+                if seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+                seen[var] = true;
+                self.bump_activity(var);
+                if self.levels[var] == level {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+            loop {
+                position -= 1;
+                if seen[self.trail[position].var()] {
+                    break;
+                }
+            }
+            let lit = self.trail[position];
+            pending -= 1;
+            if pending == 0 {
+                break lit;
+            }
+            clause = self.reasons[lit.var()].unwrap_or(conflict);
+        };
+        learnt[0] = !asserting;
+
+        // Leave out literals that are implied by the others anyway
+        let redundant: Vec<bool> = learnt
+            .iter()
+            .map(|lit| {
+                self.reasons[lit.var()].is_some_and(|reason| {
+                    self.clauses[reason][1..]
+                        .iter()
+                        .all(|other| seen[other.var()] || self.levels[other.var()] == 0)
+                })
+            })
+            .collect();
+        let mut n = 0;
+        learnt.retain(|_| {
+            n += 1;
+            n == 1 || !redundant[n - 1]
+        });
+
+        let mut back_to = 0;
+        if learnt.len() > 1 {
+            let (deepest, _) = learnt
+                .iter()
+                .enumerate()
+                .skip(1)
+                .max_by_key(|(_, lit)| self.levels[lit.var()])
+                .unwrap_or((1, &learnt[1]));
+            learnt.swap(1, deepest);
+            back_to = self.levels[learnt[1].var()];
+        }
+        (learnt, back_to)
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.trail_levels.len() <= level {
+            return;
+        }
+        let start = self.trail_levels[level];
+        for lit in self.trail.drain(start..) {
+            let var = lit.var();
+            self.values[var] = None;
+            self.reasons[var] = None;
+            self.phases[var] = !lit.negated();
+            self.heap.insert(var, &self.activity);
+        }
+        self.trail_levels.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    fn next_decision(&mut self) -> Option<usize> {
+        while let Some(var) = self.heap.pop(&self.activity) {
+            if self.values[var].is_none() {
+                return Some(var);
+            }
+        }
+        None
+    }
+
+    fn bump_activity(&mut self, var: usize) {
+        self.activity[var] += self.activity_increment;
+        if self.activity[var] > ACTIVITY_LIMIT {
+            for activity in &mut self.activity {
+                *activity /= ACTIVITY_LIMIT;
+            }
+            self.activity_increment /= ACTIVITY_LIMIT;
+        }
+        self.heap.increased(var, &self.activity);
+    }
+
+    fn decay_activity(&mut self) {
+        self.activity_increment /= ACTIVITY_DECAY;
+    }
+}
+
+// Whether a literal is true, given the values of the variables so far
+fn value(values: &[Option<bool>], lit: Lit) -> Option<bool> {
+    values[lit.var()].map(|value| value != lit.negated())
+}
+
+// The nth number of the sequence 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ...
+fn luby(mut n: u64) -> u64 {
+    let (mut size, mut power) = (1, 0);
+    while size < n + 1 {
+        power += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != n {
+        size = (size - 1) >> 1;
+        power -= 1;
+        n %= size;
+    }
+    1 << power
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every pigeon in a hole, with no two pigeons in the same hole
+    fn pigeons(pigeons: usize, holes: usize) -> (Solver, Vec<Vec<Lit>>) {
+        let mut solver = Solver::new();
+        let placed: Vec<Vec<Lit>> = (0..pigeons)
+            .map(|_| (0..holes).map(|_| solver.new_var()).collect())
+            .collect();
+        for pigeon in &placed {
+            solver.add_clause(pigeon);
+        }
+        for hole in 0..holes {
+            for (n, a) in placed.iter().enumerate() {
+                for b in &placed[n + 1..] {
+                    solver.add_clause(&[!a[hole], !b[hole]]);
+                }
+            }
+        }
+        (solver, placed)
+    }
+
+    #[test]
+    fn test_luby() {
+        let sequence: Vec<u64> = (0..15).map(luby).collect();
+        assert_eq!(sequence, [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn test_pigeonholes() {
+        let (mut solver, placed) = pigeons(5, 5);
+        assert_eq!(solver.solve(u64::MAX), Solution::Satisfiable);
+        for hole in 0..5 {
+            let filled = placed
+                .iter()
+                .filter(|p| solver.model_value(p[hole]))
+                .count();
+            assert!(filled <= 1);
+        }
+        let (mut solver, _) = pigeons(6, 5);
+        assert_eq!(solver.solve(u64::MAX), Solution::Unsatisfiable);
+        let (mut solver, _) = pigeons(9, 8);
+        assert_eq!(solver.solve(10), Solution::Unknown);
+    }
+
+    #[test]
+    fn test_random_clauses() {
+        // 3-SAT below the threshold where they stop being satisfiable
+        let mut random = 12345_u64;
+        let mut next = |below: u64| {
+            random ^= random << 13;
+            random ^= random >> 7;
+            random ^= random << 17;
+            random % below
+        };
+        let mut solver = Solver::new();
+        let vars: Vec<Lit> = (0..100).map(|_| solver.new_var()).collect();
+        let clauses: Vec<Vec<Lit>> = (0..300)
+            .map(|_| {
+                (0..3)
+                    .map(|_| {
+                        let var = vars[usize::try_from(next(100)).unwrap()];
+                        if next(2) == 0 {
+                            var
+                        } else {
+                            !var
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        for clause in &clauses {
+            solver.add_clause(clause);
+        }
+        assert_eq!(solver.solve(u64::MAX), Solution::Satisfiable);
+        assert!(clauses
+            .iter()
+            .all(|clause| clause.iter().any(|&lit| solver.model_value(lit))));
+    }
+
+    #[test]
+    fn test_contradictions() {
+        let mut solver = Solver::new();
+        let a = solver.new_var();
+        solver.add_clause(&[a, a, !a]);
+        assert_eq!(solver.solve(u64::MAX), Solution::Satisfiable);
+        solver.add_clause(&[a]);
+        solver.add_clause(&[!a]);
+        assert_eq!(solver.solve(u64::MAX), Solution::Unsatisfiable);
+    }
+}
@@ -0,0 +1,206 @@
+use super::life::Layer;
+use super::{Solution, Solver, DEFAULT_MAX_CONFLICTS};
+use crate::census::pattern::Pattern;
+use crate::data::{Rule, Transform};
+
+pub const DEFAULT_SIZE: usize = 8;
+
+/// What a search for an oscillator or spaceship found
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Outcome {
+    Found(Pattern),
+    /// There isn't one that fits
+    Impossible,
+    /// The solver gave up before finding out
+    GaveUp,
+}
+
+/// Looks for oscillators, or spaceships when there's a displacement, that come back to
+/// the same shape after exactly `period` generations and no fewer, by writing out each
+/// generation as clauses for a SAT solver, much like Logic Life Search.
+///
+/// The first generation has to fit in a box of the given size and can be made to look
+/// the same after a rotation or reflection, which narrows the search down a lot. Later
+/// generations can spread out past the box.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Periodic {
+    rule: Rule,
+    period: u64,
+    displacement: (isize, isize),
+    height: usize,
+    width: usize,
+    symmetry: Transform,
+    max_conflicts: u64,
+}
+
+impl Periodic {
+    pub fn new(period: u64) -> Self {
+        Self {
+            rule: Rule::CONWAY,
+            period: period.max(1),
+            displacement: (0, 0),
+            height: DEFAULT_SIZE,
+            width: DEFAULT_SIZE,
+            symmetry: Transform::Identity,
+            max_conflicts: DEFAULT_MAX_CONFLICTS,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Rows and columns moved each period, positive going down and right, or nothing for
+    /// an oscillator
+    #[must_use]
+    pub const fn with_displacement(mut self, displacement: (isize, isize)) -> Self {
+        self.displacement = displacement;
+        self
+    }
+
+    /// Rows and columns in the first generation. Symmetries that swap rows and columns
+    /// make the box a square with the larger of the two.
+    #[must_use]
+    pub const fn with_size(mut self, height: usize, width: usize) -> Self {
+        self.height = height;
+        self.width = width;
+        self
+    }
+
+    /// A rotation or reflection the first generation has to look the same after
+    #[must_use]
+    pub const fn with_symmetry(mut self, symmetry: Transform) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    /// Conflicts the solver can run into before giving up
+    #[must_use]
+    pub const fn with_max_conflicts(mut self, max_conflicts: u64) -> Self {
+        self.max_conflicts = max_conflicts;
+        self
+    }
+
+    pub fn find(&self) -> Outcome {
+        let mut size = (self.height, self.width);
+        if self.symmetry.swaps_axes() {
+            let longer = self.height.max(self.width);
+            size = (longer, longer);
+        }
+        let mut solver = Solver::new();
+        let first = Layer::free(&mut solver, (0, 0), size);
+        first.require_alive(&mut solver);
+        first.require_symmetry(&mut solver, self.symmetry);
+        let mut layers = vec![first];
+        for generation in 0..self.period {
+            let next = layers[layers.len() - 1].grow(&mut solver, self.rule);
+            let (down, across) = self.displacement;
+            let generation = generation + 1;
+            if generation == self.period {
+                next.require_same(&mut solver, &layers[0], self.displacement);
+            } else if self.period.is_multiple_of(generation) {
+                // A shorter cycle would only repeat a whole number of times in a period
+                // if the displacement divides up evenly between them
+                let repeats = isize::try_from(self.period / generation).unwrap_or(isize::MAX);
+                if down % repeats == 0 && across % repeats == 0 {
+                    let shift = (down / repeats, across / repeats);
+                    next.require_different(&mut solver, &layers[0], shift);
+                }
+            }
+            layers.push(next);
+        }
+        match solver.solve(self.max_conflicts) {
+            Solution::Satisfiable => Outcome::Found(layers[0].pattern(&solver)),
+            Solution::Unsatisfiable => Outcome::Impossible,
+            Solution::Unknown => Outcome::GaveUp,
+        }
+    }
+
+    /// Runs a pattern with `Grid::advance` to check it comes back moved by the
+    /// displacement after the period, and not before
+    pub fn check(&self, pattern: &Pattern) -> bool {
+        let (down, across) = self.displacement;
+        let speed = down.unsigned_abs().max(across.unsigned_abs()) + 1;
+        let period = usize::try_from(self.period).unwrap_or(usize::MAX);
+        let mut grid = pattern
+            .to_grid(period.saturating_mul(speed) + 2, self.rule)
+            .with_history(period + 1);
+        for _ in 0..self.period {
+            grid.advance();
+        }
+        grid.periodicity().is_some_and(|periodicity| {
+            (
+                periodicity.start,
+                periodicity.period,
+                periodicity.displacement,
+            ) == (0, self.period, self.displacement)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(search: &Periodic) -> Pattern {
+        match search.find() {
+            Outcome::Found(pattern) => {
+                assert!(search.check(&pattern), "{pattern} doesn't repeat");
+                pattern
+            }
+            outcome => panic!("{outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn test_oscillators() {
+        let blinker = found(&Periodic::new(2).with_size(3, 3));
+        assert_eq!(blinker.population(), 3);
+        let still_life = found(&Periodic::new(1).with_size(3, 3));
+        assert!(!still_life.is_empty());
+        // Nothing in a 3 by 3 box oscillates with period 3
+        assert_eq!(Periodic::new(3).with_size(3, 3).find(), Outcome::Impossible);
+    }
+
+    #[test]
+    fn test_spaceships() {
+        let glider = found(&Periodic::new(4).with_displacement((1, -1)).with_size(3, 3));
+        assert_eq!(glider.population(), 5);
+        let highlife: Rule = "B36/S23".parse().unwrap();
+        let search = Periodic::new(4)
+            .with_rule(highlife)
+            .with_displacement((-1, -1))
+            .with_size(3, 3);
+        assert_eq!(found(&search).population(), 5);
+        // Nothing moves as fast as a cell a generation
+        let too_fast = Periodic::new(2).with_displacement((0, 2)).with_size(4, 4);
+        assert_eq!(too_fast.find(), Outcome::Impossible);
+    }
+
+    #[test]
+    fn test_symmetry() {
+        for (size, symmetry) in [(4, Transform::Rotate180), (6, Transform::Rotate90)] {
+            let search = Periodic::new(2)
+                .with_size(size, size)
+                .with_symmetry(symmetry);
+            let oscillator = found(&search);
+            assert_eq!(oscillator.transform(symmetry), oscillator);
+        }
+        // A glider only matches its reflection after moving on a couple of generations
+        let glider = Periodic::new(4)
+            .with_displacement((1, 1))
+            .with_size(3, 3)
+            .with_symmetry(Transform::Transpose);
+        assert_eq!(glider.find(), Outcome::Impossible);
+    }
+
+    #[test]
+    fn test_check() {
+        let glider: Pattern = ".o./..o/ooo".parse().unwrap();
+        assert!(Periodic::new(4).with_displacement((1, 1)).check(&glider));
+        assert!(!Periodic::new(4).with_displacement((1, -1)).check(&glider));
+        assert!(!Periodic::new(8).with_displacement((2, 2)).check(&glider));
+    }
+}
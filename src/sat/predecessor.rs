@@ -0,0 +1,184 @@
+use super::life::{signed, Layer};
+use super::{Solution, Solver, DEFAULT_MAX_CONFLICTS};
+use crate::census::pattern::Pattern;
+use crate::data::Rule;
+
+pub const DEFAULT_MARGIN: usize = 2;
+
+/// What a search for a predecessor found
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Predecessor {
+    /// A pattern that turns into the target, with nothing else alive
+    Found(Pattern),
+    /// There isn't one in the space searched
+    Impossible,
+    /// The solver gave up before finding out
+    GaveUp,
+}
+
+/// Looks for patterns that turn into a target pattern after a number of generations, by
+/// writing out how every cell near it changes as clauses for a SAT solver.
+///
+/// Predecessors have to fit in the target's bounding box with `margin` cells added on each
+/// side, and everything else on the board is dead.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Predecessors {
+    target: Pattern,
+    rule: Rule,
+    generations: u64,
+    margin: usize,
+    max_conflicts: u64,
+}
+
+impl Predecessors {
+    pub const fn new(target: Pattern) -> Self {
+        Self {
+            target,
+            rule: Rule::CONWAY,
+            generations: 1,
+            margin: DEFAULT_MARGIN,
+            max_conflicts: DEFAULT_MAX_CONFLICTS,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Generations from the predecessor to the target
+    #[must_use]
+    pub const fn with_generations(mut self, generations: u64) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    /// Cells a predecessor can stick out past the target on each side
+    #[must_use]
+    pub const fn with_margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Conflicts the solver can run into before giving up
+    #[must_use]
+    pub const fn with_max_conflicts(mut self, max_conflicts: u64) -> Self {
+        self.max_conflicts = max_conflicts;
+        self
+    }
+
+    /// Finds a pattern that turns into exactly the target, which can be checked by
+    /// running it for `generations` on an empty board
+    pub fn find(&self) -> Predecessor {
+        let mut solver = Solver::new();
+        let margin = signed(self.margin);
+        let first = Layer::free(
+            &mut solver,
+            (-margin, -margin),
+            (
+                self.target.height() + self.margin * 2,
+                self.target.width() + self.margin * 2,
+            ),
+        );
+        let mut last = first.clone();
+        for _ in 0..self.generations {
+            last = last.grow(&mut solver, self.rule);
+        }
+        last.require(&mut solver, &self.target, (0, 0));
+        match solver.solve(self.max_conflicts) {
+            Solution::Satisfiable => Predecessor::Found(first.pattern(&solver)),
+            Solution::Unsatisfiable => Predecessor::Impossible,
+            Solution::Unknown => Predecessor::GaveUp,
+        }
+    }
+
+    /// Runs a pattern on an empty board with `Grid::advance` to check it turns into the
+    /// target after `generations`, with nothing else left alive
+    pub fn check(&self, predecessor: &Pattern) -> bool {
+        // Far enough from the edges that nothing wraps around
+        let margin = usize::try_from(self.generations)
+            .unwrap_or(usize::MAX)
+            .saturating_add(2);
+        let mut grid = predecessor.to_grid(margin, self.rule);
+        for _ in 0..self.generations {
+            grid.advance();
+        }
+        Pattern::from_grid(&grid) == self.target
+    }
+
+    /// Whether the target is a Garden of Eden, which can't be reached from anything. This
+    /// checks whether any cells at all around the target's bounding box, not just dead
+    /// ones, could make exactly that box in one generation. `Some(true)` is a proof, while
+    /// `Some(false)` means the target can at least turn up in the middle of something
+    /// bigger. `None` means the solver gave up.
+    pub fn is_garden_of_eden(&self) -> Option<bool> {
+        let mut solver = Solver::new();
+        let (height, width) = (self.target.height(), self.target.width());
+        // Only the cells next to the box can affect what's in it
+        let parent = Layer::free(&mut solver, (-1, -1), (height + 2, width + 2));
+        let child = parent.shrink(&mut solver, self.rule);
+        child.require(&mut solver, &self.target, (0, 0));
+        match solver.solve(self.max_conflicts) {
+            Solution::Satisfiable => Some(false),
+            Solution::Unsatisfiable => Some(true),
+            Solution::Unknown => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs a pattern on its own, far enough from the grid's edges not to wrap around
+    fn run(pattern: &Pattern, rule: Rule, generations: u64) -> Pattern {
+        let margin = usize::try_from(generations).unwrap() + 2;
+        let mut grid = pattern.to_grid(margin, rule);
+        for _ in 0..generations {
+            grid.advance();
+        }
+        Pattern::from_grid(&grid)
+    }
+
+    #[test]
+    fn test_predecessors_turn_into_the_target() {
+        let glider: Pattern = ".o./..o/ooo".parse().unwrap();
+        let lone_cell: Pattern = "o".parse().unwrap();
+        let tub: Pattern = ".o./o.o/.o.".parse().unwrap();
+        for (target, generations) in [(&glider, 1), (&lone_cell, 1), (&tub, 2)] {
+            let search = Predecessors::new(target.clone()).with_generations(generations);
+            let Predecessor::Found(predecessor) = search.find() else {
+                panic!("no predecessor for {target}");
+            };
+            assert_eq!(&run(&predecessor, Rule::default(), generations), target);
+            assert!(search.check(&predecessor));
+        }
+        // A glider is a different shape a generation later
+        assert!(!Predecessors::new(glider.clone()).check(&glider));
+    }
+
+    #[test]
+    fn test_other_rules() {
+        let highlife: Rule = "B36/S23".parse().unwrap();
+        let target: Pattern = "oo.o/o..o".parse().unwrap();
+        let Predecessor::Found(predecessor) =
+            Predecessors::new(target.clone()).with_rule(highlife).find()
+        else {
+            panic!("no predecessor");
+        };
+        assert_eq!(run(&predecessor, highlife, 1), target);
+    }
+
+    #[test]
+    fn test_impossible() {
+        // Nothing is ever born and nothing survives
+        let search = Predecessors::new("o".parse().unwrap())
+            .with_rule("B/S".parse().unwrap())
+            .with_margin(3);
+        assert_eq!(search.find(), Predecessor::Impossible);
+        assert_eq!(search.is_garden_of_eden(), Some(true));
+        let block = Predecessors::new("oo/oo".parse().unwrap());
+        assert_eq!(block.is_garden_of_eden(), Some(false));
+    }
+}
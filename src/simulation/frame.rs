@@ -0,0 +1,258 @@
+use crate::data::{Ages, Cell, Grid, GridIdx, NEVER_ALIVE};
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::Arc;
+
+/// An immutable snapshot of a grid at a given generation
+#[derive(Debug, Clone)]
+pub struct Frame {
+    generation: u64,
+    population: usize,
+    alive: Vec<bool>,
+    ages: Vec<u16>,
+}
+
+impl Frame {
+    /// Takes a snapshot of the given grid and the ages of its cells
+    pub fn new(grid: &Grid, ages: &Ages) -> Self {
+        let mut frame = Self {
+            generation: 0,
+            population: 0,
+            alive: Vec::with_capacity(grid.area()),
+            ages: Vec::with_capacity(grid.area()),
+        };
+        frame.capture(grid, ages);
+        frame
+    }
+
+    /// Overwrites this frame with the current state of the grid, reusing
+    /// the existing allocation where possible
+    pub fn capture(&mut self, grid: &Grid, ages: &Ages) {
+        self.generation = grid.generation();
+        self.ages.clear();
+        self.ages.extend_from_slice(ages.as_slice());
+        self.alive.clear();
+        self.alive.extend(
+            (0..grid.area()).map(|idx| grid.get_idx(&GridIdx(idx)).is_some_and(Cell::alive)),
+        );
+        self.population = grid.population();
+    }
+
+    /// The generation of the grid this frame was captured from
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Number of live cells in this frame
+    pub const fn population(&self) -> usize {
+        self.population
+    }
+
+    /// Whether the cell at the given flattened index was alive in this frame
+    pub fn alive(&self, &GridIdx(idx): &GridIdx) -> bool {
+        self.alive.get(idx).copied().unwrap_or(false)
+    }
+
+    /// How long the cell at the given flattened index had been in its current state,
+    /// see `Ages`
+    pub fn age(&self, &GridIdx(idx): &GridIdx) -> u16 {
+        self.ages.get(idx).copied().unwrap_or(NEVER_ALIVE)
+    }
+}
+
+/// Creates a connected publisher and receiver pair, seeded with a snapshot of the grid.
+///
+/// The publisher is meant to live on the simulation thread and the receiver on the render
+/// thread. Neither side ever blocks the other: frames are handed over by swapping pointers,
+/// and the receiver always picks up the newest published frame, skipping any it missed.
+pub fn channel(grid: &Grid, ages: &Ages) -> (FramePublisher, FrameReceiver) {
+    let exchange = Arc::new(Exchange {
+        latest: Slot::empty(),
+        spare: Slot::empty(),
+    });
+    let publisher = FramePublisher {
+        exchange: exchange.clone(),
+        back: None,
+    };
+    let receiver = FrameReceiver {
+        exchange,
+        front: Box::new(Frame::new(grid, ages)),
+        dropped: 0,
+    };
+    (publisher, receiver)
+}
+
+/// Publishing half of a frame channel
+pub struct FramePublisher {
+    exchange: Arc<Exchange>,
+    // Frame we will write the next snapshot into
+    back: Option<Box<Frame>>,
+}
+
+impl FramePublisher {
+    /// Publishes a snapshot of the grid, replacing any frame the receiver has not
+    /// picked up yet.
+    pub fn publish(&mut self, grid: &Grid, ages: &Ages) {
+        let mut frame = self
+            .back
+            .take()
+            .or_else(|| self.exchange.spare.take())
+            .unwrap_or_else(|| Box::new(Frame::new(grid, ages)));
+        frame.capture(grid, ages);
+        // Whatever was still waiting in the slot never got rendered, so it becomes our
+        // next back buffer.
+        self.back = self.exchange.latest.swap(Some(frame));
+    }
+}
+
+/// Receiving half of a frame channel
+pub struct FrameReceiver {
+    exchange: Arc<Exchange>,
+    // Frame currently being read from
+    front: Box<Frame>,
+    dropped: u64,
+}
+
+impl FrameReceiver {
+    /// Returns the newest published frame, or the last one returned if nothing new has
+    /// been published since.
+    pub fn latest(&mut self) -> &Frame {
+        if let Some(frame) = self.exchange.latest.take() {
+            self.dropped += frame.generation.saturating_sub(self.front.generation + 1);
+            let previous = mem::replace(&mut self.front, frame);
+            // Hand the old frame back so the publisher doesn't need to allocate
+            drop(self.exchange.spare.swap(Some(previous)));
+        }
+        &self.front
+    }
+
+    /// Number of generations that were published but never picked up by this receiver
+    pub const fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+struct Exchange {
+    // Newest frame that has not been picked up by the receiver yet
+    latest: Slot,
+    // Frame the receiver is done with, waiting to be reused by the publisher
+    spare: Slot,
+}
+
+// Holds at most one boxed frame. Ownership of the frame moves with the pointer, so
+// whichever side swaps a frame out of the slot owns it exclusively.
+struct Slot(AtomicPtr<Frame>);
+
+impl Slot {
+    const fn empty() -> Self {
+        Self(AtomicPtr::new(ptr::null_mut()))
+    }
+
+    fn swap(&self, frame: Option<Box<Frame>>) -> Option<Box<Frame>> {
+        let new = frame.map_or(ptr::null_mut(), Box::into_raw);
+        let old = self.0.swap(new, Ordering::AcqRel);
+        if old.is_null() {
+            None
+        } else {
+            // SAFETY: every non-null pointer stored in a slot came from `Box::into_raw`,
+            // and the atomic swap guarantees only one caller gets it back out.
+            Some(unsafe { Box::from_raw(old) })
+        }
+    }
+
+    fn take(&self) -> Option<Box<Frame>> {
+        self.swap(None)
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        drop(self.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_new() {
+        let grid = Grid::new(4, 3);
+        let ages = Ages::new(&grid);
+        let frame = Frame::new(&grid, &ages);
+        assert_eq!(frame.generation(), 0);
+        for idx in 0..grid.area() {
+            let grid_idx = GridIdx(idx);
+            assert_eq!(
+                frame.alive(&grid_idx),
+                grid.get_idx(&grid_idx).unwrap().alive()
+            );
+            assert_eq!(frame.age(&grid_idx), ages.get_idx(&grid_idx).unwrap());
+        }
+        assert!(!frame.alive(&GridIdx(grid.area())));
+        assert_eq!(frame.age(&GridIdx(grid.area())), NEVER_ALIVE);
+        let population = (0..grid.area())
+            .filter(|&idx| frame.alive(&GridIdx(idx)))
+            .count();
+        assert_eq!(frame.population(), population);
+    }
+
+    #[test]
+    fn test_receiver_without_publish() {
+        let grid = Grid::new(4, 3);
+        let (_, mut receiver) = channel(&grid, &Ages::new(&grid));
+        assert_eq!(receiver.latest().generation(), 0);
+        assert_eq!(receiver.latest().generation(), 0);
+        assert_eq!(receiver.dropped(), 0);
+    }
+
+    #[test]
+    fn test_receiver_picks_newest() {
+        let mut grid = Grid::new(4, 3);
+        let mut ages = Ages::new(&grid);
+        let (mut publisher, mut receiver) = channel(&grid, &ages);
+        grid.advance();
+        ages.update(&grid);
+        publisher.publish(&grid, &ages);
+        assert_eq!(receiver.latest().generation(), 1);
+        assert_eq!(receiver.dropped(), 0);
+        for _ in 0..5 {
+            grid.advance();
+            ages.update(&grid);
+            publisher.publish(&grid, &ages);
+        }
+        let frame = receiver.latest();
+        assert_eq!(frame.generation(), 6);
+        for idx in 0..grid.area() {
+            let grid_idx = GridIdx(idx);
+            assert_eq!(
+                frame.alive(&grid_idx),
+                grid.get_idx(&grid_idx).unwrap().alive()
+            );
+        }
+        assert_eq!(receiver.dropped(), 4);
+    }
+
+    #[test]
+    fn test_publish_across_threads() {
+        let mut grid = Grid::new(20, 20);
+        let mut ages = Ages::new(&grid);
+        let (mut publisher, mut receiver) = channel(&grid, &ages);
+        let handle = std::thread::spawn(move || {
+            for _ in 0..1000 {
+                grid.advance();
+                ages.update(&grid);
+                publisher.publish(&grid, &ages);
+            }
+        });
+        let mut last_seen = 0;
+        while last_seen < 1000 {
+            let generation = receiver.latest().generation();
+            assert!(generation >= last_seen);
+            last_seen = generation;
+        }
+        handle.join().unwrap();
+        assert!(receiver.dropped() < 1000);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::data::{Ages, Grid};
+use std::error::Error;
+
+/// Gets to see every generation of a headless run, e.g. to export some of them
+pub trait Observer {
+    /// Called with the starting grid, and again after every advance
+    #[allow(clippy::missing_errors_doc)]
+    fn observe(&mut self, grid: &Grid, ages: &Ages) -> Result<(), Box<dyn Error>>;
+
+    /// Called once the run is over, after the last generation has been observed
+    #[allow(clippy::missing_errors_doc)]
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Advances a grid for a set number of generations as fast as possible, without a
+/// window, handing each generation to a number of observers
+pub struct Headless {
+    grid: Grid,
+    ages: Ages,
+    generations: u64,
+    observers: Vec<Box<dyn Observer>>,
+    stop_when_periodic: bool,
+}
+
+impl Headless {
+    /// Runs the grid until it reaches the given generation
+    pub fn new(grid: Grid, generations: u64) -> Self {
+        let ages = Ages::new(&grid);
+        Self {
+            grid,
+            ages,
+            generations,
+            observers: Vec::new(),
+            stop_when_periodic: false,
+        }
+    }
+
+    /// Stops early once the grid starts repeating itself, looking back up to the given
+    /// number of generations for a match
+    #[must_use]
+    pub fn with_stop_when_periodic(mut self, history: usize) -> Self {
+        self.grid = self.grid.with_history(history);
+        self.stop_when_periodic = true;
+        self
+    }
+
+    /// Adds an observer, which sees generations after any added before it
+    #[must_use]
+    pub fn with_observer(mut self, observer: Box<dyn Observer>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Runs to the last generation, or until the grid becomes periodic if asked to,
+    /// returning the grid as it ended up. Stops at the first error from an observer, but
+    /// still lets every observer finish, so nothing is left half written.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn run(mut self) -> Result<Grid, Box<dyn Error>> {
+        let mut result = self.observe_all();
+        for observer in &mut self.observers {
+            let finished = observer.finish();
+            result = result.and(finished);
+        }
+        result.map(|()| self.grid)
+    }
+
+    fn observe_all(&mut self) -> Result<(), Box<dyn Error>> {
+        loop {
+            for observer in &mut self.observers {
+                observer.observe(&self.grid, &self.ages)?;
+            }
+            if self.grid.generation() >= self.generations
+                || (self.stop_when_periodic && self.grid.periodicity().is_some())
+            {
+                break;
+            }
+            self.grid.advance();
+            self.ages.update(&self.grid);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Status;
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder {
+        seen: Arc<Mutex<Vec<u64>>>,
+        fail_at: Option<u64>,
+    }
+
+    impl Observer for Recorder {
+        fn observe(&mut self, grid: &Grid, _: &Ages) -> Result<(), Box<dyn Error>> {
+            if self.fail_at == Some(grid.generation()) {
+                return Err("failed".into());
+            }
+            self.seen.lock().unwrap().push(grid.generation());
+            Ok(())
+        }
+
+        fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+            self.seen.lock().unwrap().push(u64::MAX);
+            Ok(())
+        }
+    }
+
+    fn recorder(fail_at: Option<u64>) -> (Box<dyn Observer>, Arc<Mutex<Vec<u64>>>) {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Recorder {
+            seen: seen.clone(),
+            fail_at,
+        };
+        (Box::new(recorder), seen)
+    }
+
+    #[test]
+    fn test_run() {
+        let (first, first_seen) = recorder(None);
+        let (second, second_seen) = recorder(None);
+        let grid = Headless::new(Grid::new(8, 8), 3)
+            .with_observer(first)
+            .with_observer(second)
+            .run()
+            .unwrap();
+        assert_eq!(grid.generation(), 3);
+        for seen in [first_seen, second_seen] {
+            assert_eq!(*seen.lock().unwrap(), vec![0, 1, 2, 3, u64::MAX]);
+        }
+    }
+
+    #[test]
+    fn test_run_stops_on_error() {
+        let (first, first_seen) = recorder(None);
+        let (failing, failing_seen) = recorder(Some(2));
+        assert!(Headless::new(Grid::new(8, 8), 5)
+            .with_observer(first)
+            .with_observer(failing)
+            .run()
+            .is_err());
+        // Both still get to finish
+        assert_eq!(*first_seen.lock().unwrap(), vec![0, 1, 2, u64::MAX]);
+        assert_eq!(*failing_seen.lock().unwrap(), vec![0, 1, u64::MAX]);
+    }
+
+    #[test]
+    fn test_run_stops_when_periodic() {
+        // A blinker, which repeats itself every other generation
+        let grid = Grid::from_fn(5, 5, |coord| {
+            if coord.i == 2 && (1..=3).contains(&coord.j) {
+                Status::Alive
+            } else {
+                Status::Dead
+            }
+        });
+        let (observer, seen) = recorder(None);
+        let grid = Headless::new(grid, 100)
+            .with_stop_when_periodic(8)
+            .with_observer(observer)
+            .run()
+            .unwrap();
+        assert_eq!(grid.generation(), 2);
+        assert_eq!(grid.periodicity().map(|p| p.period), Some(2));
+        assert_eq!(*seen.lock().unwrap(), vec![0, 1, 2, u64::MAX]);
+    }
+}
@@ -0,0 +1,410 @@
+pub mod frame;
+pub mod headless;
+pub mod replay;
+pub mod scheduler;
+pub mod timeline;
+
+pub use self::frame::*;
+pub use self::replay::*;
+pub use self::scheduler::*;
+pub use self::timeline::*;
+
+use super::census::pattern::Pattern;
+use super::data::{Ages, BoundingBox, Grid, Status, Transform};
+use super::export::runlog::RunLogWriter;
+use std::any::Any;
+use std::error::Error;
+use std::io::Write;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Messages that can be sent to a running simulation
+#[derive(Debug)]
+pub enum Command {
+    Edit(Edit),
+    /// Goes back to just before the last edit, see `Timeline::undo`, and pauses
+    Undo,
+    /// Goes back to just after the last edit undone, and pauses
+    Redo,
+    /// Goes back a generation and pauses
+    StepBack,
+    /// Moves on a generation and pauses
+    StepForward,
+    /// Pauses, or carries on from wherever the grid is
+    TogglePause,
+    /// Goes to a generation of a replay, see `Replay`, and pauses
+    Seek(u64),
+    Stop,
+}
+
+/// Changes to the cells of a grid, made between generations
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Edit {
+    /// Kills every cell in a region
+    Clear(BoundingBox),
+    /// Brings each cell in a region to life or kills it at random
+    Randomise(BoundingBox),
+    /// Rotates or reflects a region, see `Grid::transform`
+    Transform(BoundingBox, Transform),
+    /// Brings a pattern's cells to life with its top left corner at the given row and
+    /// column, which can be past the grid's edges. Cells that are already alive stay that way.
+    Paste(Pattern, (isize, isize)),
+}
+
+impl Edit {
+    pub fn apply(&self, grid: &mut Grid) {
+        match self {
+            Self::Clear(region) => grid.clear(region),
+            Self::Randomise(region) => grid.randomise(region),
+            Self::Transform(region, transform) => grid.transform(region, *transform),
+            Self::Paste(pattern, (top, left)) => {
+                let (height, width) = (grid.height(), grid.width());
+                for &(i, j) in pattern.cells() {
+                    let position = (top + signed(i), left + signed(j));
+                    if let Some(coord) = grid.topology().wrap(position, height, width) {
+                        grid.set(&coord, Status::Alive);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Advances a grid at a fixed rate, publishing each update as a `Frame`
+pub struct Simulation {
+    grid: Grid,
+    ages: Ages,
+    timeline: Timeline,
+    log: Option<RunLogWriter<Box<dyn Write + Send>>>,
+    frames: FramePublisher,
+    updates_per_second: u16,
+}
+
+impl Simulation {
+    /// Creates a simulation along with the receiver its frames get published to
+    pub fn new(grid: Grid, updates_per_second: u16) -> (Self, FrameReceiver) {
+        let ages = Ages::new(&grid);
+        let (frames, receiver) = channel(&grid, &ages);
+        let timeline = Timeline::new(&grid);
+        let simulation = Self {
+            grid,
+            ages,
+            timeline,
+            log: None,
+            frames,
+            updates_per_second,
+        };
+        (simulation, receiver)
+    }
+
+    /// Bytes of memory to keep earlier states in, for undoing and stepping back
+    #[must_use]
+    pub fn with_rewind_budget(mut self, budget: usize) -> Self {
+        self.timeline = self.timeline.with_budget(budget);
+        self
+    }
+
+    /// Writes every state the grid goes through to a run log, edits included
+    #[must_use]
+    pub fn with_log(mut self, writer: Box<dyn Write + Send>) -> Self {
+        self.log = Some(RunLogWriter::new(writer));
+        self
+    }
+
+    /// The grid as it is before the simulation starts
+    pub const fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Starts running the simulation on its own thread
+    #[allow(clippy::missing_errors_doc)]
+    pub fn spawn(self) -> Result<SimulationHandle, Box<dyn Error>> {
+        spawn(self)
+    }
+
+    fn log(&mut self) -> Result<(), ThreadError> {
+        if let Some(log) = &mut self.log {
+            log.write(&self.grid)?;
+        }
+        Ok(())
+    }
+}
+
+impl Runner for Simulation {
+    fn updates_per_second(&self) -> u16 {
+        self.updates_per_second
+    }
+
+    fn start(&mut self) -> Result<(), ThreadError> {
+        self.log()
+    }
+
+    fn step(&mut self) -> Result<bool, ThreadError> {
+        self.grid.advance();
+        self.ages.update(&self.grid);
+        self.timeline.record_step(&self.grid);
+        self.log()?;
+        Ok(true)
+    }
+
+    fn carry_out(&mut self, command: Command) -> Result<bool, ThreadError> {
+        let pauses = !matches!(command, Command::Edit(_));
+        let changed = match command {
+            Command::Edit(edit) => {
+                edit.apply(&mut self.grid);
+                self.timeline.record_edit(&self.grid);
+                true
+            }
+            Command::Undo => self.timeline.undo(&mut self.grid),
+            Command::Redo => self.timeline.redo(&mut self.grid),
+            Command::StepBack => self.timeline.step_back(&mut self.grid),
+            Command::StepForward => {
+                self.step()?;
+                self.publish();
+                return Ok(true);
+            }
+            Command::Seek(_) | Command::TogglePause | Command::Stop => return Ok(false),
+        };
+        if changed {
+            self.ages.update_edited(&self.grid);
+            self.log()?;
+            self.publish();
+        }
+        Ok(pauses)
+    }
+
+    fn publish(&mut self) {
+        self.frames.publish(&self.grid, &self.ages);
+    }
+
+    fn finish(&mut self) -> Result<(), ThreadError> {
+        if let Some(log) = &mut self.log {
+            log.flush()?;
+        }
+        Ok(())
+    }
+}
+
+type ThreadError = Box<dyn Error + Send + Sync>;
+
+// Something that runs on the simulation thread, moving on a step at every tick
+trait Runner: Send + 'static {
+    fn updates_per_second(&self) -> u16;
+
+    // Called once the thread's started, before anything else
+    fn start(&mut self) -> Result<(), ThreadError> {
+        Ok(())
+    }
+
+    // Moves on a step, returning false if there's nowhere further to go
+    fn step(&mut self) -> Result<bool, ThreadError>;
+
+    // Carries out a command that changes the grid, then publishes it. Returns whether to
+    // pause afterwards.
+    fn carry_out(&mut self, command: Command) -> Result<bool, ThreadError>;
+
+    fn publish(&mut self);
+
+    // Called when asked to stop
+    fn finish(&mut self) -> Result<(), ThreadError> {
+        Ok(())
+    }
+}
+
+fn spawn<R: Runner>(runner: R) -> Result<SimulationHandle, Box<dyn Error>> {
+    let (commands, receiver) = mpsc::channel();
+    let thread = thread::Builder::new()
+        .name("simulation".to_string())
+        .spawn(move || run(runner, &receiver))?;
+    Ok(SimulationHandle { commands, thread })
+}
+
+// Sleeps until the next tick is due, or for as long as it takes while paused, waking early
+// if a command comes in. Returns once asked to stop, once the handle has gone away, or at
+// the first error.
+fn run<R: Runner>(mut runner: R, commands: &Receiver<Command>) -> Result<(), ThreadError> {
+    runner.start()?;
+    let mut scheduler = Scheduler::new(runner.updates_per_second(), Instant::now());
+    let mut paused = false;
+    loop {
+        let command = if paused {
+            commands.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            commands.recv_timeout(scheduler.wait_time(Instant::now()))
+        };
+        match command {
+            Ok(Command::Stop) | Err(RecvTimeoutError::Disconnected) => return runner.finish(),
+            Ok(Command::TogglePause) => paused = !paused,
+            Ok(command) => paused |= runner.carry_out(command)?,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if paused {
+            // So carrying on doesn't try to catch up on the time spent paused
+            scheduler = Scheduler::new(runner.updates_per_second(), Instant::now());
+            continue;
+        }
+        let ticks = scheduler.poll(Instant::now());
+        if ticks.run > 0 {
+            for _ in 0..ticks.run {
+                if !runner.step()? {
+                    paused = true;
+                    break;
+                }
+            }
+            runner.publish();
+        }
+    }
+}
+
+/// Handle to a simulation running on another thread
+pub struct SimulationHandle {
+    commands: Sender<Command>,
+    thread: JoinHandle<Result<(), ThreadError>>,
+}
+
+impl SimulationHandle {
+    /// Whether the simulation thread has exited, either because it was stopped or
+    /// because it ran into an error
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Changes the grid's cells before its next generation
+    pub fn edit(&self, edit: Edit) {
+        self.send(Command::Edit(edit));
+    }
+
+    /// Sends a command to the simulation thread, see `Command`
+    pub fn send(&self, command: Command) {
+        // If the thread is already gone, `is_finished` and `stop` say so
+        let _ = self.commands.send(command);
+    }
+
+    /// Stops the simulation and waits for its thread to exit
+    #[allow(clippy::missing_errors_doc)]
+    pub fn stop(self) -> Result<(), Box<dyn Error>> {
+        // If the thread is already gone, joining below tells us why
+        let _ = self.commands.send(Command::Stop);
+        match self.thread.join() {
+            Ok(result) => result.map_err(|err| err as Box<dyn Error>),
+            Err(panic) => {
+                Err(format!("Simulation thread panicked: {}", panic_message(&*panic)).into())
+            }
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+const fn signed(n: usize) -> isize {
+    n as isize
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown error")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_simulation_publishes_and_stops() {
+        let grid = Grid::new(10, 10);
+        let (simulation, mut frames) = Simulation::new(grid, 1000);
+        let handle = simulation.spawn().unwrap();
+        let started = Instant::now();
+        while frames.latest().generation() == 0 {
+            assert!(started.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(!handle.is_finished());
+        handle.stop().unwrap();
+    }
+
+    #[test]
+    fn test_edits() {
+        let mut grid = Grid::empty(4, 4);
+        let glider: Pattern = ".o./..o/ooo".parse().unwrap();
+        Edit::Paste(glider.clone(), (-1, 2)).apply(&mut grid);
+        // The top row wraps around to the bottom, and the left column to the right
+        let region = BoundingBox {
+            min_i: 0,
+            min_j: 0,
+            max_i: 1,
+            max_j: 3,
+        };
+        assert_eq!(
+            Pattern::from_region(&grid, &region),
+            "o.../o.oo".parse().unwrap()
+        );
+        Edit::Transform(region, Transform::FlipRows).apply(&mut grid);
+        assert_eq!(
+            Pattern::from_region(&grid, &region),
+            "o.oo/o...".parse().unwrap()
+        );
+        Edit::Clear(region).apply(&mut grid);
+        assert_eq!(grid.population(), 1);
+        Edit::Randomise(region).apply(&mut grid);
+        assert_eq!(grid.population(), grid.live_cells().count());
+    }
+
+    #[test]
+    fn test_edits_are_published() {
+        let grid = Grid::empty(3, 3);
+        // Slow enough that the pasted cells are still there when we look for them
+        let (simulation, mut frames) = Simulation::new(grid, 1);
+        let handle = simulation.spawn().unwrap();
+        let everything = BoundingBox {
+            min_i: 0,
+            min_j: 0,
+            max_i: 2,
+            max_j: 2,
+        };
+        handle.edit(Edit::Paste("ooo".parse().unwrap(), (1, 0)));
+        let started = Instant::now();
+        while frames.latest().population() == 0 {
+            assert!(started.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+        handle.edit(Edit::Clear(everything));
+        handle.stop().unwrap();
+    }
+
+    #[test]
+    fn test_pausing_and_stepping() {
+        let (simulation, mut frames) = Simulation::new(Grid::new(10, 10), 1000);
+        let handle = simulation.spawn().unwrap();
+        let mut wait_for = |done: &dyn Fn(u64) -> bool| {
+            let started = Instant::now();
+            while !done(frames.latest().generation()) {
+                assert!(started.elapsed() < Duration::from_secs(10));
+                thread::sleep(Duration::from_millis(1));
+            }
+            frames.latest().generation()
+        };
+        wait_for(&|generation| generation > 0);
+        handle.send(Command::TogglePause);
+        // Nothing gets published once it's paused
+        thread::sleep(Duration::from_millis(50));
+        let paused = wait_for(&|_| true);
+        handle.send(Command::StepBack);
+        wait_for(&|generation| generation == paused - 1);
+        handle.send(Command::StepForward);
+        wait_for(&|generation| generation == paused);
+        handle.stop().unwrap();
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let panic = thread::spawn(|| panic!("boom")).join().unwrap_err();
+        assert_eq!(panic_message(&*panic), "boom");
+        let panic = thread::spawn(|| panic!("{}", 42)).join().unwrap_err();
+        assert_eq!(panic_message(&*panic), "42");
+    }
+}
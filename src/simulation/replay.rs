@@ -0,0 +1,140 @@
+use super::{
+    channel, spawn, Command, FramePublisher, FrameReceiver, Runner, SimulationHandle, ThreadError,
+};
+use crate::data::{Ages, Grid};
+use crate::export::runlog::RunLog;
+use std::error::Error;
+
+/// Plays back a run log at a fixed rate, publishing each of its frames the way a
+/// `Simulation` publishes generations. It can be paused, stepped through and sought with
+/// the same commands, but not edited.
+pub struct Replay {
+    log: RunLog,
+    // Frame of the log the grid is on
+    position: usize,
+    cells: Vec<bool>,
+    grid: Grid,
+    ages: Ages,
+    frames: FramePublisher,
+    updates_per_second: u16,
+}
+
+impl Replay {
+    /// Creates a replay of a log, starting from its first frame, along with the receiver
+    /// its frames get published to
+    pub fn new(log: RunLog, updates_per_second: u16) -> (Self, FrameReceiver) {
+        let grid = log.grid(0);
+        let ages = Ages::new(&grid);
+        let (frames, receiver) = channel(&grid, &ages);
+        let replay = Self {
+            cells: log.cells(0),
+            log,
+            position: 0,
+            grid,
+            ages,
+            frames,
+            updates_per_second,
+        };
+        (replay, receiver)
+    }
+
+    /// The grid as it is in the first frame
+    pub const fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Starts playing on its own thread
+    #[allow(clippy::missing_errors_doc)]
+    pub fn spawn(self) -> Result<SimulationHandle, Box<dyn Error>> {
+        spawn(self)
+    }
+
+    // Goes to a frame, working it out from the closest keyframe unless it's the next one
+    fn go_to(&mut self, frame: usize) {
+        let next = frame == self.position + 1;
+        if next {
+            self.log.apply(frame, &mut self.cells);
+        } else {
+            self.cells = self.log.cells(frame);
+        }
+        let generation = self.log.generation(frame);
+        let advanced = next && generation == self.grid.generation() + 1;
+        self.grid.restore(&self.cells, generation);
+        if advanced {
+            self.ages.update(&self.grid);
+        } else {
+            self.ages.update_edited(&self.grid);
+        }
+        self.position = frame;
+    }
+}
+
+impl Runner for Replay {
+    fn updates_per_second(&self) -> u16 {
+        self.updates_per_second
+    }
+
+    fn step(&mut self) -> Result<bool, ThreadError> {
+        if self.position + 1 >= self.log.len() {
+            return Ok(false);
+        }
+        self.go_to(self.position + 1);
+        Ok(true)
+    }
+
+    fn carry_out(&mut self, command: Command) -> Result<bool, ThreadError> {
+        let frame = match command {
+            Command::StepBack => self.position.checked_sub(1),
+            Command::StepForward => Some(self.position + 1).filter(|&n| n < self.log.len()),
+            Command::Seek(generation) => self.log.find(generation),
+            // What's happened has happened, so edits are ignored
+            Command::Edit(_)
+            | Command::Undo
+            | Command::Redo
+            | Command::TogglePause
+            | Command::Stop => return Ok(false),
+        };
+        if let Some(frame) = frame {
+            self.go_to(frame);
+            self.publish();
+        }
+        Ok(true)
+    }
+
+    fn publish(&mut self) {
+        self.frames.publish(&self.grid, &self.ages);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::runlog::RunLogWriter;
+
+    #[test]
+    fn test_replay() {
+        let mut bytes = Vec::new();
+        let mut grid = Grid::new(10, 10);
+        let mut writer = RunLogWriter::new(&mut bytes);
+        let mut populations = Vec::new();
+        for _ in 0..=100 {
+            writer.write(&grid).unwrap();
+            populations.push(grid.population());
+            grid.advance();
+        }
+        let log = RunLog::from_bytes(bytes).unwrap();
+        let (mut replay, _) = Replay::new(log, 30);
+        assert_eq!(replay.grid().generation(), 0);
+        assert!(replay.carry_out(Command::Seek(60)).unwrap());
+        assert_eq!(replay.grid().generation(), 60);
+        assert!(replay.carry_out(Command::StepBack).unwrap());
+        assert!(replay.step().unwrap());
+        assert!(replay.step().unwrap());
+        assert_eq!(replay.grid().generation(), 61);
+        assert_eq!(replay.grid().population(), populations[61]);
+        replay.carry_out(Command::Seek(100)).unwrap();
+        assert!(!replay.step().unwrap());
+        assert!(!replay.carry_out(Command::Undo).unwrap());
+        assert_eq!(replay.grid().generation(), 100);
+    }
+}
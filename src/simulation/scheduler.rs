@@ -0,0 +1,161 @@
+use std::time::{Duration, Instant};
+
+/// Maximum number of overdue ticks that get run back to back when the simulation falls
+/// behind. Anything beyond this is dropped, and the schedule restarts from the current time.
+pub const MAX_CATCH_UP_TICKS: u32 = 4;
+
+/// Ticks that were due when the scheduler was polled
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Ticks {
+    /// Number of ticks that should be run now
+    pub run: u32,
+    /// Number of ticks that were skipped because we fell too far behind
+    pub dropped: u64,
+}
+
+/// Keeps track of when the next update to the grid is due.
+///
+/// Deadlines are fixed steps from each other rather than from whenever the last update
+/// finished, so the update rate does not drift when advancing takes a while.
+#[derive(Debug)]
+pub struct Scheduler {
+    period: Duration,
+    next_deadline: Instant,
+    dropped: u64,
+}
+
+impl Scheduler {
+    /// Creates a scheduler that ticks the given number of times per second, with the first
+    /// tick due one period after `start`
+    pub fn new(updates_per_second: u16, start: Instant) -> Self {
+        let period = Duration::from_secs(1) / u32::from(updates_per_second.max(1));
+        Self {
+            period,
+            next_deadline: start + period,
+            dropped: 0,
+        }
+    }
+
+    /// Time between ticks
+    pub const fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// How long to sleep from `now` until the next tick is due
+    pub fn wait_time(&self, now: Instant) -> Duration {
+        self.next_deadline.saturating_duration_since(now)
+    }
+
+    /// Total number of ticks dropped so far
+    pub const fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Works out how many ticks are due at `now` and moves the deadline along.
+    ///
+    /// Up to `MAX_CATCH_UP_TICKS` overdue ticks are run to catch up; if more than that are
+    /// owed, the rest are dropped and the next deadline is one period from `now`.
+    pub fn poll(&mut self, now: Instant) -> Ticks {
+        if now < self.next_deadline {
+            return Ticks { run: 0, dropped: 0 };
+        }
+        let overdue = now.duration_since(self.next_deadline);
+        let owed = 1 + overdue.as_nanos() / self.period.as_nanos().max(1);
+        if owed > u128::from(MAX_CATCH_UP_TICKS) {
+            let dropped = u64::try_from(owed - u128::from(MAX_CATCH_UP_TICKS)).unwrap_or(u64::MAX);
+            self.dropped = self.dropped.saturating_add(dropped);
+            self.next_deadline = now + self.period;
+            Ticks {
+                run: MAX_CATCH_UP_TICKS,
+                dropped,
+            }
+        } else {
+            // owed is at most MAX_CATCH_UP_TICKS here, so this can't truncate
+            let run = u32::try_from(owed).unwrap_or(MAX_CATCH_UP_TICKS);
+            self.next_deadline += self.period * run;
+            Ticks { run, dropped: 0 }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_period() {
+        let scheduler = Scheduler::new(4, Instant::now());
+        assert_eq!(scheduler.period(), Duration::from_millis(250));
+        let scheduler = Scheduler::new(0, Instant::now());
+        assert_eq!(scheduler.period(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_wait_time() {
+        let start = Instant::now();
+        let scheduler = Scheduler::new(10, start);
+        assert_eq!(scheduler.wait_time(start), Duration::from_millis(100));
+        assert_eq!(
+            scheduler.wait_time(start + Duration::from_millis(40)),
+            Duration::from_millis(60)
+        );
+        assert_eq!(
+            scheduler.wait_time(start + Duration::from_millis(400)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_poll_on_schedule() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(10, start);
+        assert_eq!(
+            scheduler.poll(start + Duration::from_millis(50)),
+            Ticks { run: 0, dropped: 0 }
+        );
+        assert_eq!(
+            scheduler.poll(start + Duration::from_millis(100)),
+            Ticks { run: 1, dropped: 0 }
+        );
+        // Deadlines don't drift when we wake up a little late
+        assert_eq!(
+            scheduler.poll(start + Duration::from_millis(210)),
+            Ticks { run: 1, dropped: 0 }
+        );
+        assert_eq!(
+            scheduler.wait_time(start + Duration::from_millis(210)),
+            Duration::from_millis(90)
+        );
+    }
+
+    #[test]
+    fn test_poll_catches_up() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(10, start);
+        assert_eq!(
+            scheduler.poll(start + Duration::from_millis(350)),
+            Ticks { run: 3, dropped: 0 }
+        );
+        assert_eq!(
+            scheduler.wait_time(start + Duration::from_millis(350)),
+            Duration::from_millis(50)
+        );
+        assert_eq!(scheduler.dropped(), 0);
+    }
+
+    #[test]
+    fn test_poll_drops_ticks_when_too_far_behind() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(10, start);
+        let now = start + Duration::from_millis(1050);
+        assert_eq!(
+            scheduler.poll(now),
+            Ticks {
+                run: MAX_CATCH_UP_TICKS,
+                dropped: 10 - u64::from(MAX_CATCH_UP_TICKS)
+            }
+        );
+        assert_eq!(scheduler.wait_time(now), Duration::from_millis(100));
+        assert_eq!(scheduler.dropped(), 10 - u64::from(MAX_CATCH_UP_TICKS));
+    }
+}
@@ -0,0 +1,372 @@
+use crate::data::{Cell, Grid, GridIdx};
+use std::collections::VecDeque;
+use std::mem;
+
+/// Memory a timeline uses by default before it starts forgetting, in bytes
+pub const DEFAULT_BUDGET: usize = 64 * 1024 * 1024;
+
+// At least every this many states is kept in full, so working out any other state never
+// means replaying more than this many generations
+const KEYFRAME_INTERVAL: usize = 32;
+
+/// What took the grid from the state before to this one
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Change {
+    Step,
+    Edit,
+}
+
+#[derive(Debug)]
+enum Cells {
+    // Every cell, a bit each
+    Keyframe(Vec<u64>),
+    // Indices of the cells that flipped since the state before
+    Delta(Vec<u32>),
+    // Nothing kept, since it's the state before advanced a generation
+    Replay,
+}
+
+#[derive(Debug)]
+struct Entry {
+    generation: u64,
+    change: Change,
+    cells: Cells,
+}
+
+impl Entry {
+    fn size(&self) -> usize {
+        mem::size_of::<Self>()
+            + match &self.cells {
+                Cells::Keyframe(words) => words.len() * mem::size_of::<u64>(),
+                Cells::Delta(flipped) => flipped.len() * mem::size_of::<u32>(),
+                Cells::Replay => 0,
+            }
+    }
+}
+
+/// Every state a grid has been in, for undoing edits and stepping back through
+/// generations.
+///
+/// States are kept as the cells that changed since the one before, with the whole grid
+/// every so often. Once that takes up more than the memory budget, the oldest generations
+/// are whittled down to just the whole grids, with anything in between worked out again
+/// by advancing from the one before when it's needed, and after that they're forgotten
+/// altogether. Edits are never whittled down, since they can't be worked out again.
+#[derive(Debug)]
+pub struct Timeline {
+    budget: usize,
+    used: usize,
+    entries: VecDeque<Entry>,
+    // Index of the entry for the state the grid is in. Anything after it was stepped
+    // back or undone from.
+    cursor: usize,
+    // Live cells in that state
+    current: Vec<bool>,
+    // Entries before this have already been whittled down
+    whittled: usize,
+}
+
+impl Timeline {
+    /// Starts off with the state the grid is in now
+    pub fn new(grid: &Grid) -> Self {
+        let current = alive(grid);
+        let first = Entry {
+            generation: grid.generation(),
+            change: Change::Step,
+            cells: Cells::Keyframe(pack(&current)),
+        };
+        Self {
+            budget: DEFAULT_BUDGET,
+            used: first.size(),
+            entries: VecDeque::from(vec![first]),
+            cursor: 0,
+            current,
+            whittled: 0,
+        }
+    }
+
+    /// Bytes that can be used before older states start getting forgotten
+    #[must_use]
+    pub const fn with_budget(mut self, budget: usize) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Bytes currently in use
+    pub const fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Records that the grid was advanced a generation
+    pub fn record_step(&mut self, grid: &Grid) {
+        // Going forward again after stepping back ends up where it did the first time
+        let next = self.entries.get(self.cursor + 1);
+        if next.is_some_and(|entry| entry.change == Change::Step) {
+            self.cursor += 1;
+            self.current = alive(grid);
+            return;
+        }
+        self.push(grid, Change::Step);
+    }
+
+    /// Records that the grid's cells were edited
+    pub fn record_edit(&mut self, grid: &Grid) {
+        self.push(grid, Change::Edit);
+    }
+
+    /// Takes the grid back to just before the last edit, returning false if there's
+    /// nothing to undo
+    pub fn undo(&mut self, grid: &mut Grid) -> bool {
+        let edit = (1..=self.cursor)
+            .rev()
+            .find(|&n| self.entries[n].change == Change::Edit);
+        edit.is_some_and(|edit| self.go_to(edit - 1, grid))
+    }
+
+    /// Takes the grid to just after the edit that was last undone, returning false if
+    /// there's nothing to redo
+    pub fn redo(&mut self, grid: &mut Grid) -> bool {
+        let edit =
+            (self.cursor + 1..self.entries.len()).find(|&n| self.entries[n].change == Change::Edit);
+        edit.is_some_and(|edit| self.go_to(edit, grid))
+    }
+
+    /// Takes the grid back to the state before the current one, returning false if it's
+    /// been forgotten
+    pub fn step_back(&mut self, grid: &mut Grid) -> bool {
+        self.cursor > 0 && self.go_to(self.cursor - 1, grid)
+    }
+
+    fn push(&mut self, grid: &Grid, change: Change) {
+        // Anything that was stepped back or undone from can't be got back to any more
+        while self.entries.len() > self.cursor + 1 {
+            if let Some(entry) = self.entries.pop_back() {
+                self.used -= entry.size();
+            }
+        }
+        self.whittled = self.whittled.min(self.entries.len());
+        let now = alive(grid);
+        let flipped: Vec<u32> = (0..now.len())
+            .filter(|&idx| now[idx] != self.current[idx])
+            .filter_map(|idx| u32::try_from(idx).ok())
+            .collect();
+        let since_keyframe = self
+            .entries
+            .iter()
+            .rev()
+            .take_while(|entry| !matches!(entry.cells, Cells::Keyframe(_)))
+            .count();
+        let keyframe = pack(&now);
+        let cells = if since_keyframe + 1 >= KEYFRAME_INTERVAL
+            || flipped.len() * mem::size_of::<u32>() >= keyframe.len() * mem::size_of::<u64>()
+        {
+            Cells::Keyframe(keyframe)
+        } else {
+            Cells::Delta(flipped)
+        };
+        let entry = Entry {
+            generation: grid.generation(),
+            change,
+            cells,
+        };
+        self.used += entry.size();
+        self.entries.push_back(entry);
+        self.cursor = self.entries.len() - 1;
+        self.current = now;
+        self.keep_to_budget();
+    }
+
+    fn keep_to_budget(&mut self) {
+        // First forget the changes made by generations, oldest first, since they can be
+        // worked out again
+        while self.used > self.budget && self.whittled < self.entries.len() {
+            let entry = &mut self.entries[self.whittled];
+            if entry.change == Change::Step && matches!(entry.cells, Cells::Delta(_)) {
+                let before = entry.size();
+                entry.cells = Cells::Replay;
+                self.used -= before - entry.size();
+            }
+            self.whittled += 1;
+        }
+        // Then forget the oldest states altogether, up to the next one kept in full
+        while self.used > self.budget {
+            let next_keyframe =
+                (1..=self.cursor).find(|&n| matches!(self.entries[n].cells, Cells::Keyframe(_)));
+            let Some(next_keyframe) = next_keyframe else {
+                return;
+            };
+            for entry in self.entries.drain(..next_keyframe) {
+                self.used -= entry.size();
+            }
+            self.cursor -= next_keyframe;
+            self.whittled = self.whittled.saturating_sub(next_keyframe);
+        }
+    }
+
+    // Puts the grid in the state recorded by an entry, starting from the closest state
+    // kept in full before it and using the grid to replay generations along the way
+    fn go_to(&mut self, index: usize, grid: &mut Grid) -> bool {
+        let Some(start) = (0..=index)
+            .rev()
+            .find(|&n| matches!(self.entries[n].cells, Cells::Keyframe(_)))
+        else {
+            return false;
+        };
+        let mut cells = Vec::new();
+        for n in start..=index {
+            let entry = &self.entries[n];
+            match &entry.cells {
+                Cells::Keyframe(words) => cells = unpack(words, grid.area()),
+                Cells::Delta(flipped) => {
+                    for &idx in flipped {
+                        cells[idx as usize] ^= true;
+                    }
+                }
+                Cells::Replay => {
+                    grid.restore(&cells, entry.generation - 1);
+                    grid.advance();
+                    cells = alive(grid);
+                }
+            }
+        }
+        grid.restore(&cells, self.entries[index].generation);
+        self.cursor = index;
+        self.current = cells;
+        true
+    }
+}
+
+// Whether each cell is alive, in the same order as `Grid::get_idx`
+pub(crate) fn alive(grid: &Grid) -> Vec<bool> {
+    (0..grid.area())
+        .map(|idx| grid.get_idx(&GridIdx(idx)).is_some_and(Cell::alive))
+        .collect()
+}
+
+// A bit for each cell, 64 to a word
+pub(crate) fn pack(alive: &[bool]) -> Vec<u64> {
+    alive
+        .chunks(64)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .filter(|(_, &alive)| alive)
+                .fold(0, |word, (bit, _)| word | 1 << bit)
+        })
+        .collect()
+}
+
+pub(crate) fn unpack(words: &[u64], area: usize) -> Vec<bool> {
+    (0..area)
+        .map(|idx| words[idx / 64] & 1 << (idx % 64) != 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::BoundingBox;
+
+    const EVERYTHING: BoundingBox = BoundingBox {
+        min_i: 0,
+        min_j: 0,
+        max_i: 19,
+        max_j: 19,
+    };
+
+    // Runs a random grid on, keeping every state it goes through
+    fn run(grid: &mut Grid, timeline: &mut Timeline, generations: usize) -> Vec<Vec<bool>> {
+        let mut states = vec![alive(grid)];
+        for _ in 0..generations {
+            grid.advance();
+            timeline.record_step(grid);
+            states.push(alive(grid));
+        }
+        states
+    }
+
+    #[test]
+    fn test_step_back() {
+        let mut grid = Grid::new(20, 20);
+        let mut timeline = Timeline::new(&grid);
+        let mut states = run(&mut grid, &mut timeline, 100);
+        while let Some(state) = states.pop() {
+            assert_eq!(alive(&grid), state);
+            assert_eq!(grid.generation(), states.len() as u64);
+            assert_eq!(timeline.step_back(&mut grid), !states.is_empty());
+        }
+        // Going forward again picks back up the same states
+        grid.advance();
+        timeline.record_step(&grid);
+        assert_eq!(timeline.cursor, 1);
+        assert_eq!(timeline.entries.len(), 101);
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut grid = Grid::new(20, 20);
+        let mut timeline = Timeline::new(&grid);
+        assert!(!timeline.undo(&mut grid));
+        run(&mut grid, &mut timeline, 5);
+        let before = alive(&grid);
+        grid.randomise(&EVERYTHING);
+        timeline.record_edit(&grid);
+        let after = alive(&grid);
+        run(&mut grid, &mut timeline, 5);
+
+        assert!(timeline.undo(&mut grid));
+        assert_eq!((alive(&grid), grid.generation()), (before, 5));
+        assert!(!timeline.undo(&mut grid));
+        assert!(timeline.redo(&mut grid));
+        assert_eq!((alive(&grid), grid.generation()), (after, 5));
+        assert!(!timeline.redo(&mut grid));
+
+        // Doing something else after undoing means there's nothing left to redo
+        assert!(timeline.undo(&mut grid));
+        grid.advance();
+        timeline.record_step(&grid);
+        assert!(!timeline.redo(&mut grid));
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut grid = Grid::new(20, 20);
+        let budget = 16 * 1024;
+        let mut timeline = Timeline::new(&grid).with_budget(budget);
+        let mut states = vec![alive(&grid)];
+        states.extend(run(&mut grid, &mut timeline, 200).into_iter().skip(1));
+        grid.clear(&EVERYTHING);
+        timeline.record_edit(&grid);
+        states.push(alive(&grid));
+        states.extend(run(&mut grid, &mut timeline, 200).into_iter().skip(1));
+        assert!(timeline.used() <= budget);
+        assert!(timeline
+            .entries
+            .iter()
+            .any(|e| matches!(e.cells, Cells::Replay)));
+
+        // Whatever's left can still be stepped back through, replayed or not
+        let mut steps = 0;
+        while timeline.step_back(&mut grid) {
+            steps += 1;
+            let generation = usize::try_from(grid.generation()).unwrap();
+            // The edit doesn't count as a generation
+            let index = if steps > 200 {
+                generation
+            } else {
+                generation + 1
+            };
+            assert_eq!(alive(&grid), states[index], "{steps} steps back");
+        }
+        assert!(steps > 200 && steps < 401, "{steps}");
+    }
+
+    #[test]
+    fn test_pack() {
+        let cells: Vec<bool> = (0..130).map(|n| n % 3 == 0).collect();
+        let packed = pack(&cells);
+        assert_eq!(packed.len(), 3);
+        assert_eq!(unpack(&packed, 130), cells);
+    }
+}
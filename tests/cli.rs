@@ -0,0 +1,98 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+// Writes a plaintext pattern to a file of its own in the temp directory
+fn pattern_file(name: &str, rows: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("gol-cli-{}-{name}.cells", std::process::id()));
+    fs::write(&path, rows).unwrap();
+    path
+}
+
+// Runs gol with the given arguments, returning what it printed
+fn gol(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_gol"))
+        .args(args)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "gol {args:?} failed: {stderr}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_transform_to_nothing() {
+    let block = pattern_file("block", "OO\nOO\n");
+    let dead = pattern_file("dead", "...\n");
+    let (block, dead) = (block.to_str().unwrap(), dead.to_str().unwrap());
+    let difference = [
+        "transform",
+        block,
+        "--with",
+        block,
+        "--combine",
+        "difference",
+        "--crop",
+    ];
+    assert_eq!(gol(&difference), "");
+    let apart = [
+        "transform",
+        block,
+        "--with",
+        block,
+        "--at",
+        "2,2",
+        "--combine",
+        "and",
+        "--crop",
+    ];
+    assert_eq!(gol(&apart), "");
+    assert_eq!(gol(&["transform", dead]), "");
+    assert_eq!(gol(&["transform", dead, "--crop"]), "");
+}
+
+#[test]
+fn test_transform_rotates_whole_pattern() {
+    let row = pattern_file("row", "OOO\n");
+    let row = row.to_str().unwrap();
+    assert_eq!(
+        gol(&["transform", row, "--transform", "rotate90"]),
+        "O\nO\nO\n"
+    );
+    let plane = [
+        "--topology",
+        "plane",
+        "transform",
+        row,
+        "--transform",
+        "transpose",
+        "--crop",
+    ];
+    assert_eq!(gol(&plane), "O\nO\nO\n");
+    let and_back = [
+        "transform",
+        row,
+        "--transform",
+        "rotate90",
+        "--transform",
+        "rotate270",
+    ];
+    assert_eq!(gol(&and_back), "OOO\n");
+}
+
+#[test]
+fn test_stop_when_periodic_needs_history() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gol"))
+        .args([
+            "--headless",
+            "--stop-when-periodic",
+            "--period-history",
+            "0",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("period-history"), "{stderr}");
+}